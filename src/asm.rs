@@ -0,0 +1,645 @@
+// small two-pass 6502 assembler, encoding through the same Instruction tables the cpu decodes
+// with. the syntax is the ca65 subset the disassembler emits:
+//   label:  name = expr  ; comment
+//   .org .byte .word .setcpu
+//   LDA #$10 / $10 / $10,X / a:$10 / ($10,X) / ($10),Y / ($10) / BBR0 $10, label
+// numbers are $hex, %binary, decimal or 'c', expressions are +/- chains of numbers, labels and
+// * (the current address), optionally prefixed with < or > for the low/high byte of the whole
+// expression. zero page is used when the value is already known to fit, forward references
+// assemble as absolute unless forced with z:
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::{
+    cpu::{
+        instructions::{AddressingMode, AddressingMode::*, Instruction, InstructionName as IN},
+        Variant,
+    },
+    memory::memory_bus::Bus,
+};
+
+#[cfg(test)]
+mod tests;
+
+// deep enough for any sane chain of equates, shallow enough to catch a cycle
+const MAX_EQUATE_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    // 1-based source line
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub origin: u16,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    // one per .org, in source order
+    pub segments: Vec<Segment>,
+    pub labels: BTreeMap<String, u16>,
+}
+
+impl Program {
+    // every segment back to back, for snippets that only have one
+    pub fn bytes(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .flat_map(|s| s.bytes.iter().copied())
+            .collect()
+    }
+
+    pub fn label(&self, name: &str) -> Option<u16> {
+        self.labels.get(name).copied()
+    }
+
+    pub fn load(&self, bus: &mut impl Bus) {
+        for segment in &self.segments {
+            for (i, &byte) in segment.bytes.iter().enumerate() {
+                bus.write(segment.origin.wrapping_add(i as u16), byte);
+            }
+        }
+    }
+}
+
+// assemble with the defaults: 2A03 opcodes, starting at $0000
+pub fn assemble(src: &str) -> Result<Program, AsmError> {
+    Assembler::new().assemble(src)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Size {
+    Auto,
+    ZeroPage,
+    Absolute,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Num(i32),
+    Symbol(String),
+    Pc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Whole,
+    Lo,
+    Hi,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Expr {
+    part: Part,
+    terms: Vec<(i32, Term)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(Expr),
+    Direct(Size, Expr),
+    DirectX(Size, Expr),
+    DirectY(Size, Expr),
+    Indirect(Expr),
+    IndirectX(Expr),
+    IndirectY(Expr),
+    // BBR/BBS: zero page address, branch target
+    BitBranch(Expr, Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stmt {
+    Label(String),
+    Org(Expr),
+    Byte(Vec<Expr>),
+    Word(Vec<Expr>),
+    SetCpu(Variant),
+    Ins(IN, Option<u8>, Operand),
+}
+
+pub struct Assembler {
+    origin: u16,
+    variant: Variant,
+}
+
+impl Default for Assembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Assembler {
+            origin: 0,
+            variant: Variant::default(),
+        }
+    }
+
+    // where code goes before the first .org
+    pub fn origin(mut self, origin: u16) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn assemble(&self, src: &str) -> Result<Program, AsmError> {
+        let mut stmts = vec![];
+        let mut equates = BTreeMap::new();
+        for (i, text) in src.lines().enumerate() {
+            let line = i + 1;
+            let err = |message| AsmError { line, message };
+            parse_line(text, &mut stmts, &mut equates, line).map_err(err)?;
+        }
+
+        // pass 1: addresses for every label, and the addressing mode for every instruction
+        let mut labels = BTreeMap::new();
+        let mut chosen = vec![None; stmts.len()];
+        {
+            let mut pc = self.origin;
+            let mut variant = self.variant;
+            for (i, (line, stmt)) in stmts.iter().enumerate() {
+                let err = |message| AsmError {
+                    line: *line,
+                    message,
+                };
+                let syms = Symbols {
+                    labels: &labels,
+                    equates: &equates,
+                };
+                match stmt {
+                    Stmt::Label(name) => {
+                        if labels.contains_key(name) || equates.contains_key(name) {
+                            return Err(err(format!("`{name}` is already defined")));
+                        }
+                        labels.insert(name.clone(), pc);
+                    }
+                    Stmt::Org(expr) => pc = word(syms.eval(expr, pc).map_err(err)?).map_err(err)?,
+                    Stmt::Byte(exprs) => pc = pc.wrapping_add(exprs.len() as u16),
+                    Stmt::Word(exprs) => pc = pc.wrapping_add(exprs.len() as u16 * 2),
+                    Stmt::SetCpu(v) => variant = *v,
+                    Stmt::Ins(name, bit, operand) => {
+                        let ins = choose(variant, *name, *bit, operand, |e| syms.eval(e, pc).ok())
+                            .map_err(err)?;
+                        chosen[i] = Some(ins);
+                        pc = pc.wrapping_add(ins.bytes as u16);
+                    }
+                }
+            }
+        }
+
+        // pass 2: every symbol is known, emit the bytes
+        let syms = Symbols {
+            labels: &labels,
+            equates: &equates,
+        };
+        let mut segments = vec![Segment {
+            origin: self.origin,
+            bytes: vec![],
+        }];
+        let mut pc = self.origin;
+        for (i, (line, stmt)) in stmts.iter().enumerate() {
+            let err = |message| AsmError {
+                line: *line,
+                message,
+            };
+            let out = &mut segments.last_mut().unwrap().bytes;
+            match stmt {
+                Stmt::Label(_) | Stmt::SetCpu(_) => {}
+                Stmt::Org(expr) => {
+                    pc = word(syms.eval(expr, pc).map_err(err)?).map_err(err)?;
+                    if out.is_empty() {
+                        segments.pop();
+                    }
+                    segments.push(Segment {
+                        origin: pc,
+                        bytes: vec![],
+                    });
+                }
+                Stmt::Byte(exprs) => {
+                    for expr in exprs {
+                        out.push(byte(syms.eval(expr, pc).map_err(err)?).map_err(err)?);
+                    }
+                    pc = pc.wrapping_add(exprs.len() as u16);
+                }
+                Stmt::Word(exprs) => {
+                    for expr in exprs {
+                        let val = word(syms.eval(expr, pc).map_err(err)?).map_err(err)?;
+                        out.extend(val.to_le_bytes());
+                    }
+                    pc = pc.wrapping_add(exprs.len() as u16 * 2);
+                }
+                Stmt::Ins(_, _, operand) => {
+                    let ins = chosen[i].unwrap();
+                    out.extend(encode(ins, operand, pc, &syms).map_err(err)?);
+                    pc = pc.wrapping_add(ins.bytes as u16);
+                }
+            }
+        }
+        segments.retain(|s| !s.bytes.is_empty());
+
+        Ok(Program { segments, labels })
+    }
+}
+
+struct Symbols<'a> {
+    labels: &'a BTreeMap<String, u16>,
+    equates: &'a BTreeMap<String, Expr>,
+}
+
+impl Symbols<'_> {
+    fn eval(&self, expr: &Expr, pc: u16) -> Result<i32, String> {
+        self.eval_depth(expr, pc, 0)
+    }
+
+    fn eval_depth(&self, expr: &Expr, pc: u16, depth: usize) -> Result<i32, String> {
+        let mut total = 0;
+        for (sign, term) in &expr.terms {
+            let val = match term {
+                Term::Num(n) => *n,
+                Term::Pc => pc as i32,
+                Term::Symbol(name) => match (self.labels.get(name), self.equates.get(name)) {
+                    (Some(&addr), _) => addr as i32,
+                    (None, Some(_)) if depth >= MAX_EQUATE_DEPTH => {
+                        return Err(format!("`{name}` is defined in terms of itself"))
+                    }
+                    (None, Some(e)) => self.eval_depth(e, pc, depth + 1)?,
+                    (None, None) => return Err(format!("undefined symbol `{name}`")),
+                },
+            };
+            total += sign * val;
+        }
+        Ok(match expr.part {
+            Part::Whole => total,
+            Part::Lo => total & 0xff,
+            Part::Hi => (total >> 8) & 0xff,
+        })
+    }
+}
+
+fn byte(val: i32) -> Result<u8, String> {
+    match val {
+        -128..=255 => Ok(val as u8),
+        _ => Err(format!("${val:X} doesn't fit in a byte")),
+    }
+}
+
+fn word(val: i32) -> Result<u16, String> {
+    match val {
+        0..=0xffff => Ok(val as u16),
+        _ => Err(format!("{val} isn't a valid address")),
+    }
+}
+
+fn lookup(
+    variant: Variant,
+    name: IN,
+    mode: AddressingMode,
+    bit: Option<u8>,
+) -> Option<Instruction> {
+    let mut found: Option<Instruction> = None;
+    for opcode in 0..=255_u8 {
+        let ins = variant.decode(opcode);
+        if ins.name != name || ins.mode != mode || bit.is_some_and(|b| (opcode >> 4) & 7 != b) {
+            continue;
+        }
+        // official encodings win over unofficial copies (SBC #imm is E9, not EB)
+        if found.is_none_or(|f| f.unofficial && !ins.unofficial) {
+            found = Some(ins);
+        }
+    }
+    found
+}
+
+// picks the encoding in pass 1. `value` is the operand's value if it's already known, which
+// decides between zero page and absolute
+fn choose(
+    variant: Variant,
+    name: IN,
+    bit: Option<u8>,
+    operand: &Operand,
+    value: impl Fn(&Expr) -> Option<i32>,
+) -> Result<Instruction, String> {
+    let sized = |size: Size, expr: &Expr, zp: AddressingMode, abs: AddressingMode| match size {
+        Size::ZeroPage => vec![zp],
+        Size::Absolute => vec![abs],
+        Size::Auto if value(expr).is_some_and(|v| (0..=0xff).contains(&v)) => vec![zp, abs],
+        Size::Auto => vec![abs, zp],
+    };
+    let modes = match operand {
+        Operand::None => vec![Implicit, Accumulator],
+        Operand::Accumulator => vec![Accumulator],
+        Operand::Immediate(_) => vec![Immediate],
+        Operand::Direct(_, _) if lookup(variant, name, Relative, bit).is_some() => vec![Relative],
+        Operand::Direct(size, e) => sized(*size, e, ZeroPage, Absolute),
+        Operand::DirectX(size, e) => sized(*size, e, ZeroPageX, AbsoluteX),
+        Operand::DirectY(size, e) => sized(*size, e, ZeroPageY, AbsoluteY),
+        Operand::Indirect(e) => sized(Size::Auto, e, ZeroPageIndirect, Indirect),
+        Operand::IndirectX(e) => sized(Size::Auto, e, IndexedIndirect, AbsoluteIndexedIndirect),
+        Operand::IndirectY(_) => vec![IndirectIndexed],
+        Operand::BitBranch(_, _) => vec![ZeroPageRelative],
+    };
+
+    modes
+        .into_iter()
+        .find_map(|mode| lookup(variant, name, mode, bit))
+        .ok_or_else(|| format!("{name} has no {operand:?} form on the {variant:?}"))
+}
+
+fn encode(ins: Instruction, operand: &Operand, pc: u16, syms: &Symbols) -> Result<Vec<u8>, String> {
+    let branch = |target: &Expr, size: u16| -> Result<u8, String> {
+        let offset = syms.eval(target, pc)? - (pc.wrapping_add(size) as i32);
+        i8::try_from(offset)
+            .map(|o| o as u8)
+            .map_err(|_| format!("branch target is {offset} bytes away"))
+    };
+
+    let mut out = vec![ins.opcode];
+    match (operand, ins.bytes) {
+        // BRK's padding byte
+        (Operand::None, 2) => out.push(0),
+        (Operand::None | Operand::Accumulator, _) => {}
+        (Operand::BitBranch(zp, target), _) => {
+            out.push(byte(syms.eval(zp, pc)?).and_then(zero_page)?);
+            out.push(branch(target, 3)?);
+        }
+        (Operand::Direct(_, target), _) if ins.mode == Relative => out.push(branch(target, 2)?),
+        (
+            Operand::Immediate(e)
+            | Operand::Direct(_, e)
+            | Operand::DirectX(_, e)
+            | Operand::DirectY(_, e)
+            | Operand::Indirect(e)
+            | Operand::IndirectX(e)
+            | Operand::IndirectY(e),
+            bytes,
+        ) => {
+            let val = syms.eval(e, pc)?;
+            match (ins.mode, bytes) {
+                (Immediate, _) => out.push(byte(val)?),
+                (_, 2) => out.push(zero_page(word(val)?)?),
+                _ => out.extend(word(val)?.to_le_bytes()),
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn zero_page<T: Into<u16>>(addr: T) -> Result<u8, String> {
+    let addr = addr.into();
+    u8::try_from(addr).map_err(|_| format!("${addr:04X} isn't on the zero page"))
+}
+
+/* parsing ======================================================== */
+
+fn parse_line(
+    text: &str,
+    stmts: &mut Vec<(usize, Stmt)>,
+    equates: &mut BTreeMap<String, Expr>,
+    line: usize,
+) -> Result<(), String> {
+    let mut rest = strip_comment(text).trim();
+
+    // any number of leading labels
+    while let Some((name, after)) = split_ident(rest) {
+        let after = after.trim_start();
+        if let Some(after) = after.strip_prefix(':') {
+            stmts.push((line, Stmt::Label(name.to_string())));
+            rest = after.trim_start();
+        } else if let Some(value) = after.strip_prefix('=') {
+            if equates
+                .insert(name.to_string(), parse_expr(value)?)
+                .is_some()
+            {
+                return Err(format!("`{name}` is already defined"));
+            }
+            return Ok(());
+        } else {
+            break;
+        }
+    }
+    if rest.is_empty() {
+        return Ok(());
+    }
+
+    let (word, args) = match rest.find(char::is_whitespace) {
+        Some(i) => (&rest[..i], rest[i..].trim()),
+        None => (rest, ""),
+    };
+
+    let stmt = match word.to_ascii_lowercase().as_str() {
+        ".org" => Stmt::Org(parse_expr(args)?),
+        ".byte" | ".byt" => Stmt::Byte(parse_byte_list(args)?),
+        ".word" | ".addr" => {
+            Stmt::Word(split_args(args).map(parse_expr).collect::<Result<_, _>>()?)
+        }
+        ".setcpu" => Stmt::SetCpu(match args.trim_matches('"').to_ascii_uppercase().as_str() {
+            "6502" | "6502X" => Variant::Nmos6502,
+            "65C02" => Variant::Wdc65C02,
+            cpu => return Err(format!("unknown cpu {cpu}")),
+        }),
+        w if w.starts_with('.') => return Err(format!("unknown directive {word}")),
+        _ => {
+            let (name, bit) = parse_mnemonic(word)?;
+            Stmt::Ins(name, bit, parse_operand(args)?)
+        }
+    };
+    stmts.push((line, stmt));
+    Ok(())
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &text[..i],
+            _ => {}
+        }
+    }
+    text
+}
+
+// leading identifier and whatever follows it
+fn split_ident(text: &str) -> Option<(&str, &str)> {
+    let first = text.chars().next()?;
+    if !(first.is_ascii_alphabetic() || first == '_') {
+        return None;
+    }
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    Some((&text[..end], &text[end..]))
+}
+
+// commas outside of quotes
+fn split_args(text: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    text.split(move |c| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ',' && !quoted
+    })
+    .map(str::trim)
+    .filter(|arg| !arg.is_empty())
+}
+
+fn parse_byte_list(args: &str) -> Result<Vec<Expr>, String> {
+    let mut exprs = vec![];
+    for arg in split_args(args) {
+        match arg.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+            Some(s) => exprs.extend(s.bytes().map(|b| Expr {
+                part: Part::Whole,
+                terms: vec![(1, Term::Num(b as i32))],
+            })),
+            None => exprs.push(parse_expr(arg)?),
+        }
+    }
+    Ok(exprs)
+}
+
+// RMB/SMB/BBR/BBS take the bit number as a suffix (RMB3)
+fn parse_mnemonic(word: &str) -> Result<(IN, Option<u8>), String> {
+    let upper = word.to_ascii_uppercase();
+    let (base, bit) = match upper.as_bytes() {
+        [b'R' | b'S', b'M', b'B', n @ b'0'..=b'7'] | [b'B', b'B', b'R' | b'S', n @ b'0'..=b'7'] => {
+            (&upper[..3], Some(n - b'0'))
+        }
+        _ => (upper.as_str(), None),
+    };
+    IN::from_str(base)
+        .map(|name| (name, bit))
+        .map_err(|_| format!("unknown instruction {word}"))
+}
+
+fn parse_operand(text: &str) -> Result<Operand, String> {
+    let upper = text.to_ascii_uppercase();
+    if text.is_empty() {
+        return Ok(Operand::None);
+    }
+    if upper == "A" {
+        return Ok(Operand::Accumulator);
+    }
+    if let Some(imm) = text.strip_prefix('#') {
+        return Ok(Operand::Immediate(parse_expr(imm)?));
+    }
+    if let Some(inner) = text.strip_prefix('(') {
+        let inner_upper = &upper[1..];
+        return if inner_upper.ends_with(",X)") {
+            Ok(Operand::IndirectX(parse_expr(&inner[..inner.len() - 3])?))
+        } else if inner_upper.ends_with("),Y") {
+            Ok(Operand::IndirectY(parse_expr(&inner[..inner.len() - 3])?))
+        } else if let Some(inner) = inner.strip_suffix(')') {
+            Ok(Operand::Indirect(parse_expr(inner)?))
+        } else {
+            Err(format!("unbalanced parentheses in {text}"))
+        };
+    }
+
+    let args: Vec<&str> = text.split(',').map(str::trim).collect();
+    match args[..] {
+        [addr] => {
+            let (size, e) = parse_sized(addr)?;
+            Ok(Operand::Direct(size, e))
+        }
+        [addr, index] if index.eq_ignore_ascii_case("x") => {
+            let (size, e) = parse_sized(addr)?;
+            Ok(Operand::DirectX(size, e))
+        }
+        [addr, index] if index.eq_ignore_ascii_case("y") => {
+            let (size, e) = parse_sized(addr)?;
+            Ok(Operand::DirectY(size, e))
+        }
+        [zp, target] => Ok(Operand::BitBranch(parse_expr(zp)?, parse_expr(target)?)),
+        _ => Err(format!("can't parse operand {text}")),
+    }
+}
+
+// a: and z: force the operand size like in ca65
+fn parse_sized(text: &str) -> Result<(Size, Expr), String> {
+    let size = match text.get(..2).map(str::to_ascii_lowercase).as_deref() {
+        Some("a:") => Size::Absolute,
+        Some("z:") => Size::ZeroPage,
+        _ => return Ok((Size::Auto, parse_expr(text)?)),
+    };
+    Ok((size, parse_expr(&text[2..])?))
+}
+
+fn parse_expr(text: &str) -> Result<Expr, String> {
+    let text = text.trim();
+    let (part, mut rest) = match text.chars().next() {
+        Some('<') => (Part::Lo, &text[1..]),
+        Some('>') => (Part::Hi, &text[1..]),
+        _ => (Part::Whole, text),
+    };
+
+    let mut terms = vec![];
+    let mut sign = 1;
+    loop {
+        rest = rest.trim_start();
+        if terms.is_empty() {
+            if let Some(r) = rest.strip_prefix('-') {
+                sign = -1;
+                rest = r.trim_start();
+            }
+        }
+        let (term, after) = parse_term(rest)?;
+        terms.push((sign, term));
+
+        rest = after.trim_start();
+        sign = match rest.chars().next() {
+            None => break,
+            Some('+') => 1,
+            Some('-') => -1,
+            Some(_) => return Err(format!("unexpected `{rest}` in expression {text}")),
+        };
+        rest = &rest[1..];
+    }
+    Ok(Expr { part, terms })
+}
+
+fn parse_number(text: &str, radix: u32, skip: usize) -> Result<(Term, &str), String> {
+    let digits = &text[skip..];
+    let end = skip
+        + digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+    i32::from_str_radix(&text[skip..end], radix)
+        .map(|n| (Term::Num(n), &text[end..]))
+        .map_err(|_| format!("bad number {}", &text[..end]))
+}
+
+fn parse_term(text: &str) -> Result<(Term, &str), String> {
+    match text.chars().next() {
+        Some('$') => parse_number(text, 16, 1),
+        Some('%') => parse_number(text, 2, 1),
+        Some('0'..='9') => parse_number(text, 10, 0),
+        Some('*') => Ok((Term::Pc, &text[1..])),
+        Some('\'') => match text.as_bytes() {
+            [b'\'', c, b'\'', ..] => Ok((Term::Num(*c as i32), &text[3..])),
+            _ => Err(format!("bad character literal {text}")),
+        },
+        _ => match split_ident(text) {
+            Some((name, after)) => Ok((Term::Symbol(name.to_string()), after)),
+            None => Err(format!("expected a value, found `{text}`")),
+        },
+    }
+}
@@ -0,0 +1,161 @@
+use super::{assemble, AsmError, Assembler};
+use crate::{
+    cpu::{instructions::*, Variant},
+    disasm::Disassembler,
+};
+
+#[test]
+fn test_assemble_addressing_modes() {
+    let program = assemble(
+        "
+        .org $8000
+start:  LDA #$10        ; immediate
+        LDA $10
+        LDA $10,X
+        LDX $10,Y
+        LDA $1234
+        LDA $1234,X
+        LDA $1234,Y
+        LDA ($10,X)
+        LDA ($10),Y
+        JMP ($1234)
+        ASL A
+        ASL
+        CLC
+        BNE start
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(program.segments.len(), 1);
+    assert_eq!(program.segments[0].origin, 0x8000);
+    assert_eq!(program.label("start"), Some(0x8000));
+    #[rustfmt::skip]
+    let expected: &[u8] = &[
+        LDA_IM.opcode, 0x10,
+        LDA_Z.opcode, 0x10,
+        LDA_ZX.opcode, 0x10,
+        LDX_ZY.opcode, 0x10,
+        LDA_A.opcode, 0x34, 0x12,
+        LDA_AX.opcode, 0x34, 0x12,
+        LDA_AY.opcode, 0x34, 0x12,
+        LDA_IX.opcode, 0x10,
+        LDA_IY.opcode, 0x10,
+        JMP_I.opcode, 0x34, 0x12,
+        ASL_AC.opcode,
+        ASL_AC.opcode,
+        CLC.opcode,
+        BNE.opcode, 0xe3,
+    ];
+    assert_eq!(program.bytes(), expected);
+}
+
+#[test]
+fn test_assemble_symbols() {
+    let program = assemble(
+        "
+ptr = $20
+        .org $0300
+        LDA #<table
+        LDA #>table
+        STA ptr+1
+        LDA later
+        STA a:ptr
+        JSR *+3
+later:  .byte 1, $02, %11, 'A', \"hi\"
+table:  .word later, table-1
+
+        .org $0080
+        LDA zp_var      ; forward reference, assembled as absolute
+        LDA z:zp_var
+zp_var: RTS
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(program.segments.len(), 2);
+    assert_eq!(program.segments[1].origin, 0x0080);
+    #[rustfmt::skip]
+    let expected: &[u8] = &[
+        LDA_IM.opcode, 0x15,
+        LDA_IM.opcode, 0x03,
+        STA_Z.opcode, 0x21,
+        LDA_A.opcode, 0x0f, 0x03,
+        STA_A.opcode, 0x20, 0x00,
+        JSR_A.opcode, 0x0f, 0x03,
+        0x01, 0x02, 0x03, b'A', b'h', b'i',
+        0x0f, 0x03, 0x14, 0x03,
+        LDA_A.opcode, 0x85, 0x00,
+        LDA_Z.opcode, 0x85,
+        RTS.opcode,
+    ];
+    assert_eq!(program.bytes(), expected);
+}
+
+#[test]
+fn test_assemble_65c02() {
+    let program = Assembler::new()
+        .variant(Variant::Wdc65C02)
+        .assemble(
+            "
+loop:   STZ $10
+        LDA ($10)
+        JMP ($1234,X)
+        SMB3 $10
+        BBS7 $10, loop
+        BRA loop
+        ",
+        )
+        .unwrap();
+
+    assert_eq!(
+        program.bytes(),
+        [0x64, 0x10, 0xb2, 0x10, 0x7c, 0x34, 0x12, 0xb7, 0x10, 0xff, 0x10, 0xf4, 0x80, 0xf2]
+    );
+
+    // no (zp) on the 2A03
+    assert!(assemble("LDA ($10)").is_err());
+}
+
+#[test]
+fn test_assemble_errors() {
+    let err = |src| assemble(src).unwrap_err();
+
+    assert_eq!(
+        err("NOP\nFOO $10"),
+        AsmError {
+            line: 2,
+            message: "unknown instruction FOO".into()
+        }
+    );
+    assert_eq!(err("JMP nowhere").message, "undefined symbol `nowhere`");
+    assert_eq!(err("x: NOP\nx: NOP").line, 2);
+    assert_eq!(err("STX $1234,X").line, 1);
+    assert_eq!(
+        err("BNE far\n.org $1000\nfar: RTS").message,
+        "branch target is 4094 bytes away"
+    );
+}
+
+#[test]
+fn test_assemble_disassembly() {
+    // the disassembler's output assembles back to the same bytes
+    let rom = [
+        0x78, 0xa2, 0xff, 0x9a, 0xad, 0x02, 0x20, 0x10, 0xfb, 0x20, 0x14, 0xc0, 0xa7, 0x10, 0x2c,
+        0xa9, 0x01, 0x4c, 0x0f, 0xc0, 0xb9, 0x1a, 0xc0, 0x91, 0x10, 0x60, 0xde, 0xad, 0x8d, 0x10,
+        0x00, 0x40,
+    ];
+    let mut data = rom.to_vec();
+    data.resize(0x3ffa, 0xea);
+    data.extend([0x1f, 0xc0, 0x00, 0xc0, 0x1f, 0xc0]);
+
+    let listing = Disassembler::new(data.clone(), 0xc000)
+        .entry(0xc010)
+        .vectors()
+        .disassemble();
+    let program = assemble(&listing.to_string()).unwrap();
+
+    assert_eq!(program.segments.len(), 1);
+    assert_eq!(program.segments[0].origin, 0xc000);
+    assert_eq!(program.segments[0].bytes, data);
+}
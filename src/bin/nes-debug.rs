@@ -0,0 +1,39 @@
+use nes_emulator::{debugger::Debugger, NESSystem};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+};
+
+fn main() {
+    let Some(rom_name) = env::args().nth(1) else {
+        println!("usage: nes-debug <rom>");
+        return;
+    };
+
+    let raw_bytes = fs::read(&rom_name).unwrap();
+    let system =
+        NESSystem::new(raw_bytes).expect("unable to create emulator struct, check rom loading");
+    let mut debugger = Debugger::new(system);
+
+    println!("{}", debugger.command("regs").unwrap());
+    print!("{}", debugger.command("dis").unwrap());
+
+    let stdin = io::stdin();
+    loop {
+        print!("(nes) ");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        if matches!(line.trim(), "q" | "quit") {
+            break;
+        }
+
+        match debugger.command(&line) {
+            Ok(out) => println!("{}", out.trim_end()),
+            Err(e) => println!("error: {e}"),
+        }
+    }
+}
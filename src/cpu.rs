@@ -1,15 +1,22 @@
 #![allow(non_snake_case)]
 
 use crate::{
+    cpu::{
+        call_stack::{CallStack, Frame, FrameKind},
+        profiler::Profiler,
+    },
     make_u16,
-    memory::memory_bus::{InterruptType::NonMaskable, MemoryBus},
+    memory::memory_bus::{Bus, InterruptType, ReadKind},
     LogEvent, Logger,
 };
 
+pub mod call_stack;
 pub mod instructions;
+pub mod profiler;
 pub use instructions as ins;
 
-use instructions::{get_instruction, AddressingMode, Instruction, JMP_A, JMP_I, JSR_A};
+pub use instructions::Variant;
+use instructions::{AddressingMode, Instruction, JMP_A, JMP_I, JSR_A};
 use LogEvent as LE;
 
 use bitflags::bitflags;
@@ -17,11 +24,15 @@ use bitflags::bitflags;
 // pub mod cpu;
 
 mod internal;
-// #[cfg(test)]
-// mod tests;
+#[cfg(test)]
+mod tests;
 
 const STACK_START: u16 = 0x100;
 
+pub const NMI_VECTOR: u16 = 0xfffa;
+pub const RESET_VECTOR: u16 = 0xfffc;
+pub const IRQ_VECTOR: u16 = 0xfffe;
+
 bitflags! {
     #[derive(Debug, Clone, Copy)]
     pub struct StatusFlags: u8 {
@@ -37,7 +48,7 @@ bitflags! {
 }
 
 #[derive(Debug)]
-pub struct CPU {
+pub struct CPU<B: Bus> {
     pub reg_x: u8,
     pub reg_y: u8,
     pub accumulator: u8,
@@ -45,11 +56,27 @@ pub struct CPU {
     pub flags: StatusFlags,
     pub program_counter: u16,
     pub cycle_count: usize,
-    pub mem_bus: MemoryBus,
+    // cycle count the bus (ppu) has been clocked up to, lags cycle_count mid-instruction
+    bus_cycle_count: usize,
+    // clock the bus on every access instead of once per instruction, so reads and writes land on
+    // the exact cpu cycle they happen on
+    pub cycle_stepped: bool,
+    pub variant: Variant,
+    pub mem_bus: B,
+    // sleeping in a 65C02 WAI until an interrupt line goes active
+    waiting: bool,
+    // halted by a JAM (or the 65C02's STP), only a reset gets it going again
+    jammed: bool,
+    // the I flag as seen by the last interrupt poll, CLI/SEI/PLP only affect it one instruction late
+    irq_inhibit: bool,
+    // print a nestest style trace line for every instruction
     logged: bool,
-    pub log: String,
 
     pub logger: Option<Box<dyn Logger>>,
+    // shadow call stack for backtraces, off unless a debugger asks for it
+    pub call_stack: Option<CallStack>,
+    // cycle accounting, see enable_profiler
+    pub profiler: Option<Profiler>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -63,8 +90,8 @@ pub struct CPUStateLog {
     pub cycle_count: usize,
 }
 
-impl CPU {
-    pub fn new_program(logged: bool, mem_bus: MemoryBus, logger: Option<Box<dyn Logger>>) -> Self {
+impl<B: Bus> CPU<B> {
+    pub fn new_program(logged: bool, mem_bus: B, logger: Option<Box<dyn Logger>>) -> Self {
         Self {
             reg_x: 0,
             reg_y: 0,
@@ -72,25 +99,81 @@ impl CPU {
             stack_pointer: 0xfd,
             flags: StatusFlags::from_bits_truncate(0x24),
             program_counter: 0,
-            // TODO FIX THIS !!!!!!!! cycles should start at 0, logging is 1 instr behind (fix ppu too)
-            cycle_count: 7,
+            cycle_count: 0,
+            bus_cycle_count: 0,
+            cycle_stepped: false,
+            variant: Variant::default(),
             mem_bus,
+            waiting: false,
+            jammed: false,
+            irq_inhibit: true,
             logged,
-            log: String::new(),
             logger,
+            call_stack: None,
+            profiler: None,
         }
     }
 
     pub fn reset(&mut self) {
-        self.program_counter = self.mem_bus.read_16bit(0xFFFC);
+        self.jammed = false;
+        self.waiting = false;
+        self.program_counter = self.bus_read_u16(RESET_VECTOR, ReadKind::Data);
+
+        // the reset sequence takes 7 cycles, which is why nestest.log starts at CYC:7
+        self.cycle_count += 7;
+        self.sync_bus();
+    }
+
+    pub fn jammed(&self) -> bool {
+        self.jammed
     }
 
     pub fn run_once(&mut self) {
-        if let Some(NonMaskable) = self.mem_bus.poll_interrupt() {
-            self.interrupt_nmi();
+        // a jammed cpu ignores interrupts too, time still passes for the rest of the system
+        if self.jammed {
+            self.cycle_count += 1;
+            self.profile_cycles(self.program_counter, 1);
+            self.sync_bus();
+            return;
+        }
+
+        match self.mem_bus.poll_interrupt() {
+            Some(InterruptType::NonMaskable) => {
+                self.waiting = false;
+                self.interrupt_nmi();
+            }
+            Some(InterruptType::Request) if !self.irq_inhibit => {
+                self.waiting = false;
+                self.interrupt_irq();
+            }
+            // WAI also wakes on a masked IRQ, it just carries on with the next instruction
+            Some(InterruptType::Request) => self.waiting = false,
+            None => {}
         }
-        let cycles = self.tick();
-        self.mem_bus.tick_ppu(cycles);
+
+        if self.waiting {
+            self.cycle_count += 1;
+            self.profile_cycles(self.program_counter.wrapping_sub(1), 1);
+        } else {
+            self.tick();
+        }
+
+        self.sync_bus();
+    }
+
+    // clock the bus for every cpu cycle it hasn't seen yet
+    fn sync_bus(&mut self) {
+        let behind = self.cycle_count - self.bus_cycle_count;
+        if behind > 0 {
+            self.mem_bus.tick(behind);
+            self.bus_cycle_count = self.cycle_count;
+        }
+    }
+
+    // starts a fresh profile. routines come from the call stack, so that's turned on too
+    pub fn enable_profiler(&mut self) {
+        self.call_stack.get_or_insert_with(CallStack::default);
+        self.profiler = Some(Profiler::default());
     }
 
     pub fn run_count(&mut self, count: usize) {
@@ -101,44 +184,155 @@ impl CPU {
 
     pub fn tick(&mut self) -> usize {
         let cycle_count_before = self.cycle_count;
+        let (pc, sp) = (self.program_counter, self.stack_pointer);
+
+        if let Some(p) = self.profiler.as_mut() {
+            let frames = self.call_stack.as_ref().map_or(&[][..], |cs| cs.frames());
+            p.begin(frames, self.mem_bus.get_ppu_scanline());
+        }
+
+        let opcode = self.bus_read_as(self.program_counter, ReadKind::Opcode);
+        let ins = self.variant.decode(opcode);
 
-        let opcode = self.mem_bus.read(self.program_counter);
-        let ins = get_instruction(opcode);
+        self.log_event(LE::InstructionFetch(ins));
 
         if self.logged {
-            let log = self.logged_execute(ins);
-            println!("{log}");
-            self.log.push_str(&log);
-            self.log.push('\n');
+            println!("{}", self.logged_execute(ins));
         }
 
         self.execute(ins);
 
-        self.cycle_count - cycle_count_before
+        // the copy itself happens all at once, the cpu just loses the time: a cycle to halt,
+        // one more to line up with a read if that lands on an odd cycle, then 256 reads and
+        // 256 writes. it's charged to the STA $4014 that started it
+        if self.mem_bus.take_oam_dma() {
+            self.cycle_count += 513 + self.cycle_count % 2;
+        }
+
+        if let Some(cs) = self.call_stack.as_mut() {
+            cs.after_instruction(ins, pc, sp, self.stack_pointer);
+        }
+
+        if let Some(l) = self.logger.as_mut() {
+            l.log_event(LE::StateUpdate(CPUStateLog {
+                reg_x: self.reg_x,
+                reg_y: self.reg_y,
+                accumulator: self.accumulator,
+                stack_pointer: self.stack_pointer,
+                flags: self.flags.bits(),
+                program_counter: self.program_counter,
+                cycle_count: self.cycle_count,
+            }));
+            l.log_state();
+        }
+
+        let cycles = self.cycle_count - cycle_count_before;
+        if let Some(p) = self.profiler.as_mut() {
+            p.end(pc, cycles);
+        }
+        cycles
     }
 
-    fn interrupt_nmi(&mut self) {
+    pub fn log_event(&mut self, le: LogEvent) {
         if let Some(l) = self.logger.as_mut() {
-            l.log_event(LE::NMIInterupt);
+            l.log_event(le);
+        }
+    }
+
+    fn interrupt_nmi(&mut self) {
+        self.log_event(LE::NMIInterupt);
+
+        self.cycle_count += 7;
+        self.interrupt_fetch();
+        self.interrupt(NMI_VECTOR, false);
+        self.profile_cycles(self.program_counter, 7);
+    }
+
+    fn interrupt_irq(&mut self) {
+        self.cycle_count += 7;
+        self.interrupt_fetch();
+        self.interrupt(IRQ_VECTOR, false);
+        self.profile_cycles(self.program_counter, 7);
+    }
+
+    // cycles spent outside tick still count. interrupt entry goes to the handler's frame, pc is
+    // the handler's first instruction
+    fn profile_cycles(&mut self, pc: u16, cycles: usize) {
+        if let Some(p) = self.profiler.as_mut() {
+            let frames = self.call_stack.as_ref().map_or(&[][..], |cs| cs.frames());
+            p.begin(frames, self.mem_bus.get_ppu_scanline());
+            p.charge(pc, cycles);
         }
+    }
 
-        self.stack_push_u16(self.program_counter);
+    // a hardware interrupt replaces the opcode fetch, the opcode and the byte after it are read
+    // but pc isn't moved
+    fn interrupt_fetch(&mut self) {
+        self.mem_read_intermediate(self.program_counter);
+        self.mem_read_intermediate(self.program_counter);
+    }
+
+    // interrupt sequence shared by BRK, NMI and IRQ: push pc and status (with B set only for
+    // BRK), then jump through the vector. the caller accounts for the 7 cycles it takes
+    fn interrupt(&mut self, vector: u16, break_flag: bool) {
+        let ret = self.program_counter;
+        self.stack_push_u16(ret);
         let mut new_flags = self.flags;
-        new_flags.remove(StatusFlags::BREAK);
+        new_flags.set(StatusFlags::BREAK, break_flag);
         new_flags.insert(StatusFlags::BREAK2_U);
 
         self.stack_push_u8(new_flags.bits());
         self.flags.insert(StatusFlags::INTERRUPT_DISABLE);
+        if self.variant == Variant::Wdc65C02 {
+            self.flags.remove(StatusFlags::DECIMAL);
+        }
+        self.irq_inhibit = true;
 
-        self.mem_bus.tick_ppu(2);
-        self.program_counter = self.mem_bus.read_16bit(0xFFFA); //TODO make this a constant
+        // an NMI that arrives before the vector is fetched hijacks a BRK or IRQ, the pushed
+        // status still says which one it was
+        if !self.cycle_stepped {
+            self.sync_bus();
+        }
+        let vector = if vector != NMI_VECTOR && self.mem_bus.poll_nmi() {
+            self.log_event(LE::NMIInterupt);
+            NMI_VECTOR
+        } else {
+            vector
+        };
+
+        let lo = self.mem_read(vector);
+        self.program_counter = make_u16!(self.mem_read(vector.wrapping_add(1)), lo);
+
+        if let Some(cs) = self.call_stack.as_mut() {
+            let (kind, site) = match (vector, break_flag) {
+                (NMI_VECTOR, _) => (FrameKind::Nmi, ret),
+                (_, true) => (FrameKind::Brk, ret.wrapping_sub(2)),
+                (_, false) => (FrameKind::Irq, ret),
+            };
+            cs.call(Frame {
+                kind,
+                site,
+                target: self.program_counter,
+                ret,
+                sp: self.stack_pointer,
+            });
+        }
     }
 
     pub fn execute(&mut self, ins: Instruction) {
         use instructions::InstructionName as IN;
 
+        let i_flag_before = self.flags.contains(StatusFlags::INTERRUPT_DISABLE);
         self.program_counter = self.program_counter.wrapping_add(1); // TODO do this in fetch?
-        self.cycle_count += ins.cycles as usize; // TODO add oops cycles
+
+        // page-cross and branch-taken penalties are added by the addressing helpers
+        self.cycle_count += ins.cycles as usize;
+
+        // single byte instructions still read the byte after the opcode and throw it away
+        // (except the 65C02's one-cycle nops)
+        if ins.bytes == 1 && ins.cycles > 1 {
+            self.mem_read_intermediate(self.program_counter);
+        }
 
         let n = ins.name;
 
@@ -204,21 +398,23 @@ impl CPU {
             ============================================================= */
             IN::ADC => {
                 let val = self.fetch_ins_operand(ins);
-                self.add_to_acc(val);
-                self.set_zn_flags(self.accumulator);
+                self.add_with_carry(val);
             }
 
             IN::SBC => {
-                let val = !self.fetch_ins_operand(ins);
-                self.add_to_acc(val);
-                self.set_zn_flags(self.accumulator);
+                let val = self.fetch_ins_operand(ins);
+                self.subtract_with_carry(val);
             }
 
             IN::INC => {
-                // TODO rmw
-                let (val, addr) = self.fetch_value_keep_addr(ins);
-                self.mem_bus.write(addr, val.wrapping_add(1));
-                self.set_zn_flags(val.wrapping_add(1));
+                if ins.mode == AddressingMode::Accumulator {
+                    self.accumulator = self.accumulator.wrapping_add(1);
+                    self.set_zn_flags(self.accumulator);
+                } else {
+                    let (val, addr) = self.fetch_value_keep_addr(ins);
+                    self.mem_write(addr, val.wrapping_add(1));
+                    self.set_zn_flags(val.wrapping_add(1));
+                }
             }
 
             IN::INX => {
@@ -232,10 +428,14 @@ impl CPU {
             }
 
             IN::DEC => {
-                // TODO rmw
-                let (val, addr) = self.fetch_value_keep_addr(ins);
-                self.mem_bus.write(addr, val.wrapping_sub(1));
-                self.set_zn_flags(val.wrapping_sub(1));
+                if ins.mode == AddressingMode::Accumulator {
+                    self.accumulator = self.accumulator.wrapping_sub(1);
+                    self.set_zn_flags(self.accumulator);
+                } else {
+                    let (val, addr) = self.fetch_value_keep_addr(ins);
+                    self.mem_write(addr, val.wrapping_sub(1));
+                    self.set_zn_flags(val.wrapping_sub(1));
+                }
             }
 
             IN::DEX => {
@@ -252,69 +452,42 @@ impl CPU {
             ============================================================= */
             IN::ASL => {
                 if ins.mode == AddressingMode::Accumulator {
-                    self.set_status_bit(StatusFlags::CARRY, self.accumulator >> 7);
-                    self.accumulator <<= 1;
-                    self.set_zn_flags(self.accumulator);
+                    self.accumulator = self.shift_left(self.accumulator);
                 } else {
-                    let (mut val, addr) = self.fetch_value_keep_addr(ins);
-                    self.set_status_bit(StatusFlags::CARRY, val >> 7);
-                    val <<= 1;
-                    self.set_zn_flags(val);
-                    self.mem_bus.write(addr, val);
+                    let (val, addr) = self.fetch_value_keep_addr(ins);
+                    let val = self.shift_left(val);
+                    self.mem_write(addr, val);
                 }
-                // TODO rmw
             }
 
             IN::LSR => {
-                // TODO rmw
                 if ins.mode == AddressingMode::Accumulator {
-                    self.set_status_bit(StatusFlags::CARRY, self.accumulator & 1);
-                    self.accumulator >>= 1;
-                    self.set_zn_flags(self.accumulator);
+                    self.accumulator = self.shift_right(self.accumulator);
                 } else {
-                    let (mut val, addr) = self.fetch_value_keep_addr(ins);
-
-                    self.set_status_bit(StatusFlags::CARRY, val & 1);
-                    val >>= 1;
-                    self.set_zn_flags(val);
-                    self.mem_bus.write(addr, val);
+                    let (val, addr) = self.fetch_value_keep_addr(ins);
+                    let val = self.shift_right(val);
+                    self.mem_write(addr, val);
                 }
             }
 
             IN::ROL => {
                 if ins.mode == AddressingMode::Accumulator {
-                    let old_c = self.get_status_bit(StatusFlags::CARRY);
-                    self.set_status_bit(StatusFlags::CARRY, self.accumulator >> 7);
-                    self.accumulator <<= 1;
-                    self.accumulator |= old_c;
-                    self.set_zn_flags(self.accumulator);
+                    self.accumulator = self.rotate_left(self.accumulator);
                 } else {
-                    let (mut val, addr) = self.fetch_value_keep_addr(ins);
-                    let old_c = self.get_status_bit(StatusFlags::CARRY);
-                    self.set_status_bit(StatusFlags::CARRY, val >> 7);
-                    val <<= 1;
-                    val |= old_c;
-                    self.set_zn_flags(val);
-                    self.mem_bus.write(addr, val);
-                };
+                    let (val, addr) = self.fetch_value_keep_addr(ins);
+                    let val = self.rotate_left(val);
+                    self.mem_write(addr, val);
+                }
             }
 
             IN::ROR => {
                 if ins.mode == AddressingMode::Accumulator {
-                    let old_c = self.get_status_bit(StatusFlags::CARRY);
-                    self.set_status_bit(StatusFlags::CARRY, self.accumulator & 1);
-                    self.accumulator >>= 1;
-                    self.accumulator |= old_c << 7;
-                    self.set_zn_flags(self.accumulator);
+                    self.accumulator = self.rotate_right(self.accumulator);
                 } else {
-                    let (mut val, addr) = self.fetch_value_keep_addr(ins);
-                    let old_c = self.get_status_bit(StatusFlags::CARRY);
-                    self.set_status_bit(StatusFlags::CARRY, val & 1);
-                    val >>= 1;
-                    val |= old_c << 7;
-                    self.set_zn_flags(val);
-                    self.mem_bus.write(addr, val);
-                };
+                    let (val, addr) = self.fetch_value_keep_addr(ins);
+                    let val = self.rotate_right(val);
+                    self.mem_write(addr, val);
+                }
             }
 
             /* BITWISE INSTRUCTIONS ========================================
@@ -327,8 +500,11 @@ impl CPU {
             IN::BIT => {
                 let val = self.fetch_ins_operand(ins);
                 self.set_status_bit(StatusFlags::ZERO, (val & self.accumulator == 0) as u8);
-                self.set_status_bit(StatusFlags::OVERFLOW, val >> 6 & 1);
-                self.set_status_bit(StatusFlags::NEGATIVE, val >> 7);
+                // the 65C02's BIT #imm only sets Z
+                if ins.mode != AddressingMode::Immediate {
+                    self.set_status_bit(StatusFlags::OVERFLOW, val >> 6 & 1);
+                    self.set_status_bit(StatusFlags::NEGATIVE, val >> 7);
+                }
             }
 
             IN::ORA => {
@@ -345,197 +521,124 @@ impl CPU {
             ============================================================= */
             IN::CMP => {
                 let val = self.fetch_ins_operand(ins);
-                self.set_status_bit(
-                    StatusFlags::CARRY,
-                    if self.accumulator >= val { 1 } else { 0 },
-                );
-                self.set_status_bit(
-                    StatusFlags::ZERO,
-                    if self.accumulator == val { 1 } else { 0 },
-                );
-                self.set_status_bit(
-                    StatusFlags::NEGATIVE,
-                    self.accumulator.wrapping_sub(val) >> 7,
-                );
+                self.compare(self.accumulator, val);
             }
 
             IN::CPX => {
                 let val = self.fetch_ins_operand(ins);
-                self.set_status_bit(StatusFlags::CARRY, if self.reg_x >= val { 1 } else { 0 });
-                self.set_status_bit(StatusFlags::ZERO, if self.reg_x == val { 1 } else { 0 });
-                self.set_status_bit(StatusFlags::NEGATIVE, self.reg_x.wrapping_sub(val) >> 7);
+                self.compare(self.reg_x, val);
             }
 
             IN::CPY => {
                 let val = self.fetch_ins_operand(ins);
-                self.set_status_bit(StatusFlags::CARRY, if self.reg_y >= val { 1 } else { 0 });
-                self.set_status_bit(StatusFlags::ZERO, if self.reg_y == val { 1 } else { 0 });
-                self.set_status_bit(StatusFlags::NEGATIVE, self.reg_y.wrapping_sub(val) >> 7);
+                self.compare(self.reg_y, val);
             }
 
             /* BRANCH INSTRUCTIONS =========================================
             ============================================================= */
-            IN::BCC => {
-                if self.get_status_bit(StatusFlags::CARRY) == 0 {
-                    self.cycle_count += 1;
-                    // TODO find better way?
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BCS => {
-                if self.get_status_bit(StatusFlags::CARRY) == 1 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BEQ => {
-                if self.get_status_bit(StatusFlags::ZERO) == 1 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BNE => {
-                if self.get_status_bit(StatusFlags::ZERO) == 0 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BPL => {
-                if self.get_status_bit(StatusFlags::NEGATIVE) == 0 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BMI => {
-                if self.get_status_bit(StatusFlags::NEGATIVE) == 1 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BVC => {
-                if self.get_status_bit(StatusFlags::OVERFLOW) == 0 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
-            }
-
-            IN::BVS => {
-                if self.get_status_bit(StatusFlags::OVERFLOW) == 1 {
-                    self.cycle_count += 1;
-                    let byte = self.mem_read_pc_u8();
-                    let branch = if byte & 0b10000000 != 0 {
-                        -(((!byte).wrapping_add(1)) as i32)
-                    } else {
-                        byte as i32
-                    };
-                    self.program_counter = ((self.program_counter as i32) + branch) as u16;
-                } else {
-                    self.program_counter += 1;
-                }
+            IN::BCC => self.branch(self.get_status_bit(StatusFlags::CARRY) == 0),
+            IN::BCS => self.branch(self.get_status_bit(StatusFlags::CARRY) == 1),
+            IN::BEQ => self.branch(self.get_status_bit(StatusFlags::ZERO) == 1),
+            IN::BNE => self.branch(self.get_status_bit(StatusFlags::ZERO) == 0),
+            IN::BPL => self.branch(self.get_status_bit(StatusFlags::NEGATIVE) == 0),
+            IN::BMI => self.branch(self.get_status_bit(StatusFlags::NEGATIVE) == 1),
+            IN::BVC => self.branch(self.get_status_bit(StatusFlags::OVERFLOW) == 0),
+            IN::BVS => self.branch(self.get_status_bit(StatusFlags::OVERFLOW) == 1),
+            IN::BRA => self.branch(true),
+
+            IN::BBR | IN::BBS => {
+                let addr = self.mem_read_pc_u8();
+                let val = self.mem_read(addr as u16);
+                self.mem_read_intermediate(addr as u16);
+                let bit_set = val & (1 << ((ins.opcode >> 4) & 7)) != 0;
+                self.branch(bit_set == (n == IN::BBS));
             }
 
             /* JUMP INSTRUCTIONS ===========================================
             ============================================================= */
-            IN::JMP => {
-                if ins.mode == AddressingMode::Absolute {
+            IN::JMP => match ins.mode {
+                AddressingMode::Absolute => {
                     self.program_counter = self.mem_read_pc_u16();
-                } else {
+                }
+                AddressingMode::Indirect if self.variant == Variant::Wdc65C02 => {
+                    // fixed on the 65C02: the pointer carries into the next page, which costs
+                    // a cycle spent re-reading the operand
                     let addr = self.mem_read_pc_u16();
-                    if addr & 0xff == 0xff {
-                        self.program_counter =
-                            make_u16!(self.mem_bus.read(addr - 0xff), self.mem_bus.read(addr));
-                    } else {
-                        self.program_counter = make_u16!(
-                            self.mem_bus.read(addr.wrapping_add(1)),
-                            self.mem_bus.read(addr)
-                        );
-                    }
+                    self.mem_read_intermediate(self.program_counter.wrapping_sub(1));
+                    let lo = self.mem_read_pointer(addr);
+                    self.program_counter =
+                        make_u16!(self.mem_read_pointer(addr.wrapping_add(1)), lo);
                 }
-            }
+                AddressingMode::Indirect => {
+                    // the pointer's high byte is read without carrying into the page
+                    let addr = self.mem_read_pc_u16();
+                    let lo = self.mem_read_pointer(addr);
+                    let hi =
+                        self.mem_read_pointer((addr & 0xff00) | (addr.wrapping_add(1) & 0x00ff));
+                    self.program_counter = make_u16!(hi, lo);
+                }
+                _ => {
+                    // (abs,X)
+                    let addr = self.mem_read_pc_u16();
+                    self.mem_read_intermediate(self.program_counter.wrapping_sub(1));
+                    let ptr = addr.wrapping_add(self.reg_x as u16);
+                    let lo = self.mem_read_pointer(ptr);
+                    self.program_counter =
+                        make_u16!(self.mem_read_pointer(ptr.wrapping_add(1)), lo);
+                }
+            },
 
             IN::JSR => {
-                let sr_addr = self.mem_read_pc_u16();
-
-                self.stack_push_u16(self.program_counter - 1);
-                self.program_counter = sr_addr;
+                // the return address is pushed between the two operand fetches, so it points
+                // at the high byte
+                let lo = self.bus_read_as(self.program_counter, ReadKind::Operand);
+                self.program_counter = self.program_counter.wrapping_add(1);
+                self.stack_peek();
+                self.stack_push_u16(self.program_counter);
+                let ret = self.program_counter;
+                let hi = self.bus_read_as(self.program_counter, ReadKind::Operand);
+                self.program_counter = make_u16!(hi, lo);
+                self.log_event(LE::OperandFetch(self.program_counter));
+
+                if let Some(cs) = self.call_stack.as_mut() {
+                    cs.call(Frame {
+                        kind: FrameKind::Jsr,
+                        site: ret.wrapping_sub(2),
+                        target: self.program_counter,
+                        ret: ret.wrapping_add(1),
+                        sp: self.stack_pointer,
+                    });
+                }
             }
 
             IN::RTI => {
+                let (site, sp) = (self.program_counter.wrapping_sub(1), self.stack_pointer);
+                self.stack_peek();
                 self.flags =
                     StatusFlags::from_bits_truncate(self.stack_pull_u8() & 0b11101111 | 0b00100000);
                 self.program_counter = self.stack_pull_u16();
+
+                if let Some(cs) = self.call_stack.as_mut() {
+                    cs.ret(true, site, sp, self.program_counter);
+                }
             }
 
             IN::RTS => {
+                let (site, sp) = (self.program_counter.wrapping_sub(1), self.stack_pointer);
+                self.stack_peek();
                 self.program_counter = self.stack_pull_u16();
-                self.program_counter += 1;
+                self.mem_read_intermediate(self.program_counter);
+                self.program_counter = self.program_counter.wrapping_add(1);
+
+                if let Some(cs) = self.call_stack.as_mut() {
+                    cs.ret(false, site, sp, self.program_counter);
+                }
             }
 
             IN::BRK => {
-                //TODO
-                // todo!("havent implemented BRK yet");
+                // BRK has a padding byte, so the return address is pc + 2
+                self.mem_read_pc_u8();
+                self.interrupt(IRQ_VECTOR, true);
             }
 
             /* STACK INSTRUCTIONS ==========================================
@@ -553,19 +656,40 @@ impl CPU {
                 self.stack_push_u8(self.accumulator);
             }
 
+            IN::PHX => {
+                self.stack_push_u8(self.reg_x);
+            }
+
+            IN::PHY => {
+                self.stack_push_u8(self.reg_y);
+            }
+
             IN::PHP => {
                 self.stack_push_u8(self.flags.bits() | 0b00110000);
             }
 
             IN::PLA => {
+                self.stack_peek();
                 self.accumulator = self.stack_pull_u8();
                 self.set_zn_flags(self.accumulator);
             }
 
+            IN::PLX => {
+                self.stack_peek();
+                self.reg_x = self.stack_pull_u8();
+                self.set_zn_flags(self.reg_x);
+            }
+
+            IN::PLY => {
+                self.stack_peek();
+                self.reg_y = self.stack_pull_u8();
+                self.set_zn_flags(self.reg_y);
+            }
+
             IN::PLP => {
+                self.stack_peek();
                 self.flags =
                     StatusFlags::from_bits_truncate(self.stack_pull_u8() & 0b11101111 | 0b00100000);
-                // TODO the I flag needs to be delayed 1 instr
             }
 
             /* FLAG INSTRUCTIONS ===========================================
@@ -579,7 +703,6 @@ impl CPU {
             }
 
             IN::CLI => {
-                // TODO this needs to be delayed by 1 instruction
                 self.set_status_bit(StatusFlags::INTERRUPT_DISABLE, 0);
             }
 
@@ -602,8 +725,194 @@ impl CPU {
             /* OTHER INSTRUCTIONS ==========================================
             ============================================================= */
             IN::NOP => {
-                // do nothing
+                // unofficial SKB/IGN nops still read their operand
+                if ins.mode != AddressingMode::Implicit {
+                    self.fetch_ins_operand(ins);
+                }
+                // the 65C02's 8 cycle $5c nop spends the rest of its time on idle reads
+                if self.variant == Variant::Wdc65C02 && ins.opcode == 0x5c {
+                    for _ in 0..4 {
+                        self.mem_read_intermediate(0xffff);
+                    }
+                }
+            }
+
+            /* UNOFFICIAL INSTRUCTIONS =====================================
+            ============================================================= */
+            IN::LAX => {
+                let val = self.fetch_ins_operand(ins);
+
+                self.set_zn_flags(val);
+                self.accumulator = val;
+                self.reg_x = val;
+            }
+
+            IN::SAX => {
+                self.mem_write_with_mode(self.accumulator & self.reg_x, ins);
+            }
+
+            IN::DCP => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let val = val.wrapping_sub(1);
+                self.mem_write(addr, val);
+                self.compare(self.accumulator, val);
+            }
+
+            IN::ISB => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let val = val.wrapping_add(1);
+                self.mem_write(addr, val);
+                self.subtract_with_carry(val);
+            }
+
+            IN::SLO => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let val = self.shift_left(val);
+                self.mem_write(addr, val);
+                self.accumulator |= val;
+                self.set_zn_flags(self.accumulator);
+            }
+
+            IN::RLA => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let val = self.rotate_left(val);
+                self.mem_write(addr, val);
+                self.accumulator &= val;
+                self.set_zn_flags(self.accumulator);
+            }
+
+            IN::SRE => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let val = self.shift_right(val);
+                self.mem_write(addr, val);
+                self.accumulator ^= val;
+                self.set_zn_flags(self.accumulator);
+            }
+
+            IN::RRA => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let val = self.rotate_right(val);
+                self.mem_write(addr, val);
+                self.add_with_carry(val);
+            }
+
+            IN::ANC => {
+                self.accumulator &= self.fetch_ins_operand(ins);
+                self.set_zn_flags(self.accumulator);
+                self.set_status_bit(StatusFlags::CARRY, self.accumulator >> 7);
+            }
+
+            IN::ALR => {
+                let val = self.accumulator & self.fetch_ins_operand(ins);
+                self.accumulator = self.shift_right(val);
+            }
+
+            IN::ARR => {
+                let val = self.accumulator & self.fetch_ins_operand(ins);
+                self.accumulator = (val >> 1) | (self.get_status_bit(StatusFlags::CARRY) << 7);
+                self.set_zn_flags(self.accumulator);
+                let bit6 = (self.accumulator >> 6) & 1;
+                let bit5 = (self.accumulator >> 5) & 1;
+                self.set_status_bit(StatusFlags::CARRY, bit6);
+                self.set_status_bit(StatusFlags::OVERFLOW, bit6 ^ bit5);
+            }
+
+            IN::AXS => {
+                let val = self.fetch_ins_operand(ins);
+                let and = self.accumulator & self.reg_x;
+                self.set_status_bit(StatusFlags::CARRY, if and >= val { 1 } else { 0 });
+                self.reg_x = and.wrapping_sub(val);
+                self.set_zn_flags(self.reg_x);
+            }
+
+            IN::XAA => {
+                // unstable on real hardware, 0xee is the most commonly observed magic constant
+                let val = self.fetch_ins_operand(ins);
+                self.accumulator = (self.accumulator | 0xee) & self.reg_x & val;
+                self.set_zn_flags(self.accumulator);
+            }
+
+            IN::LXA => {
+                let val = self.fetch_ins_operand(ins);
+                self.accumulator = (self.accumulator | 0xee) & val;
+                self.reg_x = self.accumulator;
+                self.set_zn_flags(self.accumulator);
+            }
+
+            IN::LAS => {
+                let val = self.fetch_ins_operand(ins) & self.stack_pointer;
+                self.accumulator = val;
+                self.reg_x = val;
+                self.stack_pointer = val;
+                self.set_zn_flags(val);
+            }
+
+            IN::SHA => {
+                self.mem_write_high_and(self.accumulator & self.reg_x, ins);
+            }
+
+            IN::SHX => {
+                self.mem_write_high_and(self.reg_x, ins);
+            }
+
+            IN::SHY => {
+                self.mem_write_high_and(self.reg_y, ins);
+            }
+
+            IN::TAS => {
+                self.stack_pointer = self.accumulator & self.reg_x;
+                self.mem_write_high_and(self.stack_pointer, ins);
+            }
+
+            IN::JAM => {
+                // the cpu locks up with pc still on the opcode
+                self.log_event(LE::BadOpcode(ins.opcode));
+                self.program_counter = self.program_counter.wrapping_sub(1);
+                self.jammed = true;
+            }
+
+            /* 65C02 INSTRUCTIONS ==========================================
+            ============================================================= */
+            IN::STZ => {
+                self.mem_write_with_mode(0, ins);
             }
+
+            IN::TSB | IN::TRB => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                self.set_status_bit(StatusFlags::ZERO, (val & self.accumulator == 0) as u8);
+                let val = if n == IN::TSB {
+                    val | self.accumulator
+                } else {
+                    val & !self.accumulator
+                };
+                self.mem_write(addr, val);
+            }
+
+            IN::RMB | IN::SMB => {
+                let (val, addr) = self.fetch_value_keep_addr(ins);
+                let bit = 1 << ((ins.opcode >> 4) & 7);
+                let val = if n == IN::SMB { val | bit } else { val & !bit };
+                self.mem_write(addr, val);
+            }
+
+            // both spend their third cycle on another idle read before stopping
+            IN::WAI => {
+                self.mem_read_intermediate(self.program_counter);
+                self.waiting = true;
+            }
+
+            IN::STP => {
+                self.mem_read_intermediate(self.program_counter);
+                self.program_counter = self.program_counter.wrapping_sub(1);
+                self.jammed = true;
+            }
+        };
+
+        // CLI, SEI and PLP change the I flag after the cpu has already polled for interrupts,
+        // so the old value stays in effect for one more instruction. RTI takes effect at once
+        self.irq_inhibit = match n {
+            IN::CLI | IN::SEI | IN::PLP => i_flag_before,
+            _ => self.flags.contains(StatusFlags::INTERRUPT_DISABLE),
         };
     }
 
@@ -621,7 +930,8 @@ impl CPU {
                 2 => {
                     let addr = self.mem_bus.dbg_read(begin.wrapping_add(1));
                     if ins.mode != AddressingMode::Relative {
-                        (addr as u16, self.dbg_read_8bit(addr, ins.mode))
+                        let addr = self.dbg_get_addr_8bit(addr, ins.mode);
+                        (addr, self.mem_bus.dbg_read(addr))
                     } else {
                         (addr as u16, 0)
                     }
@@ -631,12 +941,16 @@ impl CPU {
                         self.mem_bus.dbg_read(begin.wrapping_add(2)),
                         self.mem_bus.dbg_read(begin.wrapping_add(1))
                     );
-                    (addr, self.dbg_read_16bit(addr, ins.mode))
-                }
-                _ => {
-                    println!("{:?} causing problems", ins);
-                    unreachable!()
+                    match ins.mode {
+                        AddressingMode::AbsoluteIndexedIndirect
+                        | AddressingMode::ZeroPageRelative => (addr, 0),
+                        _ => {
+                            let addr = self.dbg_get_addr_16bit(addr, ins.mode);
+                            (addr, self.mem_bus.dbg_read(addr))
+                        }
+                    }
                 }
+                _ => (0, 0),
             },
         };
 
@@ -679,31 +993,24 @@ impl CPU {
                     AddressingMode::IndirectIndexed => format!(
                         "(${:02x}),Y = {:04x} @ {:04x} = {:02x}",
                         address,
-                        self.mem_bus
-                            .dbg_read(mem_addr.wrapping_sub(self.reg_y as u16)),
-                        {
-                            let hi = self
-                                .mem_bus
-                                .read(mem_addr.wrapping_sub(self.reg_y as u16).wrapping_add(1));
-                            let lo = self
-                                .mem_bus
-                                .dbg_read(mem_addr.wrapping_sub(self.reg_y as u16));
-                            self.mem_bus
-                                .dbg_read(make_u16!(hi, lo).wrapping_add(self.reg_y as u16))
-                        },
+                        mem_addr.wrapping_sub(self.reg_y as u16),
+                        mem_addr,
                         stored_value
                     ),
-                    AddressingMode::Implicit | AddressingMode::Relative => {
+                    AddressingMode::ZeroPageIndirect => format!(
+                        "(${:02x}) = {:04x} = {:02x}",
+                        address, mem_addr, stored_value
+                    ),
+                    // no operand to show for implied instructions, BRK's second byte is padding
+                    AddressingMode::Implicit => String::new(),
+                    AddressingMode::Relative => {
                         // assuming local jumps: BNE, BVS, etc....
                         let address: usize = ((begin as usize).wrapping_add(2))
                             .wrapping_add((address as i8) as usize);
                         format!("${:04x}", address)
                     }
 
-                    _ => panic!(
-                        "unexpected addressing mode {:?} has ops-len 2. code {:02x}",
-                        ins.mode, ins.opcode
-                    ),
+                    _ => String::new(),
                 }
             }
             3 => {
@@ -716,17 +1023,18 @@ impl CPU {
 
                 if ins == JMP_A || ins == JSR_A {
                     format!("${:04x}", address)
-                } else if ins == JMP_I {
-                    let jmp_addr = if address & 0x00FF == 0x00FF {
-                        let lo = self.mem_bus.dbg_read(address);
-                        let hi = self.mem_bus.dbg_read(address & 0xFF00);
-                        (hi as u16) << 8 | (lo as u16)
-                    } else {
-                        make_u16!(
-                            self.mem_bus.dbg_read(address.wrapping_add(1)),
-                            self.mem_bus.dbg_read(address)
-                        )
-                    };
+                } else if ins.name == JMP_I.name && ins.mode == JMP_I.mode {
+                    let jmp_addr =
+                        if address & 0x00FF == 0x00FF && self.variant != Variant::Wdc65C02 {
+                            let lo = self.mem_bus.dbg_read(address);
+                            let hi = self.mem_bus.dbg_read(address & 0xFF00);
+                            (hi as u16) << 8 | (lo as u16)
+                        } else {
+                            make_u16!(
+                                self.mem_bus.dbg_read(address.wrapping_add(1)),
+                                self.mem_bus.dbg_read(address)
+                            )
+                        };
 
                     // let jmp_addr = cpu.mem_read_u16(address);
                     format!("(${:04x}) = {:04x}", address, jmp_addr)
@@ -746,10 +1054,18 @@ impl CPU {
                             "${:04x},Y @ {:04x} = {:02x}",
                             address, mem_addr, stored_value
                         ),
-                        _ => panic!(
-                            "unexpected addressing mode {:?} has ops-len 3. code {:02x}",
-                            ins.mode, ins.opcode
-                        ),
+                        AddressingMode::AbsoluteIndexedIndirect => {
+                            format!("(${:04x},X)", address)
+                        }
+                        AddressingMode::ZeroPageRelative => {
+                            let offset = address_hi as i8;
+                            format!(
+                                "${:02x},${:04x}",
+                                address_lo,
+                                begin.wrapping_add(3).wrapping_add(offset as u16)
+                            )
+                        }
+                        _ => String::new(),
                     }
                 }
             }
@@ -761,7 +1077,13 @@ impl CPU {
             .map(|z| format!("{:02x}", z))
             .collect::<Vec<String>>()
             .join(" ");
-        let asm_str = format!("{:04x}  {:8} {: >4} {}", begin, hex_str, ins.name, tmp)
+        // nestest.log marks unofficial opcodes with a leading *
+        let name = if ins.unofficial {
+            format!("*{}", ins.name)
+        } else {
+            ins.name.to_string()
+        };
+        let asm_str = format!("{:04x}  {:8} {: >4} {}", begin, hex_str, name, tmp)
             .trim()
             .to_string();
 
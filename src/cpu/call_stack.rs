@@ -0,0 +1,108 @@
+// shadow call stack kept next to the real one. JSR and interrupts push a frame, RTS/RTI pop it
+// when they come back where the frame says they should. anything else that moves the stack under
+// a frame (TXS, PLA/PLA to drop a return address, RTS through a pushed jump table address) puts
+// it out of sync until a return lines up with a frame again
+use crate::cpu::instructions::{Instruction, InstructionName as IN};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameKind {
+    Jsr,
+    Nmi,
+    Irq,
+    Brk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    // the JSR or BRK, or the instruction an interrupt arrived before
+    pub site: u16,
+    pub target: u16,
+    // where the matching RTS/RTI lands
+    pub ret: u16,
+    // stack pointer right after the return address (and status) was pushed
+    pub sp: u8,
+}
+
+#[derive(Debug, Default)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    desync: Option<String>,
+}
+
+impl CallStack {
+    // innermost call last
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn desync(&self) -> Option<&str> {
+        self.desync.as_deref()
+    }
+
+    pub fn backtrace(&self) -> Result<&[Frame], &str> {
+        match &self.desync {
+            Some(reason) => Err(reason),
+            None => Ok(&self.frames),
+        }
+    }
+
+    pub(crate) fn call(&mut self, frame: Frame) {
+        // frames at or below the new one were overwritten by it, the stack pointer was moved
+        // without us noticing (it's only a byte, wrapping counts too)
+        self.frames.retain(|f| f.sp > frame.sp);
+        self.frames.push(frame);
+    }
+
+    // `site` is the RTS/RTI, `sp` the stack pointer before it pulled anything and `ret` the
+    // address it carries on from
+    pub(crate) fn ret(&mut self, rti: bool, site: u16, sp: u8, ret: u16) {
+        let matches = |f: &Frame| f.sp == sp && f.ret == ret && (f.kind != FrameKind::Jsr) == rti;
+
+        match self.frames.iter().rposition(matches) {
+            Some(i) => {
+                self.frames.truncate(i);
+                self.desync = None;
+            }
+            None => {
+                let name = if rti { "RTI" } else { "RTS" };
+                self.desync = Some(format!(
+                    "{name} at {site:04X} went to {ret:04X}, which no {} pushed",
+                    if rti { "interrupt" } else { "JSR" }
+                ));
+                self.drop_pulled(sp.wrapping_add(if rti { 3 } else { 2 }));
+            }
+        }
+    }
+
+    // catches everything else that moves the stack pointer under a frame
+    pub(crate) fn after_instruction(&mut self, ins: Instruction, pc: u16, sp_before: u8, sp: u8) {
+        let Some(top) = self.frames.last() else {
+            return;
+        };
+
+        match ins.name {
+            IN::JSR | IN::RTS | IN::RTI | IN::BRK => {}
+            IN::TXS | IN::TAS if sp != sp_before => {
+                self.desync = Some(format!(
+                    "{} at {pc:04X} moved the stack pointer from {sp_before:02X} to {sp:02X}",
+                    ins.name
+                ));
+                self.drop_pulled(sp);
+            }
+            _ if sp > top.sp => {
+                self.desync = Some(format!(
+                    "{} at {pc:04X} pulled the return address pushed at {:04X}",
+                    ins.name, top.site
+                ));
+                self.drop_pulled(sp);
+            }
+            _ => {}
+        }
+    }
+
+    // frames whose return address is above the stack pointer now are gone
+    fn drop_pulled(&mut self, sp: u8) {
+        self.frames.retain(|f| f.sp >= sp);
+    }
+}
@@ -15,9 +15,36 @@ pub enum AddressingMode {
     Indirect,        // I
     IndexedIndirect, // IX
     IndirectIndexed, // IY
+
+    // 65C02 only
+    ZeroPageIndirect,        // ZI
+    AbsoluteIndexedIndirect, // IAX
+    ZeroPageRelative,        // ZR
+}
+
+// which chip the core behaves as. they share the official NMOS instruction set and differ in
+// decimal mode, the opcodes outside it, and a few timings
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Variant {
+    // NES / Famicom cpu: an NMOS 6502 with the decimal adder disconnected
+    #[default]
+    Ricoh2A03,
+    Nmos6502,
+    Wdc65C02,
+}
+
+impl Variant {
+    pub const fn decode(self, opcode: u8) -> Instruction {
+        match self {
+            // the decimal adder is the only difference between the 2A03 and an NMOS 6502, they
+            // share one opcode matrix (unofficial opcodes included) on purpose
+            Variant::Ricoh2A03 | Variant::Nmos6502 => get_instruction(opcode),
+            Variant::Wdc65C02 => wdc65c02::get_instruction(opcode),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, strum_macros::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, strum_macros::Display, strum_macros::EnumString)]
 pub enum InstructionName {
     ADC,
     AND,
@@ -75,6 +102,44 @@ pub enum InstructionName {
     TXA,
     TXS,
     TYA,
+
+    // unofficial
+    ALR,
+    ANC,
+    ARR,
+    AXS,
+    DCP,
+    ISB,
+    JAM,
+    LAS,
+    LAX,
+    LXA,
+    RLA,
+    RRA,
+    SAX,
+    SHA,
+    SHX,
+    SHY,
+    SLO,
+    SRE,
+    TAS,
+    XAA,
+
+    // 65C02
+    BBR,
+    BBS,
+    BRA,
+    PHX,
+    PHY,
+    PLX,
+    PLY,
+    RMB,
+    SMB,
+    STP,
+    STZ,
+    TRB,
+    TSB,
+    WAI,
 }
 
 use AddressingMode::*;
@@ -87,6 +152,7 @@ pub struct Instruction {
     pub mode: AddressingMode,
     pub bytes: u8,
     pub cycles: u8,
+    pub unofficial: bool,
 }
 
 impl Instruction {
@@ -103,6 +169,24 @@ impl Instruction {
             mode,
             bytes,
             cycles,
+            unofficial: false,
+        }
+    }
+
+    pub const fn unofficial(
+        name: InstructionName,
+        opcode: u8,
+        mode: AddressingMode,
+        bytes: u8,
+        cycles: u8,
+    ) -> Self {
+        Instruction {
+            name,
+            opcode,
+            mode,
+            bytes,
+            cycles,
+            unofficial: true,
         }
     }
 }
@@ -129,10 +213,10 @@ pub const AND_IY: Instruction = Instruction::new(IN::AND, 0x31, IndirectIndexed,
 
 // ASL: arithmetic shift left
 pub const ASL_AC: Instruction = Instruction::new(IN::ASL, 0x0a, Accumulator, 1, 2);
-pub const ASL_Z: Instruction = Instruction::new(IN::ASL, 0x06, ZeroPage, 2, 3);
-pub const ASL_ZX: Instruction = Instruction::new(IN::ASL, 0x16, ZeroPageX, 2, 4);
-pub const ASL_A: Instruction = Instruction::new(IN::ASL, 0x0e, Absolute, 3, 4);
-pub const ASL_AX: Instruction = Instruction::new(IN::ASL, 0x1e, AbsoluteX, 3, 4);
+pub const ASL_Z: Instruction = Instruction::new(IN::ASL, 0x06, ZeroPage, 2, 5);
+pub const ASL_ZX: Instruction = Instruction::new(IN::ASL, 0x16, ZeroPageX, 2, 6);
+pub const ASL_A: Instruction = Instruction::new(IN::ASL, 0x0e, Absolute, 3, 6);
+pub const ASL_AX: Instruction = Instruction::new(IN::ASL, 0x1e, AbsoluteX, 3, 7);
 
 // BCC: branch if carry clear
 pub const BCC: Instruction = Instruction::new(IN::BCC, 0x90, Relative, 2, 2); // ex
@@ -370,6 +454,159 @@ pub const TXS: Instruction = Instruction::new(IN::TXS, 0x9a, Implicit, 1, 2);
 // TYA: transfer y to accumulator
 pub const TYA: Instruction = Instruction::new(IN::TYA, 0x98, Implicit, 1, 2);
 
+/* UNOFFICIAL INSTRUCTIONS =========================================
+names follow nestest.log / the nesdev wiki "unofficial opcodes" page
+================================================================= */
+
+// ALR: and immediate then lsr accumulator
+pub const ALR_IM: Instruction = Instruction::unofficial(IN::ALR, 0x4b, Immediate, 2, 2);
+
+// ANC: and immediate, copy bit 7 into carry
+pub const ANC_IM: Instruction = Instruction::unofficial(IN::ANC, 0x0b, Immediate, 2, 2);
+pub const ANC_IM_2B: Instruction = Instruction::unofficial(IN::ANC, 0x2b, Immediate, 2, 2);
+
+// ARR: and immediate then ror accumulator, with odd C/V flags
+pub const ARR_IM: Instruction = Instruction::unofficial(IN::ARR, 0x6b, Immediate, 2, 2);
+
+// AXS: x = (a & x) - immediate, without borrow
+pub const AXS_IM: Instruction = Instruction::unofficial(IN::AXS, 0xcb, Immediate, 2, 2);
+
+// DCP: dec memory then cmp
+pub const DCP_Z: Instruction = Instruction::unofficial(IN::DCP, 0xc7, ZeroPage, 2, 5);
+pub const DCP_ZX: Instruction = Instruction::unofficial(IN::DCP, 0xd7, ZeroPageX, 2, 6);
+pub const DCP_A: Instruction = Instruction::unofficial(IN::DCP, 0xcf, Absolute, 3, 6);
+pub const DCP_AX: Instruction = Instruction::unofficial(IN::DCP, 0xdf, AbsoluteX, 3, 7);
+pub const DCP_AY: Instruction = Instruction::unofficial(IN::DCP, 0xdb, AbsoluteY, 3, 7);
+pub const DCP_IX: Instruction = Instruction::unofficial(IN::DCP, 0xc3, IndexedIndirect, 2, 8);
+pub const DCP_IY: Instruction = Instruction::unofficial(IN::DCP, 0xd3, IndirectIndexed, 2, 8);
+
+// ISB: inc memory then sbc (also known as ISC)
+pub const ISB_Z: Instruction = Instruction::unofficial(IN::ISB, 0xe7, ZeroPage, 2, 5);
+pub const ISB_ZX: Instruction = Instruction::unofficial(IN::ISB, 0xf7, ZeroPageX, 2, 6);
+pub const ISB_A: Instruction = Instruction::unofficial(IN::ISB, 0xef, Absolute, 3, 6);
+pub const ISB_AX: Instruction = Instruction::unofficial(IN::ISB, 0xff, AbsoluteX, 3, 7);
+pub const ISB_AY: Instruction = Instruction::unofficial(IN::ISB, 0xfb, AbsoluteY, 3, 7);
+pub const ISB_IX: Instruction = Instruction::unofficial(IN::ISB, 0xe3, IndexedIndirect, 2, 8);
+pub const ISB_IY: Instruction = Instruction::unofficial(IN::ISB, 0xf3, IndirectIndexed, 2, 8);
+
+// JAM: locks up the cpu (also known as KIL/HLT)
+pub const JAM_02: Instruction = Instruction::unofficial(IN::JAM, 0x02, Implicit, 1, 2);
+pub const JAM_12: Instruction = Instruction::unofficial(IN::JAM, 0x12, Implicit, 1, 2);
+pub const JAM_22: Instruction = Instruction::unofficial(IN::JAM, 0x22, Implicit, 1, 2);
+pub const JAM_32: Instruction = Instruction::unofficial(IN::JAM, 0x32, Implicit, 1, 2);
+pub const JAM_42: Instruction = Instruction::unofficial(IN::JAM, 0x42, Implicit, 1, 2);
+pub const JAM_52: Instruction = Instruction::unofficial(IN::JAM, 0x52, Implicit, 1, 2);
+pub const JAM_62: Instruction = Instruction::unofficial(IN::JAM, 0x62, Implicit, 1, 2);
+pub const JAM_72: Instruction = Instruction::unofficial(IN::JAM, 0x72, Implicit, 1, 2);
+pub const JAM_92: Instruction = Instruction::unofficial(IN::JAM, 0x92, Implicit, 1, 2);
+pub const JAM_B2: Instruction = Instruction::unofficial(IN::JAM, 0xb2, Implicit, 1, 2);
+pub const JAM_D2: Instruction = Instruction::unofficial(IN::JAM, 0xd2, Implicit, 1, 2);
+pub const JAM_F2: Instruction = Instruction::unofficial(IN::JAM, 0xf2, Implicit, 1, 2);
+
+// LAS: a = x = sp = memory & sp
+pub const LAS_AY: Instruction = Instruction::unofficial(IN::LAS, 0xbb, AbsoluteY, 3, 4); // ex
+
+// LAX: lda + ldx
+pub const LAX_Z: Instruction = Instruction::unofficial(IN::LAX, 0xa7, ZeroPage, 2, 3);
+pub const LAX_ZY: Instruction = Instruction::unofficial(IN::LAX, 0xb7, ZeroPageY, 2, 4);
+pub const LAX_A: Instruction = Instruction::unofficial(IN::LAX, 0xaf, Absolute, 3, 4);
+pub const LAX_AY: Instruction = Instruction::unofficial(IN::LAX, 0xbf, AbsoluteY, 3, 4); // ex
+pub const LAX_IX: Instruction = Instruction::unofficial(IN::LAX, 0xa3, IndexedIndirect, 2, 6);
+pub const LAX_IY: Instruction = Instruction::unofficial(IN::LAX, 0xb3, IndirectIndexed, 2, 5); // ex
+
+// LXA: a = x = (a | magic) & immediate (unstable)
+pub const LXA_IM: Instruction = Instruction::unofficial(IN::LXA, 0xab, Immediate, 2, 2);
+
+// NOP: unofficial nops, including SKB (2 byte) and IGN (3 byte) variants that do a dummy read
+pub const NOP_1A: Instruction = Instruction::unofficial(IN::NOP, 0x1a, Implicit, 1, 2);
+pub const NOP_3A: Instruction = Instruction::unofficial(IN::NOP, 0x3a, Implicit, 1, 2);
+pub const NOP_5A: Instruction = Instruction::unofficial(IN::NOP, 0x5a, Implicit, 1, 2);
+pub const NOP_7A: Instruction = Instruction::unofficial(IN::NOP, 0x7a, Implicit, 1, 2);
+pub const NOP_DA: Instruction = Instruction::unofficial(IN::NOP, 0xda, Implicit, 1, 2);
+pub const NOP_FA: Instruction = Instruction::unofficial(IN::NOP, 0xfa, Implicit, 1, 2);
+pub const NOP_IM_80: Instruction = Instruction::unofficial(IN::NOP, 0x80, Immediate, 2, 2);
+pub const NOP_IM_82: Instruction = Instruction::unofficial(IN::NOP, 0x82, Immediate, 2, 2);
+pub const NOP_IM_89: Instruction = Instruction::unofficial(IN::NOP, 0x89, Immediate, 2, 2);
+pub const NOP_IM_C2: Instruction = Instruction::unofficial(IN::NOP, 0xc2, Immediate, 2, 2);
+pub const NOP_IM_E2: Instruction = Instruction::unofficial(IN::NOP, 0xe2, Immediate, 2, 2);
+pub const NOP_Z_04: Instruction = Instruction::unofficial(IN::NOP, 0x04, ZeroPage, 2, 3);
+pub const NOP_Z_44: Instruction = Instruction::unofficial(IN::NOP, 0x44, ZeroPage, 2, 3);
+pub const NOP_Z_64: Instruction = Instruction::unofficial(IN::NOP, 0x64, ZeroPage, 2, 3);
+pub const NOP_ZX_14: Instruction = Instruction::unofficial(IN::NOP, 0x14, ZeroPageX, 2, 4);
+pub const NOP_ZX_34: Instruction = Instruction::unofficial(IN::NOP, 0x34, ZeroPageX, 2, 4);
+pub const NOP_ZX_54: Instruction = Instruction::unofficial(IN::NOP, 0x54, ZeroPageX, 2, 4);
+pub const NOP_ZX_74: Instruction = Instruction::unofficial(IN::NOP, 0x74, ZeroPageX, 2, 4);
+pub const NOP_ZX_D4: Instruction = Instruction::unofficial(IN::NOP, 0xd4, ZeroPageX, 2, 4);
+pub const NOP_ZX_F4: Instruction = Instruction::unofficial(IN::NOP, 0xf4, ZeroPageX, 2, 4);
+pub const NOP_A_0C: Instruction = Instruction::unofficial(IN::NOP, 0x0c, Absolute, 3, 4);
+pub const NOP_AX_1C: Instruction = Instruction::unofficial(IN::NOP, 0x1c, AbsoluteX, 3, 4); // ex
+pub const NOP_AX_3C: Instruction = Instruction::unofficial(IN::NOP, 0x3c, AbsoluteX, 3, 4); // ex
+pub const NOP_AX_5C: Instruction = Instruction::unofficial(IN::NOP, 0x5c, AbsoluteX, 3, 4); // ex
+pub const NOP_AX_7C: Instruction = Instruction::unofficial(IN::NOP, 0x7c, AbsoluteX, 3, 4); // ex
+pub const NOP_AX_DC: Instruction = Instruction::unofficial(IN::NOP, 0xdc, AbsoluteX, 3, 4); // ex
+pub const NOP_AX_FC: Instruction = Instruction::unofficial(IN::NOP, 0xfc, AbsoluteX, 3, 4); // ex
+
+// RLA: rol memory then and
+pub const RLA_Z: Instruction = Instruction::unofficial(IN::RLA, 0x27, ZeroPage, 2, 5);
+pub const RLA_ZX: Instruction = Instruction::unofficial(IN::RLA, 0x37, ZeroPageX, 2, 6);
+pub const RLA_A: Instruction = Instruction::unofficial(IN::RLA, 0x2f, Absolute, 3, 6);
+pub const RLA_AX: Instruction = Instruction::unofficial(IN::RLA, 0x3f, AbsoluteX, 3, 7);
+pub const RLA_AY: Instruction = Instruction::unofficial(IN::RLA, 0x3b, AbsoluteY, 3, 7);
+pub const RLA_IX: Instruction = Instruction::unofficial(IN::RLA, 0x23, IndexedIndirect, 2, 8);
+pub const RLA_IY: Instruction = Instruction::unofficial(IN::RLA, 0x33, IndirectIndexed, 2, 8);
+
+// RRA: ror memory then adc
+pub const RRA_Z: Instruction = Instruction::unofficial(IN::RRA, 0x67, ZeroPage, 2, 5);
+pub const RRA_ZX: Instruction = Instruction::unofficial(IN::RRA, 0x77, ZeroPageX, 2, 6);
+pub const RRA_A: Instruction = Instruction::unofficial(IN::RRA, 0x6f, Absolute, 3, 6);
+pub const RRA_AX: Instruction = Instruction::unofficial(IN::RRA, 0x7f, AbsoluteX, 3, 7);
+pub const RRA_AY: Instruction = Instruction::unofficial(IN::RRA, 0x7b, AbsoluteY, 3, 7);
+pub const RRA_IX: Instruction = Instruction::unofficial(IN::RRA, 0x63, IndexedIndirect, 2, 8);
+pub const RRA_IY: Instruction = Instruction::unofficial(IN::RRA, 0x73, IndirectIndexed, 2, 8);
+
+// SAX: store a & x
+pub const SAX_Z: Instruction = Instruction::unofficial(IN::SAX, 0x87, ZeroPage, 2, 3);
+pub const SAX_ZY: Instruction = Instruction::unofficial(IN::SAX, 0x97, ZeroPageY, 2, 4);
+pub const SAX_A: Instruction = Instruction::unofficial(IN::SAX, 0x8f, Absolute, 3, 4);
+pub const SAX_IX: Instruction = Instruction::unofficial(IN::SAX, 0x83, IndexedIndirect, 2, 6);
+
+// SBC: unofficial copy of SBC_IM
+pub const SBC_IM_EB: Instruction = Instruction::unofficial(IN::SBC, 0xeb, Immediate, 2, 2);
+
+// SHA: store a & x & (hi + 1) (unstable, also known as AHX)
+pub const SHA_AY: Instruction = Instruction::unofficial(IN::SHA, 0x9f, AbsoluteY, 3, 5);
+pub const SHA_IY: Instruction = Instruction::unofficial(IN::SHA, 0x93, IndirectIndexed, 2, 6);
+
+// SHX: store x & (hi + 1) (unstable)
+pub const SHX_AY: Instruction = Instruction::unofficial(IN::SHX, 0x9e, AbsoluteY, 3, 5);
+
+// SHY: store y & (hi + 1) (unstable)
+pub const SHY_AX: Instruction = Instruction::unofficial(IN::SHY, 0x9c, AbsoluteX, 3, 5);
+
+// SLO: asl memory then ora
+pub const SLO_Z: Instruction = Instruction::unofficial(IN::SLO, 0x07, ZeroPage, 2, 5);
+pub const SLO_ZX: Instruction = Instruction::unofficial(IN::SLO, 0x17, ZeroPageX, 2, 6);
+pub const SLO_A: Instruction = Instruction::unofficial(IN::SLO, 0x0f, Absolute, 3, 6);
+pub const SLO_AX: Instruction = Instruction::unofficial(IN::SLO, 0x1f, AbsoluteX, 3, 7);
+pub const SLO_AY: Instruction = Instruction::unofficial(IN::SLO, 0x1b, AbsoluteY, 3, 7);
+pub const SLO_IX: Instruction = Instruction::unofficial(IN::SLO, 0x03, IndexedIndirect, 2, 8);
+pub const SLO_IY: Instruction = Instruction::unofficial(IN::SLO, 0x13, IndirectIndexed, 2, 8);
+
+// SRE: lsr memory then eor
+pub const SRE_Z: Instruction = Instruction::unofficial(IN::SRE, 0x47, ZeroPage, 2, 5);
+pub const SRE_ZX: Instruction = Instruction::unofficial(IN::SRE, 0x57, ZeroPageX, 2, 6);
+pub const SRE_A: Instruction = Instruction::unofficial(IN::SRE, 0x4f, Absolute, 3, 6);
+pub const SRE_AX: Instruction = Instruction::unofficial(IN::SRE, 0x5f, AbsoluteX, 3, 7);
+pub const SRE_AY: Instruction = Instruction::unofficial(IN::SRE, 0x5b, AbsoluteY, 3, 7);
+pub const SRE_IX: Instruction = Instruction::unofficial(IN::SRE, 0x43, IndexedIndirect, 2, 8);
+pub const SRE_IY: Instruction = Instruction::unofficial(IN::SRE, 0x53, IndirectIndexed, 2, 8);
+
+// TAS: sp = a & x, store sp & (hi + 1) (unstable, also known as SHS)
+pub const TAS_AY: Instruction = Instruction::unofficial(IN::TAS, 0x9b, AbsoluteY, 3, 5);
+
+// XAA: a = (a | magic) & x & immediate (unstable, also known as ANE)
+pub const XAA_IM: Instruction = Instruction::unofficial(IN::XAA, 0x8b, Immediate, 2, 2);
+
 pub const fn get_instruction(opcode: u8) -> Instruction {
     match opcode {
         0x69 => ADC_IM,
@@ -523,7 +760,252 @@ pub const fn get_instruction(opcode: u8) -> Instruction {
         0x8a => TXA,
         0x9a => TXS,
         0x98 => TYA,
-        _ => NOP,
-        // _ => panic!("bad opcode"),
+        0x4b => ALR_IM,
+        0x0b => ANC_IM,
+        0x2b => ANC_IM_2B,
+        0x6b => ARR_IM,
+        0xcb => AXS_IM,
+        0xc7 => DCP_Z,
+        0xd7 => DCP_ZX,
+        0xcf => DCP_A,
+        0xdf => DCP_AX,
+        0xdb => DCP_AY,
+        0xc3 => DCP_IX,
+        0xd3 => DCP_IY,
+        0xe7 => ISB_Z,
+        0xf7 => ISB_ZX,
+        0xef => ISB_A,
+        0xff => ISB_AX,
+        0xfb => ISB_AY,
+        0xe3 => ISB_IX,
+        0xf3 => ISB_IY,
+        0x02 => JAM_02,
+        0x12 => JAM_12,
+        0x22 => JAM_22,
+        0x32 => JAM_32,
+        0x42 => JAM_42,
+        0x52 => JAM_52,
+        0x62 => JAM_62,
+        0x72 => JAM_72,
+        0x92 => JAM_92,
+        0xb2 => JAM_B2,
+        0xd2 => JAM_D2,
+        0xf2 => JAM_F2,
+        0xbb => LAS_AY,
+        0xa7 => LAX_Z,
+        0xb7 => LAX_ZY,
+        0xaf => LAX_A,
+        0xbf => LAX_AY,
+        0xa3 => LAX_IX,
+        0xb3 => LAX_IY,
+        0xab => LXA_IM,
+        0x1a => NOP_1A,
+        0x3a => NOP_3A,
+        0x5a => NOP_5A,
+        0x7a => NOP_7A,
+        0xda => NOP_DA,
+        0xfa => NOP_FA,
+        0x80 => NOP_IM_80,
+        0x82 => NOP_IM_82,
+        0x89 => NOP_IM_89,
+        0xc2 => NOP_IM_C2,
+        0xe2 => NOP_IM_E2,
+        0x04 => NOP_Z_04,
+        0x44 => NOP_Z_44,
+        0x64 => NOP_Z_64,
+        0x14 => NOP_ZX_14,
+        0x34 => NOP_ZX_34,
+        0x54 => NOP_ZX_54,
+        0x74 => NOP_ZX_74,
+        0xd4 => NOP_ZX_D4,
+        0xf4 => NOP_ZX_F4,
+        0x0c => NOP_A_0C,
+        0x1c => NOP_AX_1C,
+        0x3c => NOP_AX_3C,
+        0x5c => NOP_AX_5C,
+        0x7c => NOP_AX_7C,
+        0xdc => NOP_AX_DC,
+        0xfc => NOP_AX_FC,
+        0x27 => RLA_Z,
+        0x37 => RLA_ZX,
+        0x2f => RLA_A,
+        0x3f => RLA_AX,
+        0x3b => RLA_AY,
+        0x23 => RLA_IX,
+        0x33 => RLA_IY,
+        0x67 => RRA_Z,
+        0x77 => RRA_ZX,
+        0x6f => RRA_A,
+        0x7f => RRA_AX,
+        0x7b => RRA_AY,
+        0x63 => RRA_IX,
+        0x73 => RRA_IY,
+        0x87 => SAX_Z,
+        0x97 => SAX_ZY,
+        0x8f => SAX_A,
+        0x83 => SAX_IX,
+        0xeb => SBC_IM_EB,
+        0x9f => SHA_AY,
+        0x93 => SHA_IY,
+        0x9e => SHX_AY,
+        0x9c => SHY_AX,
+        0x07 => SLO_Z,
+        0x17 => SLO_ZX,
+        0x0f => SLO_A,
+        0x1f => SLO_AX,
+        0x1b => SLO_AY,
+        0x03 => SLO_IX,
+        0x13 => SLO_IY,
+        0x47 => SRE_Z,
+        0x57 => SRE_ZX,
+        0x4f => SRE_A,
+        0x5f => SRE_AX,
+        0x5b => SRE_AY,
+        0x43 => SRE_IX,
+        0x53 => SRE_IY,
+        0x9b => TAS_AY,
+        0x8b => XAA_IM,
+    }
+}
+
+/* WDC 65C02 =======================================================
+every NMOS opcode that isn't listed here decodes the same on the 65C02
+================================================================= */
+pub mod wdc65c02 {
+    use super::{AddressingMode::*, Instruction, InstructionName as IN};
+
+    // (zp) addressing
+    pub const ORA_ZI: Instruction = Instruction::new(IN::ORA, 0x12, ZeroPageIndirect, 2, 5);
+    pub const AND_ZI: Instruction = Instruction::new(IN::AND, 0x32, ZeroPageIndirect, 2, 5);
+    pub const EOR_ZI: Instruction = Instruction::new(IN::EOR, 0x52, ZeroPageIndirect, 2, 5);
+    pub const ADC_ZI: Instruction = Instruction::new(IN::ADC, 0x72, ZeroPageIndirect, 2, 5);
+    pub const STA_ZI: Instruction = Instruction::new(IN::STA, 0x92, ZeroPageIndirect, 2, 5);
+    pub const LDA_ZI: Instruction = Instruction::new(IN::LDA, 0xb2, ZeroPageIndirect, 2, 5);
+    pub const CMP_ZI: Instruction = Instruction::new(IN::CMP, 0xd2, ZeroPageIndirect, 2, 5);
+    pub const SBC_ZI: Instruction = Instruction::new(IN::SBC, 0xf2, ZeroPageIndirect, 2, 5);
+
+    // BIT: new addressing modes, the immediate form only sets Z
+    pub const BIT_IM: Instruction = Instruction::new(IN::BIT, 0x89, Immediate, 2, 2);
+    pub const BIT_ZX: Instruction = Instruction::new(IN::BIT, 0x34, ZeroPageX, 2, 4);
+    pub const BIT_AX: Instruction = Instruction::new(IN::BIT, 0x3c, AbsoluteX, 3, 4); // ex
+
+    // INC/DEC: accumulator forms
+    pub const INC_AC: Instruction = Instruction::new(IN::INC, 0x1a, Accumulator, 1, 2);
+    pub const DEC_AC: Instruction = Instruction::new(IN::DEC, 0x3a, Accumulator, 1, 2);
+
+    // shifts and rotates on abs,X only pay the extra cycle on a page cross
+    pub const ASL_AX: Instruction = Instruction::new(IN::ASL, 0x1e, AbsoluteX, 3, 6); // ex
+    pub const ROL_AX: Instruction = Instruction::new(IN::ROL, 0x3e, AbsoluteX, 3, 6); // ex
+    pub const LSR_AX: Instruction = Instruction::new(IN::LSR, 0x5e, AbsoluteX, 3, 6); // ex
+    pub const ROR_AX: Instruction = Instruction::new(IN::ROR, 0x7e, AbsoluteX, 3, 6); // ex
+
+    // JMP: the indirect page wrap bug is fixed at the cost of a cycle, plus (abs,X)
+    pub const JMP_I: Instruction = Instruction::new(IN::JMP, 0x6c, Indirect, 3, 6);
+    pub const JMP_IAX: Instruction = Instruction::new(IN::JMP, 0x7c, AbsoluteIndexedIndirect, 3, 6);
+
+    // BRA: branch always
+    pub const BRA: Instruction = Instruction::new(IN::BRA, 0x80, Relative, 2, 2); // ex
+
+    // PHX, PHY, PLX, PLY: push/pull the index registers
+    pub const PHX: Instruction = Instruction::new(IN::PHX, 0xda, Implicit, 1, 3);
+    pub const PHY: Instruction = Instruction::new(IN::PHY, 0x5a, Implicit, 1, 3);
+    pub const PLX: Instruction = Instruction::new(IN::PLX, 0xfa, Implicit, 1, 4);
+    pub const PLY: Instruction = Instruction::new(IN::PLY, 0x7a, Implicit, 1, 4);
+
+    // STZ: store zero
+    pub const STZ_Z: Instruction = Instruction::new(IN::STZ, 0x64, ZeroPage, 2, 3);
+    pub const STZ_ZX: Instruction = Instruction::new(IN::STZ, 0x74, ZeroPageX, 2, 4);
+    pub const STZ_A: Instruction = Instruction::new(IN::STZ, 0x9c, Absolute, 3, 4);
+    pub const STZ_AX: Instruction = Instruction::new(IN::STZ, 0x9e, AbsoluteX, 3, 5);
+
+    // TRB, TSB: test and reset/set memory bits with the accumulator
+    pub const TSB_Z: Instruction = Instruction::new(IN::TSB, 0x04, ZeroPage, 2, 5);
+    pub const TSB_A: Instruction = Instruction::new(IN::TSB, 0x0c, Absolute, 3, 6);
+    pub const TRB_Z: Instruction = Instruction::new(IN::TRB, 0x14, ZeroPage, 2, 5);
+    pub const TRB_A: Instruction = Instruction::new(IN::TRB, 0x1c, Absolute, 3, 6);
+
+    // RMB, SMB: reset/set memory bit, the bit number is bits 4-6 of the opcode
+    pub const fn rmb(opcode: u8) -> Instruction {
+        Instruction::new(IN::RMB, opcode, ZeroPage, 2, 5)
+    }
+
+    pub const fn smb(opcode: u8) -> Instruction {
+        Instruction::new(IN::SMB, opcode, ZeroPage, 2, 5)
+    }
+
+    // BBR, BBS: branch on memory bit reset/set, the bit number is bits 4-6 of the opcode
+    pub const fn bbr(opcode: u8) -> Instruction {
+        Instruction::new(IN::BBR, opcode, ZeroPageRelative, 3, 5) // ex
+    }
+
+    pub const fn bbs(opcode: u8) -> Instruction {
+        Instruction::new(IN::BBS, opcode, ZeroPageRelative, 3, 5) // ex
+    }
+
+    // WAI: sleep until an interrupt, STP: stop until reset
+    pub const WAI: Instruction = Instruction::new(IN::WAI, 0xcb, Implicit, 1, 3);
+    pub const STP: Instruction = Instruction::new(IN::STP, 0xdb, Implicit, 1, 3);
+
+    // every other opcode is a documented nop, sized by its column
+    pub const fn nop(opcode: u8) -> Instruction {
+        match opcode {
+            0x44 => Instruction::new(IN::NOP, opcode, ZeroPage, 2, 3),
+            0x54 | 0xd4 | 0xf4 => Instruction::new(IN::NOP, opcode, ZeroPageX, 2, 4),
+            0x5c => Instruction::new(IN::NOP, opcode, Absolute, 3, 8),
+            0xdc | 0xfc => Instruction::new(IN::NOP, opcode, Absolute, 3, 4),
+            _ if opcode & 0x0f == 0x02 => Instruction::new(IN::NOP, opcode, Immediate, 2, 2),
+            _ => Instruction::new(IN::NOP, opcode, Implicit, 1, 1),
+        }
+    }
+
+    pub const fn get_instruction(opcode: u8) -> Instruction {
+        match opcode {
+            0x12 => ORA_ZI,
+            0x32 => AND_ZI,
+            0x52 => EOR_ZI,
+            0x72 => ADC_ZI,
+            0x92 => STA_ZI,
+            0xb2 => LDA_ZI,
+            0xd2 => CMP_ZI,
+            0xf2 => SBC_ZI,
+            0x89 => BIT_IM,
+            0x34 => BIT_ZX,
+            0x3c => BIT_AX,
+            0x1a => INC_AC,
+            0x3a => DEC_AC,
+            0x1e => ASL_AX,
+            0x3e => ROL_AX,
+            0x5e => LSR_AX,
+            0x7e => ROR_AX,
+            0x6c => JMP_I,
+            0x7c => JMP_IAX,
+            0x80 => BRA,
+            0xda => PHX,
+            0x5a => PHY,
+            0xfa => PLX,
+            0x7a => PLY,
+            0x64 => STZ_Z,
+            0x74 => STZ_ZX,
+            0x9c => STZ_A,
+            0x9e => STZ_AX,
+            0x04 => TSB_Z,
+            0x0c => TSB_A,
+            0x14 => TRB_Z,
+            0x1c => TRB_A,
+            0xcb => WAI,
+            0xdb => STP,
+            _ if opcode & 0x8f == 0x07 => rmb(opcode),
+            _ if opcode & 0x8f == 0x87 => smb(opcode),
+            _ if opcode & 0x8f == 0x0f => bbr(opcode),
+            _ if opcode & 0x8f == 0x8f => bbs(opcode),
+            _ => {
+                let ins = super::get_instruction(opcode);
+                if ins.unofficial {
+                    nop(opcode)
+                } else {
+                    ins
+                }
+            }
+        }
     }
 }
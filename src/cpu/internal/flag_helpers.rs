@@ -1,6 +1,9 @@
-use crate::cpu::{StatusFlags, CPU};
+use crate::{
+    cpu::{StatusFlags, CPU},
+    memory::memory_bus::Bus,
+};
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     pub fn set_status_bit(&mut self, bit: StatusFlags, val: u8) {
         self.flags.set(bit, val == 1);
     }
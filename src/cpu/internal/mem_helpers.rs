@@ -1,59 +1,127 @@
 use crate::{
     cpu::{
-        instructions::{AddressingMode, Instruction},
-        CPU,
+        instructions::{AddressingMode, Instruction, InstructionName},
+        Variant, CPU,
     },
-    make_u16, LogEvent,
+    make_u16,
+    memory::memory_bus::{Bus, ReadKind},
+    LogEvent,
 };
 use LogEvent as LE;
 
-impl CPU {
+pub fn page_crossed(a: u16, b: u16) -> bool {
+    (a ^ b) & 0xff00 != 0
+}
+
+// what an instruction does with its operand, decides which dummy accesses it makes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    Modify,
+}
+
+impl<B: Bus> CPU<B> {
+    // every cpu bus access goes through here. in cycle-stepped mode each one is a cpu cycle,
+    // so the bus is clocked before the access lands
+    pub fn bus_read(&mut self, addr: u16) -> u8 {
+        self.bus_read_as(addr, ReadKind::Other)
+    }
+
+    pub fn bus_read_as(&mut self, addr: u16, kind: ReadKind) -> u8 {
+        self.step_bus_cycle();
+        self.mem_bus.read_as(addr, kind)
+    }
+
+    pub fn bus_write(&mut self, addr: u16, val: u8) {
+        self.step_bus_cycle();
+        self.mem_bus.write(addr, val);
+    }
+
+    // low byte first, the order the 6502 puts them on the bus
+    pub fn bus_read_u16(&mut self, addr: u16, kind: ReadKind) -> u16 {
+        let lo = self.bus_read_as(addr, kind);
+        make_u16!(self.bus_read_as(addr.wrapping_add(1), kind), lo)
+    }
+
+    // the logged accesses. reads are either the data an instruction works on or intermediate
+    // (pointers, dummy reads); opcode, operand and stack accesses have their own events
+    pub fn mem_read(&mut self, addr: u16) -> u8 {
+        let val = self.bus_read_as(addr, ReadKind::Data);
+        self.log_event(LE::MemoryRead(addr, val));
+        val
+    }
+
+    // the read an operand resolves to, which the code/data log wants to know came through a
+    // pointer
+    fn mem_read_resolved(&mut self, addr: u16, mode: AddressingMode) -> u8 {
+        use AddressingMode as M;
+
+        let kind = match mode {
+            M::IndexedIndirect | M::IndirectIndexed | M::ZeroPageIndirect => ReadKind::IndirectData,
+            _ => ReadKind::Data,
+        };
+        let val = self.bus_read_as(addr, kind);
+        self.log_event(LE::MemoryRead(addr, val));
+        val
+    }
+
+    pub fn mem_read_intermediate(&mut self, addr: u16) -> u8 {
+        let val = self.bus_read(addr);
+        self.log_event(LE::MemoryReadIntermediate(addr, val));
+        val
+    }
+
+    // pointers are data as far as the code/data log goes, JMP ($nnnn) reads them out of rom
+    pub fn mem_read_pointer(&mut self, addr: u16) -> u8 {
+        let val = self.bus_read_as(addr, ReadKind::Data);
+        self.log_event(LE::MemoryReadIntermediate(addr, val));
+        val
+    }
+
+    pub fn mem_write(&mut self, addr: u16, val: u8) {
+        self.bus_write(addr, val);
+        self.log_event(LE::MemoryWrite(val, addr));
+    }
+
+    fn step_bus_cycle(&mut self) {
+        if self.cycle_stepped {
+            self.mem_bus.tick(1);
+            self.bus_cycle_count += 1;
+        }
+    }
+
     // INCREMENTS PC
     pub fn mem_read_pc_u8(&mut self) -> u8 {
-        self.program_counter += 1;
-        self.mem_bus.read(self.program_counter - 1)
+        self.program_counter = self.program_counter.wrapping_add(1);
+        let operand = self.bus_read_as(self.program_counter.wrapping_sub(1), ReadKind::Operand);
+        self.log_event(LE::OperandFetch(operand as u16));
+        operand
     }
 
     // INCREMENTS PC
     pub fn mem_read_pc_u16(&mut self) -> u16 {
-        self.program_counter += 2;
-        let operand = self.mem_bus.read_16bit(self.program_counter - 2);
-        
-        if let Some(l) = self.logger.as_mut() {
-            l.log_event(LE::OperandFetch(operand));
-        }
-
+        self.program_counter = self.program_counter.wrapping_add(2);
+        let operand = self.bus_read_u16(self.program_counter.wrapping_sub(2), ReadKind::Operand);
+        self.log_event(LE::OperandFetch(operand));
         operand
-        // make16!(
-        //     self.mem_bus.read(self.program_counter - 1),
-        //     self.mem_bus.read(self.program_counter - 2)
-        // )
     }
 
+    // the effective address for a mode without going through the operand fetch (so it doesn't
+    // move pc), zero page modes take an 8 bit base. the decode tables only pair the address
+    // helpers with modes that have one, any other mode is a bug in a table
     pub fn get_addr_8bit(&mut self, address: u8, mode: AddressingMode) -> u16 {
         use AddressingMode as M;
         match mode {
             M::ZeroPage => address as u16,
             M::ZeroPageX => self.reg_x.wrapping_add(address) as u16,
             M::ZeroPageY => self.reg_y.wrapping_add(address) as u16,
-            M::IndexedIndirect => {
-                let hi = self.get_addr_8bit(address.wrapping_add(1), M::ZeroPageX);
-                let lo = self.get_addr_8bit(address, M::ZeroPageX);
-                make_u16!(self.mem_bus.read(hi), self.mem_bus.read(lo))
-            }
-            M::IndirectIndexed => {
-                // TODO add a method for this
-                let hi = self.get_addr_8bit(address.wrapping_add(1), M::ZeroPage);
-                let lo = self.get_addr_8bit(address, M::ZeroPage);
-                make_u16!(self.mem_bus.read(hi), self.mem_bus.read(lo))
-                    .wrapping_add(self.reg_y as u16)
-            }
-            M::Relative | M::Immediate | M::Accumulator | M::Implicit => {
-                panic!("{:?} does not need memory address", mode)
-            }
-            _ => {
-                panic!("wrong mode, use get_addr_16bit for {:?}", mode)
-            }
+            M::IndexedIndirect => self.read_zero_page_ptr(self.reg_x.wrapping_add(address)),
+            M::IndirectIndexed => self
+                .read_zero_page_ptr(address)
+                .wrapping_add(self.reg_y as u16),
+            M::ZeroPageIndirect => self.read_zero_page_ptr(address),
+            _ => unreachable!("{mode:?} has no address"),
         }
     }
 
@@ -61,149 +129,203 @@ impl CPU {
         use AddressingMode as M;
         match mode {
             M::Indirect => {
-                self.mem_bus.read_16bit(address)
-                // make_u16!(self.mem_bus.read(address.wrapping_add(1)), self.mem_bus.read(address))
+                let lo = self.mem_read_pointer(address);
+                make_u16!(self.mem_read_pointer(address.wrapping_add(1)), lo)
             }
             M::Absolute => address,
             M::AbsoluteX => address.wrapping_add(self.reg_x as u16),
             M::AbsoluteY => address.wrapping_add(self.reg_y as u16),
-            M::Relative | M::Immediate | M::Accumulator | M::Implicit => {
-                panic!("{:?} does not need memory address", mode)
-            }
-            _ => {
-                panic!("wrong mode, use get_addr_8bit for {:?}", mode)
-            }
+            _ => unreachable!("{mode:?} has no address"),
         }
     }
 
-    pub fn dbg_get_addr_8bit(&mut self, address: u8, mode: AddressingMode) -> u16 {
+    pub fn dbg_get_addr_8bit(&self, address: u8, mode: AddressingMode) -> u16 {
         use AddressingMode as M;
+        let zero_page_ptr = |ptr: u8| {
+            make_u16!(
+                self.mem_bus.dbg_read(ptr.wrapping_add(1) as u16),
+                self.mem_bus.dbg_read(ptr as u16)
+            )
+        };
         match mode {
             M::ZeroPage => address as u16,
             M::ZeroPageX => self.reg_x.wrapping_add(address) as u16,
             M::ZeroPageY => self.reg_y.wrapping_add(address) as u16,
-            M::IndexedIndirect => {
-                let hi = self.dbg_get_addr_8bit(address.wrapping_add(1), M::ZeroPageX);
-                let lo = self.dbg_get_addr_8bit(address, M::ZeroPageX);
-                make_u16!(self.mem_bus.dbg_read(hi), self.mem_bus.dbg_read(lo))
-            }
-            M::IndirectIndexed => {
-                // TODO add a method for this
-                let hi = self.dbg_get_addr_8bit(address.wrapping_add(1), M::ZeroPage);
-                let lo = self.dbg_get_addr_8bit(address, M::ZeroPage);
-                make_u16!(self.mem_bus.dbg_read(hi), self.mem_bus.dbg_read(lo))
-                    .wrapping_add(self.reg_y as u16)
-            }
-            M::Relative | M::Immediate | M::Accumulator | M::Implicit => {
-                panic!("{:?} does not need memory address", mode)
-            }
-            _ => {
-                panic!("wrong mode, use get_addr_16bit for {:?}", mode)
-            }
+            M::IndexedIndirect => zero_page_ptr(self.reg_x.wrapping_add(address)),
+            M::IndirectIndexed => zero_page_ptr(address).wrapping_add(self.reg_y as u16),
+            M::ZeroPageIndirect => zero_page_ptr(address),
+            _ => unreachable!("{mode:?} has no address"),
         }
     }
 
-    pub fn dbg_get_addr_16bit(&mut self, address: u16, mode: AddressingMode) -> u16 {
+    pub fn dbg_get_addr_16bit(&self, address: u16, mode: AddressingMode) -> u16 {
         use AddressingMode as M;
         match mode {
-            M::Indirect => {
-                make_u16!(
-                    self.mem_bus.dbg_read(address.wrapping_add(1)),
-                    self.mem_bus.dbg_read(address)
-                )
-            }
+            M::Indirect => self.mem_bus.dbg_read_16bit(address),
             M::Absolute => address,
             M::AbsoluteX => address.wrapping_add(self.reg_x as u16),
             M::AbsoluteY => address.wrapping_add(self.reg_y as u16),
-            M::Relative | M::Immediate | M::Accumulator | M::Implicit => {
-                panic!("{:?} does not need memory address", mode)
-            }
-            _ => {
-                panic!("wrong mode, use get_addr_8bit for {:?}", mode)
-            }
+            _ => unreachable!("{mode:?} has no address"),
         }
     }
 
+    // pointers in zero page wrap around inside it
+    fn read_zero_page_ptr(&mut self, ptr: u8) -> u16 {
+        let lo = self.mem_read_pointer(ptr as u16);
+        make_u16!(self.mem_read_pointer(ptr.wrapping_add(1) as u16), lo)
+    }
+
     pub fn mem_read_with_mode_u8(&mut self, address: u8, mode: AddressingMode) -> u8 {
         let addr = self.get_addr_8bit(address, mode);
-        self.mem_bus.read(addr)
+        self.mem_read_resolved(addr, mode)
     }
 
     pub fn mem_read_with_mode_u16(&mut self, address: u16, mode: AddressingMode) -> u8 {
         let addr = self.get_addr_16bit(address, mode);
-        self.mem_bus.read(addr)
+        self.mem_read(addr)
     }
 
-    pub fn dbg_read_8bit(&mut self, address: u8, mode: AddressingMode) -> u8 {
+    pub fn dbg_read_8bit(&self, address: u8, mode: AddressingMode) -> u8 {
         let addr = self.dbg_get_addr_8bit(address, mode);
         self.mem_bus.dbg_read(addr)
     }
 
-    pub fn dbg_read_16bit(&mut self, address: u16, mode: AddressingMode) -> u8 {
+    pub fn dbg_read_16bit(&self, address: u16, mode: AddressingMode) -> u8 {
         let addr = self.dbg_get_addr_16bit(address, mode);
         self.mem_bus.dbg_read(addr)
     }
 
-    pub fn fetch_ins_operand(&mut self, ins: Instruction) -> u8 {
+    // fetches the operand bytes and walks the addressing mode cycle by cycle, including the
+    // dummy reads the 6502 makes along the way, and returns the effective address
+    pub fn operand_addr(&mut self, mode: AddressingMode, access: Access) -> u16 {
         use AddressingMode as M;
 
-        let mode = ins.mode;
-
         match mode {
-            M::Immediate => self.mem_read_pc_u8(),
-            M::ZeroPage | M::ZeroPageX | M::ZeroPageY | M::IndexedIndirect | M::IndirectIndexed => {
+            M::ZeroPage => self.mem_read_pc_u8() as u16,
+            M::ZeroPageX | M::ZeroPageY => {
                 let addr = self.mem_read_pc_u8();
-                self.mem_read_with_mode_u8(addr, mode)
+                // the unindexed address is read while the index is added
+                self.mem_read_intermediate(addr as u16);
+                let index = if mode == M::ZeroPageX {
+                    self.reg_x
+                } else {
+                    self.reg_y
+                };
+                addr.wrapping_add(index) as u16
             }
-            M::Absolute | M::AbsoluteX | M::AbsoluteY | M::Indirect => {
-                let addr = self.mem_read_pc_u16();
-                self.mem_read_with_mode_u16(addr, mode)
+            M::Absolute => self.mem_read_pc_u16(),
+            M::AbsoluteX | M::AbsoluteY => {
+                let base = self.mem_read_pc_u16();
+                let index = if mode == M::AbsoluteX {
+                    self.reg_x
+                } else {
+                    self.reg_y
+                };
+                let addr = base.wrapping_add(index as u16);
+                self.index_fixup(base, addr, access);
+                addr
             }
-            _ => panic!("cannot fetch value for {:?}", mode),
+            M::IndexedIndirect => {
+                let ptr = self.mem_read_pc_u8();
+                self.mem_read_intermediate(ptr as u16);
+                self.read_zero_page_ptr(ptr.wrapping_add(self.reg_x))
+            }
+            M::IndirectIndexed => {
+                let ptr = self.mem_read_pc_u8();
+                let base = self.read_zero_page_ptr(ptr);
+                let addr = base.wrapping_add(self.reg_y as u16);
+                self.index_fixup(base, addr, access);
+                addr
+            }
+            M::ZeroPageIndirect => {
+                let ptr = self.mem_read_pc_u8();
+                self.read_zero_page_ptr(ptr)
+            }
+            _ => unreachable!("{mode:?} has no address"),
         }
     }
 
-    pub fn fetch_value_keep_addr(&mut self, ins: Instruction) -> (u8, u16) {
-        use AddressingMode as M;
+    // indexing first reads from the address without the carry into the high byte. reads only
+    // pay for it (the "oops" cycle) when that address was wrong, writes and rmw always do it.
+    // the 65C02 re-reads the last operand byte instead of touching the wrong address
+    fn index_fixup(&mut self, base: u16, addr: u16, access: Access) {
+        let crossed = page_crossed(base, addr);
+        if crossed || access != Access::Read {
+            let dummy_addr = if self.variant == Variant::Wdc65C02 && crossed {
+                self.program_counter.wrapping_sub(1)
+            } else {
+                (base & 0xff00) | (addr & 0x00ff)
+            };
+            self.mem_read_intermediate(dummy_addr);
+        }
+        if crossed && access == Access::Read {
+            self.cycle_count += 1;
+        }
+    }
+
+    // only used by read instructions, which are the ones that pay the page-cross ("oops") cycle
+    pub fn fetch_ins_operand(&mut self, ins: Instruction) -> u8 {
+        if ins.mode == AddressingMode::Immediate {
+            return self.mem_read_pc_u8();
+        }
 
-        let mode = ins.mode;
+        let addr = self.operand_addr(ins.mode, Access::Read);
+        self.mem_read_resolved(addr, ins.mode)
+    }
 
-        match mode {
-            M::ZeroPage | M::ZeroPageX | M::ZeroPageY | M::IndexedIndirect | M::IndirectIndexed => {
-                let addr = self.mem_read_pc_u8();
-                (
-                    self.mem_read_with_mode_u8(addr, mode),
-                    self.get_addr_8bit(addr, mode),
-                )
-            }
-            M::Absolute | M::AbsoluteX | M::AbsoluteY | M::Indirect => {
-                let addr = self.mem_read_pc_u16();
-                (
-                    self.mem_read_with_mode_u16(addr, mode),
-                    self.get_addr_16bit(addr, mode),
-                )
-            }
-            _ => panic!("cannot fetch value for {:?}", mode),
+    // read-modify-write: the unmodified value is written back while the new one is computed
+    // (the 65C02 reads it again instead), the caller then writes the result
+    pub fn fetch_value_keep_addr(&mut self, ins: Instruction) -> (u8, u16) {
+        use InstructionName as IN;
+
+        if self.variant != Variant::Wdc65C02 {
+            let addr = self.operand_addr(ins.mode, Access::Modify);
+            let val = self.mem_read_resolved(addr, ins.mode);
+            self.mem_write(addr, val);
+            return (val, addr);
         }
+
+        // 65C02 shifts on abs,X only take the extra cycle on a page cross, like a read
+        let access = match ins.name {
+            IN::ASL | IN::LSR | IN::ROL | IN::ROR => Access::Read,
+            _ => Access::Modify,
+        };
+        let addr = self.operand_addr(ins.mode, access);
+        let val = self.mem_read_resolved(addr, ins.mode);
+        self.mem_read_intermediate(addr);
+        (val, addr)
     }
 
     pub fn mem_write_with_mode(&mut self, val: u8, ins: Instruction) {
-        use AddressingMode as M;
+        let addr = self.operand_addr(ins.mode, Access::Write);
+        self.mem_write(addr, val);
+    }
 
-        let mode = ins.mode;
+    // SHA/SHX/SHY/TAS: the stored value is and-ed with (high byte of the base address + 1),
+    // and when indexing crosses a page that value also replaces the high byte of the target
+    pub fn mem_write_high_and(&mut self, val: u8, ins: Instruction) {
+        use AddressingMode as M;
 
-        match mode {
-            M::ZeroPage | M::ZeroPageX | M::ZeroPageY | M::IndexedIndirect | M::IndirectIndexed => {
-                let addr = self.mem_read_pc_u8();
-                let write_addr = self.get_addr_8bit(addr, mode);
-                self.mem_bus.write(write_addr, val);
-            }
-            M::Absolute | M::AbsoluteX | M::AbsoluteY | M::Indirect => {
-                let addr = self.mem_read_pc_u16();
-                let write_addr = self.get_addr_16bit(addr, mode);
-                self.mem_bus.write(write_addr, val);
+        let (base, index) = match ins.mode {
+            M::AbsoluteX => (self.mem_read_pc_u16(), self.reg_x),
+            M::AbsoluteY => (self.mem_read_pc_u16(), self.reg_y),
+            M::IndirectIndexed => {
+                let ptr = self.mem_read_pc_u8();
+                (self.read_zero_page_ptr(ptr), self.reg_y)
             }
-            _ => panic!("cannot store value for {:?}", mode),
-        }
+            _ => unreachable!("no high-and store for {:?}", ins.mode),
+        };
+
+        let addr = base.wrapping_add(index as u16);
+        self.index_fixup(base, addr, Access::Write);
+
+        let val = val & ((base >> 8) as u8).wrapping_add(1);
+        let write_addr = if page_crossed(base, addr) {
+            make_u16!(val, addr as u8)
+        } else {
+            addr
+        };
+
+        self.mem_write(write_addr, val);
     }
 }
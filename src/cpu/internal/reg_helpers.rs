@@ -1,10 +1,13 @@
 use crate::cpu::STACK_START;
 use crate::{
-    cpu::{StatusFlags, CPU},
+    cpu::{internal::mem_helpers::page_crossed, StatusFlags, Variant, CPU},
     make_u16,
+    memory::memory_bus::Bus,
+    LogEvent,
 };
+use LogEvent as LE;
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     pub fn add_to_acc(&mut self, val: u8) {
         let sum = (self.accumulator as u16)
             + (val as u16)
@@ -29,6 +32,152 @@ impl CPU {
         self.accumulator = result;
     }
 
+    // the 2A03 has no decimal adder, D is just a flag there
+    fn decimal_mode(&self) -> bool {
+        self.variant != Variant::Ricoh2A03 && self.flags.contains(StatusFlags::DECIMAL)
+    }
+
+    // the 65C02's decimal fixup costs a cycle, spent re-reading the next opcode
+    fn decimal_fixup_cycle(&mut self) {
+        self.cycle_count += 1;
+        self.mem_read_intermediate(self.program_counter);
+    }
+
+    // ADC, sets nvzc. decimal mode follows http://www.6502.org/tutorials/decimal_mode.html:
+    // the NMOS part takes N, V and Z from the binary intermediates, the 65C02 gives valid N and Z
+    // for an extra cycle
+    pub fn add_with_carry(&mut self, val: u8) {
+        if !self.decimal_mode() {
+            self.add_to_acc(val);
+            self.set_zn_flags(self.accumulator);
+            return;
+        }
+
+        let a = self.accumulator as u16;
+        let b = val as u16;
+        let c = self.get_status_bit(StatusFlags::CARRY) as u16;
+
+        let mut lo = (a & 0x0f) + (b & 0x0f) + c;
+        if lo >= 0x0a {
+            lo = ((lo + 0x06) & 0x0f) + 0x10;
+        }
+        let mut sum = (a & 0xf0) + (b & 0xf0) + lo;
+        let negative = (sum >> 7) as u8 & 1;
+        let overflow = (!(a ^ b) & (a ^ sum) & 0x80 != 0) as u8;
+        if sum >= 0xa0 {
+            sum += 0x60;
+        }
+
+        self.accumulator = sum as u8;
+        self.set_status_bit(StatusFlags::CARRY, (sum >= 0x100) as u8);
+        self.set_status_bit(StatusFlags::OVERFLOW, overflow);
+        if self.variant == Variant::Wdc65C02 {
+            self.set_zn_flags(self.accumulator);
+            self.decimal_fixup_cycle();
+        } else {
+            let binary = (a + b + c) as u8;
+            self.set_status_bit(StatusFlags::ZERO, (binary == 0) as u8);
+            self.set_status_bit(StatusFlags::NEGATIVE, negative);
+        }
+    }
+
+    // SBC, sets nvzc. on the NMOS part every flag comes from the binary subtraction
+    pub fn subtract_with_carry(&mut self, val: u8) {
+        let a = self.accumulator as i16;
+        let b = val as i16;
+        let borrow = 1 - self.get_status_bit(StatusFlags::CARRY) as i16;
+
+        self.add_to_acc(!val);
+        self.set_zn_flags(self.accumulator);
+        if !self.decimal_mode() {
+            return;
+        }
+
+        let lo = (a & 0x0f) - (b & 0x0f) - borrow;
+        let result = if self.variant == Variant::Wdc65C02 {
+            let mut result = a - b - borrow;
+            if result < 0 {
+                result -= 0x60;
+            }
+            if lo < 0 {
+                result -= 0x06;
+            }
+            result
+        } else {
+            let lo = if lo < 0 {
+                ((lo - 0x06) & 0x0f) - 0x10
+            } else {
+                lo
+            };
+            let mut result = (a & 0xf0) - (b & 0xf0) + lo;
+            if result < 0 {
+                result -= 0x60;
+            }
+            result
+        };
+
+        self.accumulator = result as u8;
+        if self.variant == Variant::Wdc65C02 {
+            self.set_zn_flags(self.accumulator);
+            self.decimal_fixup_cycle();
+        }
+    }
+
+    pub fn compare(&mut self, reg: u8, val: u8) {
+        self.set_status_bit(StatusFlags::CARRY, if reg >= val { 1 } else { 0 });
+        self.set_status_bit(StatusFlags::ZERO, if reg == val { 1 } else { 0 });
+        self.set_status_bit(StatusFlags::NEGATIVE, reg.wrapping_sub(val) >> 7);
+    }
+
+    // shift/rotate helpers update carry + zn and return the new value
+    pub fn shift_left(&mut self, val: u8) -> u8 {
+        self.set_status_bit(StatusFlags::CARRY, val >> 7);
+        let result = val << 1;
+        self.set_zn_flags(result);
+        result
+    }
+
+    pub fn shift_right(&mut self, val: u8) -> u8 {
+        self.set_status_bit(StatusFlags::CARRY, val & 1);
+        let result = val >> 1;
+        self.set_zn_flags(result);
+        result
+    }
+
+    pub fn rotate_left(&mut self, val: u8) -> u8 {
+        let old_c = self.get_status_bit(StatusFlags::CARRY);
+        self.set_status_bit(StatusFlags::CARRY, val >> 7);
+        let result = (val << 1) | old_c;
+        self.set_zn_flags(result);
+        result
+    }
+
+    pub fn rotate_right(&mut self, val: u8) -> u8 {
+        let old_c = self.get_status_bit(StatusFlags::CARRY);
+        self.set_status_bit(StatusFlags::CARRY, val & 1);
+        let result = (val >> 1) | (old_c << 7);
+        self.set_zn_flags(result);
+        result
+    }
+
+    // taken branches cost 1 extra cycle, plus 1 more if the target is on a different page
+    pub fn branch(&mut self, condition: bool) {
+        let offset = self.mem_read_pc_u8() as i8;
+
+        if condition {
+            let target = self.program_counter.wrapping_add(offset as u16);
+            // the next opcode is read while the offset is added, and again from the wrong page
+            // while the high byte is fixed up
+            self.mem_read_intermediate(self.program_counter);
+            self.cycle_count += 1;
+            if page_crossed(self.program_counter, target) {
+                self.mem_read_intermediate((self.program_counter & 0xff00) | (target & 0x00ff));
+                self.cycle_count += 1;
+            }
+            self.program_counter = target;
+        }
+    }
+
     pub fn inc_sp(&mut self) {
         self.stack_pointer = self.stack_pointer.wrapping_add(1);
     }
@@ -37,18 +186,21 @@ impl CPU {
         self.stack_pointer = self.stack_pointer.wrapping_sub(1);
     }
 
+    // the dummy stack read made while the stack pointer is adjusted
     pub fn stack_peek(&mut self) -> u8 {
-        self.mem_bus.read(self.stack_pointer as u16 + STACK_START)
+        self.mem_read_intermediate(self.stack_pointer as u16 + STACK_START)
     }
 
     pub fn stack_pull_u8(&mut self) -> u8 {
         self.inc_sp();
-        self.stack_peek()
+        let val = self.bus_read(self.stack_pointer as u16 + STACK_START);
+        self.log_event(LE::StackPull(val));
+        val
     }
 
     pub fn stack_push_u8(&mut self, val: u8) {
-        self.mem_bus
-            .write(self.stack_pointer as u16 + STACK_START, val);
+        self.bus_write(self.stack_pointer as u16 + STACK_START, val);
+        self.log_event(LE::StackPush(val));
         self.dec_sp();
     }
 
@@ -0,0 +1,237 @@
+// adds up where the cycles go: per instruction address, per routine (anything entered by a JSR or
+// an interrupt, as the call stack sees it) and per band of scanlines. the profiler leans on the
+// call stack for routines, CPU::enable_profiler turns both on
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+};
+
+use crate::cpu::call_stack::Frame;
+
+const SCANLINES_PER_FRAME: usize = 262;
+pub const DEFAULT_BAND_SIZE: usize = 8;
+
+// the name cycles spent outside any routine go under
+const TOP_LEVEL: &str = "top";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RoutineStats {
+    pub calls: u64,
+    // the routine and everything it called, a recursive call only counts once
+    pub inclusive: u64,
+    // just the routine's own instructions
+    pub exclusive: u64,
+}
+
+#[derive(Debug)]
+pub struct Profiler {
+    per_pc: Vec<u64>,
+    routines: HashMap<u16, RoutineStats>,
+    top_level: u64,
+    bands: Vec<u64>,
+    band_size: usize,
+    // cycles per call path, outermost routine first, for the folded stack file
+    paths: HashMap<Vec<u16>, u64>,
+    // (entry point, stack pointer) of every routine on the call stack when the current
+    // instruction started
+    stack: Vec<(u16, u8)>,
+    path: Vec<u16>,
+    scanline: u16,
+    total: u64,
+    instructions: u64,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new(DEFAULT_BAND_SIZE)
+    }
+}
+
+impl Profiler {
+    // scanlines are added up `band_size` at a time
+    pub fn new(band_size: usize) -> Self {
+        let band_size = band_size.max(1);
+        Profiler {
+            per_pc: vec![0; 0x10000],
+            routines: HashMap::new(),
+            top_level: 0,
+            bands: vec![0; SCANLINES_PER_FRAME.div_ceil(band_size)],
+            band_size,
+            paths: HashMap::new(),
+            stack: Vec::new(),
+            path: Vec::new(),
+            scanline: 0,
+            total: 0,
+            instructions: 0,
+        }
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.total
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn cycles_at(&self, pc: u16) -> u64 {
+        self.per_pc[pc as usize]
+    }
+
+    // stats for the routine entered at `addr`
+    pub fn routine(&self, addr: u16) -> Option<RoutineStats> {
+        self.routines.get(&addr).copied()
+    }
+
+    // cycles outside any routine
+    pub fn top_level_cycles(&self) -> u64 {
+        self.top_level
+    }
+
+    pub fn band_size(&self) -> usize {
+        self.band_size
+    }
+
+    // cycles per band of scanlines, band n starts at scanline n * band_size
+    pub fn bands(&self) -> &[u64] {
+        &self.bands
+    }
+
+    // called before an instruction runs with the call stack as it stands, interrupts included.
+    // routines that weren't there last time were just called
+    pub(crate) fn begin(&mut self, frames: &[Frame], scanline: u16) {
+        let kept = self
+            .stack
+            .iter()
+            .zip(frames)
+            .take_while(|(&(target, sp), f)| target == f.target && sp == f.sp)
+            .count();
+        for frame in &frames[kept..] {
+            self.routines.entry(frame.target).or_default().calls += 1;
+        }
+
+        self.stack.truncate(kept);
+        self.stack
+            .extend(frames[kept..].iter().map(|f| (f.target, f.sp)));
+        self.path.clear();
+        self.path
+            .extend(self.stack.iter().map(|&(target, _)| target));
+        self.scanline = scanline;
+    }
+
+    // called after it ran, with the cycles it took
+    pub(crate) fn end(&mut self, pc: u16, cycles: usize) {
+        self.instructions += 1;
+        self.charge(pc, cycles);
+    }
+
+    // cycles that aren't an instruction's (interrupt entry, waiting, jammed) go to `pc` and the
+    // routines from the last begin without counting an instruction
+    pub(crate) fn charge(&mut self, pc: u16, cycles: usize) {
+        let cycles = cycles as u64;
+        self.total += cycles;
+        self.per_pc[pc as usize] += cycles;
+
+        let band = (self.scanline as usize % SCANLINES_PER_FRAME) / self.band_size;
+        self.bands[band] += cycles;
+
+        match self.path.last() {
+            Some(&routine) => self.routines.entry(routine).or_default().exclusive += cycles,
+            None => self.top_level += cycles,
+        }
+        for (i, &routine) in self.path.iter().enumerate() {
+            if !self.path[..i].contains(&routine) {
+                self.routines.entry(routine).or_default().inclusive += cycles;
+            }
+        }
+
+        match self.paths.get_mut(self.path.as_slice()) {
+            Some(path_cycles) => *path_cycles += cycles,
+            None => {
+                self.paths.insert(self.path.clone(), cycles);
+            }
+        }
+    }
+
+    // the `top` busiest routines and addresses and every scanline band, sorted by cycles.
+    // `name` turns an address into something readable (symbols), see Symbols::describe
+    pub fn report(&self, top: usize, name: impl Fn(u16) -> String) -> String {
+        let percent = |cycles: u64| cycles as f64 * 100.0 / self.total.max(1) as f64;
+        let mut out = format!(
+            "{} cycles in {} instructions\n\n",
+            self.total, self.instructions
+        );
+
+        out += &format!(
+            "{:<20} {:>10} {:>17} {:>17}\n",
+            "routine", "calls", "inclusive", "exclusive"
+        );
+        out += &format!(
+            "{TOP_LEVEL:<20} {:>10} {:>10} {:5.1}% {:>10} {:5.1}%\n",
+            "-",
+            self.total,
+            100.0,
+            self.top_level,
+            percent(self.top_level)
+        );
+        let mut routines: Vec<(u16, RoutineStats)> =
+            self.routines.iter().map(|(&a, &s)| (a, s)).collect();
+        routines.sort_by(|a, b| b.1.inclusive.cmp(&a.1.inclusive).then(a.0.cmp(&b.0)));
+        for (addr, stats) in routines.into_iter().take(top) {
+            out += &format!(
+                "{:<20} {:>10} {:>10} {:5.1}% {:>10} {:5.1}%\n",
+                name(addr),
+                stats.calls,
+                stats.inclusive,
+                percent(stats.inclusive),
+                stats.exclusive,
+                percent(stats.exclusive)
+            );
+        }
+
+        out += &format!("\n{:<20} {:>10}\n", "address", "cycles");
+        let mut addrs: Vec<(u16, u64)> = (0..=0xffff_u16)
+            .map(|pc| (pc, self.per_pc[pc as usize]))
+            .filter(|&(_, cycles)| cycles > 0)
+            .collect();
+        addrs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (pc, cycles) in addrs.into_iter().take(top) {
+            out += &format!("{:<20} {cycles:>10} {:5.1}%\n", name(pc), percent(cycles));
+        }
+
+        out += &format!("\n{:<20} {:>10}\n", "scanlines", "cycles");
+        for (i, &cycles) in self.bands.iter().enumerate() {
+            let first = i * self.band_size;
+            let last = (first + self.band_size).min(SCANLINES_PER_FRAME) - 1;
+            let lines = format!("{first}-{last}");
+            out += &format!("{lines:<20} {cycles:>10} {:5.1}%\n", percent(cycles));
+        }
+        out
+    }
+
+    // one line per call path, "top;outer;inner cycles", the input flamegraph.pl and inferno take
+    pub fn write_folded(
+        &self,
+        out: &mut impl Write,
+        name: impl Fn(u16) -> String,
+    ) -> io::Result<()> {
+        let mut paths: Vec<(&Vec<u16>, &u64)> = self.paths.iter().collect();
+        paths.sort();
+        for (path, cycles) in paths {
+            let mut line = TOP_LEVEL.to_string();
+            for &routine in path {
+                line.push(';');
+                line += &name(routine);
+            }
+            writeln!(out, "{line} {cycles}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.report(20, |addr| format!("{addr:04X}")))
+    }
+}
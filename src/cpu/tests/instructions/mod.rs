@@ -1,5 +1,6 @@
 #![cfg(test)]
 
+pub mod test_65c02_ins;
 pub mod test_access_ins;
 pub mod test_arithmetic_ins;
 pub mod test_bitwise_ins;
@@ -11,3 +12,4 @@ pub mod test_other_misc_ins;
 pub mod test_shift_ins;
 pub mod test_stack_ins;
 pub mod test_transfer_ins;
+pub mod test_unofficial_ins;
@@ -0,0 +1,185 @@
+// 65C02: BRA, PHX, PHY, PLX, PLY, STZ, TRB, TSB, RMB, SMB, BBR, BBS, (zp), JMP fixes
+
+use crate::cpu::{
+    instructions::{self, wdc65c02 as C02, InstructionName, Variant},
+    tests::{set_multiple_bytes, test_cpu},
+    StatusFlags,
+};
+use crate::memory::memory_bus::Bus;
+use instructions as IN;
+
+#[test]
+fn test_65c02_decode() {
+    for opcode in 0..=255_u8 {
+        let ins = Variant::Wdc65C02.decode(opcode);
+        assert_eq!(ins.opcode, opcode);
+        assert!(!ins.unofficial, "{opcode:02x} decoded as {:?}", ins.name);
+    }
+
+    assert_eq!(Variant::Wdc65C02.decode(0xa9), IN::LDA_IM);
+    assert_eq!(Variant::Nmos6502.decode(0x80).name, InstructionName::NOP);
+    assert_eq!(Variant::Wdc65C02.decode(0x80), C02::BRA);
+    assert_eq!(Variant::Wdc65C02.decode(0xf7).name, InstructionName::SMB);
+}
+
+#[test]
+fn test_jmp_indirect_page_wrap() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xff, 0x10]);
+    set_multiple_bytes(&mut cpu, 0x10ff, &[0x34, 0x12]);
+    cpu.mem_bus.write(0x1000, 0x56);
+
+    cpu.execute(IN::JMP_I);
+    assert_eq!(cpu.program_counter, 0x5634);
+
+    cpu.variant = Variant::Wdc65C02;
+    cpu.program_counter = 0;
+    cpu.cycle_count = 0;
+    cpu.execute(C02::JMP_I);
+    assert_eq!(cpu.program_counter, 0x1234);
+    assert_eq!(cpu.cycle_count, 6);
+
+    cpu.program_counter = 0;
+    cpu.reg_x = 0x01;
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xfe, 0x10]);
+    cpu.execute(C02::JMP_IAX);
+    assert_eq!(cpu.program_counter, 0x1234);
+}
+
+#[test]
+fn test_bra() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x10]);
+
+    cpu.execute(C02::BRA);
+    assert_eq!(cpu.program_counter, 0x12);
+    assert_eq!(cpu.cycle_count, 3);
+}
+
+#[test]
+fn test_push_pull_index() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+
+    cpu.reg_x = 0x12;
+    cpu.reg_y = 0x80;
+    cpu.execute(C02::PHX);
+    cpu.execute(C02::PHY);
+    assert_eq!(cpu.stack_pointer, 0xfd);
+
+    cpu.execute(C02::PLX);
+    assert_eq!(cpu.reg_x, 0x80);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+
+    cpu.execute(C02::PLY);
+    assert_eq!(cpu.reg_y, 0x12);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.stack_pointer, 0xff);
+}
+
+#[test]
+fn test_stz_tsb_trb() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x20, 0, 0x20, 0, 0x20]);
+    cpu.mem_bus.write(0x20, 0xff);
+
+    cpu.execute(C02::STZ_Z);
+    assert_eq!(cpu.mem_bus.read(0x20), 0);
+
+    cpu.accumulator = 0x81;
+    cpu.execute(C02::TSB_Z);
+    assert_eq!(cpu.mem_bus.read(0x20), 0x81);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+
+    cpu.accumulator = 0x01;
+    cpu.execute(C02::TRB_Z);
+    assert_eq!(cpu.mem_bus.read(0x20), 0x80);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+}
+
+#[test]
+fn test_bit_ops() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+
+    set_multiple_bytes(
+        &mut cpu,
+        0,
+        &[0, 0x20, 0, 0x20, 0, 0x20, 0, 0x20, 0x05, 0, 0x20, 0x03],
+    );
+
+    cpu.execute(C02::smb(0xb7));
+    assert_eq!(cpu.mem_bus.read(0x20), 0b0000_1000);
+
+    cpu.execute(C02::smb(0x87));
+    cpu.execute(C02::rmb(0x37));
+    assert_eq!(cpu.mem_bus.read(0x20), 0b0000_0001);
+
+    // BBS0 $20, +5: taken
+    cpu.execute(C02::bbs(0x8f));
+    assert_eq!(cpu.program_counter, 9 + 5);
+
+    // BBR0 $20, +3: not taken
+    cpu.program_counter = 9;
+    cpu.execute(C02::bbr(0x0f));
+    assert_eq!(cpu.program_counter, 12);
+}
+
+#[test]
+fn test_zero_page_indirect() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x40, 0, 0x40]);
+    set_multiple_bytes(&mut cpu, 0x40, &[0x00, 0x13]);
+    cpu.mem_bus.write(0x1300, 0x99);
+
+    cpu.execute(C02::LDA_ZI);
+    assert_eq!(cpu.accumulator, 0x99);
+
+    cpu.accumulator = 0x42;
+    cpu.execute(C02::STA_ZI);
+    assert_eq!(cpu.mem_bus.read(0x1300), 0x42);
+}
+
+#[test]
+fn test_inc_dec_accumulator() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+
+    cpu.accumulator = 0xff;
+    cpu.execute(C02::INC_AC);
+    assert_eq!(cpu.accumulator, 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+
+    cpu.execute(C02::DEC_AC);
+    assert_eq!(cpu.accumulator, 0xff);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+}
+
+#[test]
+fn test_wai() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+    cpu.flags = StatusFlags::INTERRUPT_DISABLE;
+
+    // WAI, INX
+    set_multiple_bytes(&mut cpu, 0, &[0xcb, 0xe8]);
+
+    cpu.run_once();
+    cpu.run_once();
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 1);
+    assert_eq!(cpu.reg_x, 0);
+
+    // a masked irq still wakes it up
+    cpu.mem_bus.irq = true;
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 2);
+    assert_eq!(cpu.reg_x, 1);
+}
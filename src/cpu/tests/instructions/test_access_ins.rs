@@ -1,114 +1,162 @@
 // LDA, STA, LDX, STX, LDY, STY
-use crate::cpu::{cpu::tests::set_multiple_bytes, instructions, StatusFlags, CPU};
+use crate::cpu::{
+    instructions,
+    tests::{set_multiple_bytes, test_cpu},
+    StatusFlags,
+};
+use crate::memory::memory_bus::Bus;
 use instructions as IN;
 
 #[test]
 fn test_lda() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0xab, 0, 0x00, 0, 0x05]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xab, 0, 0x00, 0, 0x05]);
 
     cpu.execute(IN::LDA_IM);
     assert_eq!(cpu.accumulator, 0xab);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
 
     cpu.execute(IN::LDA_IM);
     assert_eq!(cpu.accumulator, 0x00);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 
     cpu.execute(IN::LDA_IM);
     assert_eq!(cpu.accumulator, 0x05);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 }
 
 #[test]
 fn test_ldx() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0xab, 0, 0x00, 0, 0x05]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xab, 0, 0x00, 0, 0x05]);
 
     cpu.execute(IN::LDX_IM);
     assert_eq!(cpu.reg_x, 0xab);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
 
     cpu.execute(IN::LDX_IM);
     assert_eq!(cpu.reg_x, 0x00);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 
     cpu.execute(IN::LDX_IM);
     assert_eq!(cpu.reg_x, 0x05);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 }
 
 #[test]
 fn test_ldy() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0xab, 0, 0x00, 0, 0x05]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xab, 0, 0x00, 0, 0x05]);
 
     cpu.execute(IN::LDY_IM);
     assert_eq!(cpu.reg_y, 0xab);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
 
     cpu.execute(IN::LDY_IM);
     assert_eq!(cpu.reg_y, 0x00);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 
     cpu.execute(IN::LDY_IM);
     assert_eq!(cpu.reg_y, 0x05);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 }
 
 #[test]
 fn test_sta() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0xab, 0x1d, 0, 0x33]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xab, 0x1d, 0, 0x33]);
 
     cpu.accumulator = 0xbc;
     cpu.execute(IN::STA_A);
-    assert_eq!(cpu.memory.read(0x1dab), 0xbc);
+    assert_eq!(cpu.mem_bus.read(0x1dab), 0xbc);
 
     cpu.accumulator = 0xde;
     cpu.execute(IN::STA_Z);
-    assert_eq!(cpu.memory.read(0x33), 0xde);
+    assert_eq!(cpu.mem_bus.read(0x33), 0xde);
 }
 
 #[test]
 fn test_stx() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0xab, 0x1d, 0, 0x33]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xab, 0x1d, 0, 0x33]);
 
     cpu.reg_x = 0xbc;
     cpu.execute(IN::STX_A);
-    assert_eq!(cpu.memory.read(0x1dab), 0xbc);
+    assert_eq!(cpu.mem_bus.read(0x1dab), 0xbc);
 
     cpu.reg_x = 0xde;
     cpu.execute(IN::STX_Z);
-    assert_eq!(cpu.memory.read(0x33), 0xde);
+    assert_eq!(cpu.mem_bus.read(0x33), 0xde);
 }
 
 #[test]
 fn test_sty() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0xab, 0x1d, 0, 0x33]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xab, 0x1d, 0, 0x33]);
 
     cpu.reg_y = 0xbc;
     cpu.execute(IN::STY_A);
-    assert_eq!(cpu.memory.read(0x1dab), 0xbc);
+    assert_eq!(cpu.mem_bus.read(0x1dab), 0xbc);
 
     cpu.reg_y = 0xde;
     cpu.execute(IN::STY_Z);
-    assert_eq!(cpu.memory.read(0x33), 0xde);
+    assert_eq!(cpu.mem_bus.read(0x33), 0xde);
+}
+
+#[test]
+fn test_page_cross_cycles() {
+    let mut cpu = test_cpu();
+    cpu.reg_x = 0x10;
+    cpu.reg_y = 0x10;
+
+    // base $12e0: + $10 stays in the page, base $12f8 crosses into $1300
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xe0, 0x12, 0, 0xf8, 0x12]);
+    cpu.execute(IN::LDA_AX);
+    assert_eq!(cpu.cycle_count, 4);
+    cpu.execute(IN::LDA_AX);
+    assert_eq!(cpu.cycle_count, 4 + 5);
+
+    cpu.program_counter = 0;
+    cpu.cycle_count = 0;
+    cpu.execute(IN::LDA_AY);
+    assert_eq!(cpu.cycle_count, 4);
+    cpu.execute(IN::LDA_AY);
+    assert_eq!(cpu.cycle_count, 4 + 5);
+
+    // the pointers at $40 and $42 hold the same two bases
+    cpu.program_counter = 0;
+    cpu.cycle_count = 0;
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x40, 0, 0x42]);
+    set_multiple_bytes(&mut cpu, 0x40, &[0xe0, 0x12, 0xf8, 0x12]);
+    cpu.execute(IN::LDA_IY);
+    assert_eq!(cpu.cycle_count, 5);
+    cpu.execute(IN::LDA_IY);
+    assert_eq!(cpu.cycle_count, 5 + 6);
+
+    // stores always take the extra cycle
+    cpu.program_counter = 0;
+    cpu.cycle_count = 0;
+    cpu.accumulator = 0x42;
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xe0, 0x12, 0, 0xf8, 0x12]);
+    cpu.execute(IN::STA_AX);
+    assert_eq!(cpu.cycle_count, 5);
+    cpu.execute(IN::STA_AX);
+    assert_eq!(cpu.cycle_count, 5 + 5);
+    assert_eq!(cpu.mem_bus.read(0x12f0), 0x42);
+    assert_eq!(cpu.mem_bus.read(0x1308), 0x42);
 }
@@ -1,11 +1,16 @@
 // ADC, SBC, INC, DEC, INC, DEX, INY, DEY
-use crate::cpu::{cpu::tests::set_multiple_bytes, instructions, StatusFlags, CPU};
+use crate::cpu::{
+    instructions,
+    tests::{set_multiple_bytes, test_cpu},
+    StatusFlags, Variant,
+};
+use crate::memory::memory_bus::Bus;
 use bitflags::Flags;
 use instructions as IN;
 
 #[test]
 fn test_adc() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     set_multiple_bytes(
         &mut cpu,
@@ -66,7 +71,7 @@ fn test_adc() {
 
 #[test]
 fn test_sbc() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     set_multiple_bytes(
         &mut cpu,
@@ -91,49 +96,49 @@ fn test_sbc() {
         ],
     );
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0x50;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0x60);
     assert_eq!(cpu.flags.bits(), 0b00000000);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0x50;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0xa0);
     assert_eq!(cpu.flags.bits(), 0b11000000);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0x50;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0xe0);
     assert_eq!(cpu.flags.bits(), 0b10000000);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0x50;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0x20);
     assert_eq!(cpu.flags.bits(), 0b00000001);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0x50;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0x60);
     assert_eq!(cpu.flags.bits(), 0b00000000);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0xd0;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0x20);
     assert_eq!(cpu.flags.bits(), 0b00000001);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0xd0;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0x60);
     assert_eq!(cpu.flags.bits(), 0b01000001);
 
-    cpu.flags.clear();
+    cpu.flags = StatusFlags::CARRY;
     cpu.accumulator = 0xd0;
     cpu.execute(IN::SBC_IM);
     assert_eq!(cpu.accumulator, 0xa0);
@@ -142,194 +147,257 @@ fn test_sbc() {
 
 #[test]
 fn test_inc() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
     set_multiple_bytes(
         &mut cpu,
         0,
-        &vec![0, 0x34, 0x12, 0, 0x35, 0x12, 0, 0x36, 0x12, 0, 0x37, 0x12],
+        &[0, 0x34, 0x12, 0, 0x35, 0x12, 0, 0x36, 0x12, 0, 0x37, 0x12],
     );
-    set_multiple_bytes(&mut cpu, 0x1234, &vec![0x11, 0, 0x88, 0xff]);
+    set_multiple_bytes(&mut cpu, 0x1234, &[0x11, 0, 0x88, 0xff]);
 
     cpu.execute(IN::INC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
-    assert_eq!(cpu.memory.read(0x1234), 0x12);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.mem_bus.read(0x1234), 0x12);
 
     cpu.execute(IN::INC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
-    assert_eq!(cpu.memory.read(0x1235), 0x1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.mem_bus.read(0x1235), 0x1);
 
     cpu.execute(IN::INC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
-    assert_eq!(cpu.memory.read(0x1236), 0x89);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.mem_bus.read(0x1236), 0x89);
 
     cpu.execute(IN::INC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
-    assert_eq!(cpu.memory.read(0x1237), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.mem_bus.read(0x1237), 0);
 }
 
 #[test]
 fn test_inx() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.reg_x = 0x11;
 
     cpu.execute(IN::INX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_x, 0x12);
 
     cpu.reg_x = 0x0;
 
     cpu.execute(IN::INX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_x, 0x1);
 
     cpu.reg_x = 0x88;
 
     cpu.execute(IN::INX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
     assert_eq!(cpu.reg_x, 0x89);
 
     cpu.reg_x = 0xff;
 
     cpu.execute(IN::INX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_x, 0x0);
 }
 
 #[test]
 fn test_iny() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.reg_y = 0x11;
 
     cpu.execute(IN::INY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_y, 0x12);
 
     cpu.reg_y = 0x0;
 
     cpu.execute(IN::INY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_y, 0x1);
 
     cpu.reg_y = 0x88;
 
     cpu.execute(IN::INY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
     assert_eq!(cpu.reg_y, 0x89);
 
     cpu.reg_y = 0xff;
 
     cpu.execute(IN::INY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_y, 0x0);
 }
 
 #[test]
 fn test_dec() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
     set_multiple_bytes(
         &mut cpu,
         0,
-        &vec![0, 0x34, 0x12, 0, 0x35, 0x12, 0, 0x36, 0x12, 0, 0x37, 0x12],
+        &[0, 0x34, 0x12, 0, 0x35, 0x12, 0, 0x36, 0x12, 0, 0x37, 0x12],
     );
-    set_multiple_bytes(&mut cpu, 0x1234, &vec![0x11, 0, 0x1, 0xff]);
+    set_multiple_bytes(&mut cpu, 0x1234, &[0x11, 0, 0x1, 0xff]);
 
     cpu.execute(IN::DEC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
-    assert_eq!(cpu.memory.read(0x1234), 0x10);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.mem_bus.read(0x1234), 0x10);
 
     cpu.execute(IN::DEC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
-    assert_eq!(cpu.memory.read(0x1235), 0xff);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.mem_bus.read(0x1235), 0xff);
 
     cpu.execute(IN::DEC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
-    assert_eq!(cpu.memory.read(0x1236), 0x0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.mem_bus.read(0x1236), 0x0);
 
     cpu.execute(IN::DEC_A);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
-    assert_eq!(cpu.memory.read(0x1237), 0xfe);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.mem_bus.read(0x1237), 0xfe);
 }
 
 #[test]
 fn test_dex() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.reg_x = 0x11;
 
     cpu.execute(IN::DEX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_x, 0x10);
 
     cpu.reg_x = 0x0;
 
     cpu.execute(IN::DEX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
     assert_eq!(cpu.reg_x, 0xff);
 
     cpu.reg_x = 0x1;
 
     cpu.execute(IN::DEX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_x, 0x0);
 
     cpu.reg_x = 0xff;
 
     cpu.execute(IN::DEX);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
     assert_eq!(cpu.reg_x, 0xfe);
 }
 
 #[test]
 fn test_dey() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.reg_y = 0x11;
 
     cpu.execute(IN::DEY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_y, 0x10);
 
     cpu.reg_y = 0x0;
 
     cpu.execute(IN::DEY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
     assert_eq!(cpu.reg_y, 0xff);
 
     cpu.reg_y = 0x1;
 
     cpu.execute(IN::DEY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
     assert_eq!(cpu.reg_y, 0x0);
 
     cpu.reg_y = 0xff;
 
     cpu.execute(IN::DEY);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
     assert_eq!(cpu.reg_y, 0xfe);
 }
+
+#[test]
+fn test_adc_decimal() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x01, 0, 0x01, 0, 0x01, 0, 0x01]);
+
+    // the 2A03 ignores D
+    cpu.flags = StatusFlags::DECIMAL;
+    cpu.accumulator = 0x09;
+    cpu.execute(IN::ADC_IM);
+    assert_eq!(cpu.accumulator, 0x0a);
+
+    cpu.variant = Variant::Nmos6502;
+    cpu.flags = StatusFlags::DECIMAL;
+    cpu.accumulator = 0x09;
+    cpu.execute(IN::ADC_IM);
+    assert_eq!(cpu.accumulator, 0x10);
+    assert_eq!(cpu.flags.bits(), 0b00001000);
+
+    // nmos Z comes from the binary sum (0x9a)
+    cpu.flags = StatusFlags::DECIMAL;
+    cpu.accumulator = 0x99;
+    cpu.execute(IN::ADC_IM);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert_eq!(cpu.flags.bits(), 0b10001001);
+
+    cpu.variant = Variant::Wdc65C02;
+    cpu.flags = StatusFlags::DECIMAL;
+    cpu.accumulator = 0x99;
+    cpu.cycle_count = 0;
+    cpu.execute(IN::ADC_IM);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert_eq!(cpu.flags.bits(), 0b00001011);
+    assert_eq!(cpu.cycle_count, 3);
+}
+
+#[test]
+fn test_sbc_decimal() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x01, 0, 0x01, 0, 0x01]);
+
+    cpu.variant = Variant::Nmos6502;
+    cpu.flags = StatusFlags::DECIMAL | StatusFlags::CARRY;
+    cpu.accumulator = 0x10;
+    cpu.execute(IN::SBC_IM);
+    assert_eq!(cpu.accumulator, 0x09);
+    assert_eq!(cpu.flags.bits(), 0b00001001);
+
+    cpu.flags = StatusFlags::DECIMAL | StatusFlags::CARRY;
+    cpu.accumulator = 0x00;
+    cpu.execute(IN::SBC_IM);
+    assert_eq!(cpu.accumulator, 0x99);
+    assert_eq!(cpu.flags.bits(), 0b10001000);
+
+    cpu.variant = Variant::Wdc65C02;
+    cpu.flags = StatusFlags::DECIMAL;
+    cpu.accumulator = 0x10;
+    cpu.execute(IN::SBC_IM);
+    assert_eq!(cpu.accumulator, 0x08);
+    assert_eq!(cpu.flags.bits(), 0b00001001);
+}
@@ -1,14 +1,18 @@
 // BCC, BCS, BEQ, BNE, BPL, BMI, BVC, BVS
 
-use crate::cpu::{cpu::tests::set_multiple_bytes, instructions, StatusFlags, CPU};
+use crate::cpu::{
+    instructions,
+    tests::{set_multiple_bytes, test_cpu},
+    StatusFlags,
+};
 use instructions as IN;
 
 #[test]
 fn test_bcs() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::CARRY, 1);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::CARRY, 1);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BCS);
@@ -26,7 +30,7 @@ fn test_bcs() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::CARRY, 0);
+    cpu.set_status_bit(StatusFlags::CARRY, 0);
     cpu.execute(IN::BCS);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -35,10 +39,10 @@ fn test_bcs() {
 
 #[test]
 fn test_bcc() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::CARRY, 0);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::CARRY, 0);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BCC);
@@ -56,7 +60,7 @@ fn test_bcc() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::CARRY, 1);
+    cpu.set_status_bit(StatusFlags::CARRY, 1);
     cpu.execute(IN::BCC);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -65,10 +69,10 @@ fn test_bcc() {
 
 #[test]
 fn test_beq() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::ZERO, 1);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::ZERO, 1);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BEQ);
@@ -86,7 +90,7 @@ fn test_beq() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::ZERO, 0);
+    cpu.set_status_bit(StatusFlags::ZERO, 0);
     cpu.execute(IN::BEQ);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -95,10 +99,10 @@ fn test_beq() {
 
 #[test]
 fn test_bne() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::ZERO, 0);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::ZERO, 0);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BNE);
@@ -116,7 +120,7 @@ fn test_bne() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::ZERO, 1);
+    cpu.set_status_bit(StatusFlags::ZERO, 1);
     cpu.execute(IN::BNE);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -125,10 +129,10 @@ fn test_bne() {
 
 #[test]
 fn test_bmi() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::NEGATIVE, 1);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::NEGATIVE, 1);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BMI);
@@ -146,7 +150,7 @@ fn test_bmi() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::NEGATIVE, 0);
+    cpu.set_status_bit(StatusFlags::NEGATIVE, 0);
     cpu.execute(IN::BMI);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -155,10 +159,10 @@ fn test_bmi() {
 
 #[test]
 fn test_bpl() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::NEGATIVE, 0);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::NEGATIVE, 0);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BPL);
@@ -176,7 +180,7 @@ fn test_bpl() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::NEGATIVE, 1);
+    cpu.set_status_bit(StatusFlags::NEGATIVE, 1);
     cpu.execute(IN::BPL);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -185,10 +189,10 @@ fn test_bpl() {
 
 #[test]
 fn test_bvc() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::OVERFLOW, 0);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::OVERFLOW, 0);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BVC);
@@ -206,7 +210,7 @@ fn test_bvc() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::OVERFLOW, 1);
+    cpu.set_status_bit(StatusFlags::OVERFLOW, 1);
     cpu.execute(IN::BVC);
 
     assert_eq!(cpu.cycle_count, 11);
@@ -215,10 +219,10 @@ fn test_bvc() {
 
 #[test]
 fn test_bvs() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.set_flag(StatusFlags::OVERFLOW, 1);
-    set_multiple_bytes(&mut cpu, 2111, &vec![0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
+    cpu.set_status_bit(StatusFlags::OVERFLOW, 1);
+    set_multiple_bytes(&mut cpu, 2111, &[0, 0, 0, 3, 0, 0, 0, 0, 0xff, 0x10]);
 
     cpu.program_counter = 2111;
     cpu.execute(IN::BVS);
@@ -236,7 +240,7 @@ fn test_bvs() {
     assert_eq!(cpu.cycle_count, 9);
     assert_eq!(cpu.program_counter, (2113 + 2 + 3) + 2 - 1);
 
-    cpu.set_flag(StatusFlags::OVERFLOW, 0);
+    cpu.set_status_bit(StatusFlags::OVERFLOW, 0);
     cpu.execute(IN::BVS);
 
     assert_eq!(cpu.cycle_count, 11);
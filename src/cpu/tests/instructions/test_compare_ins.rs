@@ -1,11 +1,14 @@
 // CMP, CPX, CPY
-use crate::cpu::{cpu::tests::set_multiple_bytes, instructions, CPU};
+use crate::cpu::{
+    instructions,
+    tests::{set_multiple_bytes, test_cpu},
+};
 use instructions as IN;
 
 #[test]
 fn test_cmp() {
-    let mut cpu = CPU::new();
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 5, 0, 20, 0, 10]);
+    let mut cpu = test_cpu();
+    set_multiple_bytes(&mut cpu, 0, &[0, 5, 0, 20, 0, 10]);
 
     cpu.accumulator = 10;
     cpu.reg_x = 100;
@@ -26,8 +29,8 @@ fn test_cmp() {
 
 #[test]
 fn test_cpx() {
-    let mut cpu = CPU::new();
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 5, 0, 20, 0, 10]);
+    let mut cpu = test_cpu();
+    set_multiple_bytes(&mut cpu, 0, &[0, 5, 0, 20, 0, 10]);
 
     cpu.accumulator = 100;
     cpu.reg_x = 10;
@@ -48,8 +51,8 @@ fn test_cpx() {
 
 #[test]
 fn test_cpy() {
-    let mut cpu = CPU::new();
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 5, 0, 20, 0, 10]);
+    let mut cpu = test_cpu();
+    set_multiple_bytes(&mut cpu, 0, &[0, 5, 0, 20, 0, 10]);
 
     cpu.accumulator = 100;
     cpu.reg_x = 200;
@@ -1,21 +1,23 @@
 // JMP, JSR, RTS, BRK, RTI
 
 use crate::cpu::{
-    cpu::{tests::set_multiple_bytes, STACK_START},
-    instructions, CPU,
+    instructions,
+    tests::{set_multiple_bytes, test_cpu},
+    StatusFlags, STACK_START,
 };
+use crate::memory::memory_bus::Bus;
 use instructions as IN;
 
 #[test]
 fn test_jmp() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0, 0x11, 0x02]);
-    set_multiple_bytes(&mut cpu, 0x0211, &vec![0x20, 0x05]);
-    set_multiple_bytes(&mut cpu, 0x0521, &vec![0xff, 0x03]);
-    set_multiple_bytes(&mut cpu, 0x0300, &vec![0x02, 0x14]);
-    set_multiple_bytes(&mut cpu, 0x03ff, &vec![0x56, 0x48]);
-    set_multiple_bytes(&mut cpu, 0x0257, &vec![0x56, 0x08]);
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x11, 0x02]);
+    set_multiple_bytes(&mut cpu, 0x0211, &[0x20, 0x05]);
+    set_multiple_bytes(&mut cpu, 0x0521, &[0xff, 0x03]);
+    set_multiple_bytes(&mut cpu, 0x0300, &[0x02, 0x14]);
+    set_multiple_bytes(&mut cpu, 0x03ff, &[0x56, 0x48]);
+    set_multiple_bytes(&mut cpu, 0x0257, &[0x56, 0x08]);
 
     cpu.execute(IN::JMP_I);
     assert_eq!(cpu.program_counter, 0x0520);
@@ -29,22 +31,22 @@ fn test_jmp() {
 
 #[test]
 fn test_jsr_and_rts() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.program_counter = 0x1233;
     cpu.stack_pointer = 255;
 
-    set_multiple_bytes(&mut cpu, 0x1234, &vec![0x25, 0x20, 0x54, 0x32]);
+    set_multiple_bytes(&mut cpu, 0x1234, &[0x25, 0x20, 0x54, 0x32]);
 
     cpu.execute(IN::JSR_A);
     assert_eq!(cpu.program_counter, 0x2025);
     assert_eq!(cpu.stack_pointer, 253);
     assert_eq!(
-        cpu.memory.read(STACK_START + cpu.stack_pointer as u16 + 1),
+        cpu.mem_bus.read(STACK_START + cpu.stack_pointer as u16 + 1),
         0x35
     );
     assert_eq!(
-        cpu.memory.read(STACK_START + cpu.stack_pointer as u16 + 2),
+        cpu.mem_bus.read(STACK_START + cpu.stack_pointer as u16 + 2),
         0x12
     );
 
@@ -52,3 +54,45 @@ fn test_jsr_and_rts() {
     assert_eq!(cpu.program_counter, 0x1236);
     assert_eq!(cpu.stack_pointer, 255);
 }
+
+#[test]
+fn test_brk_and_rti() {
+    let mut cpu = test_cpu();
+
+    cpu.program_counter = 0x1234;
+    cpu.flags.insert(StatusFlags::CARRY);
+    set_multiple_bytes(&mut cpu, 0xfffa, &[0x00, 0x30, 0x00, 0x00, 0x00, 0x20]);
+
+    cpu.execute(IN::BRK);
+    assert_eq!(cpu.program_counter, 0x2000);
+    assert_eq!(cpu.stack_pointer, 0xfc);
+    assert_eq!(cpu.cycle_count, 7);
+    assert!(cpu.flags.contains(StatusFlags::INTERRUPT_DISABLE));
+
+    // the return address skips the padding byte
+    assert_eq!(cpu.mem_bus.read(STACK_START + 0xff), 0x12);
+    assert_eq!(cpu.mem_bus.read(STACK_START + 0xfe), 0x36);
+    assert_eq!(cpu.mem_bus.read(STACK_START + 0xfd), 0b0011_0001);
+
+    cpu.program_counter = 0x3000;
+    cpu.execute(IN::RTI);
+    assert_eq!(cpu.program_counter, 0x1236);
+    assert_eq!(cpu.stack_pointer, 0xff);
+    assert_eq!(cpu.flags.bits(), 0b0010_0001);
+}
+
+#[test]
+fn test_nmi_hijacks_brk() {
+    let mut cpu = test_cpu();
+
+    cpu.program_counter = 0x1234;
+    set_multiple_bytes(&mut cpu, 0xfffa, &[0x00, 0x30, 0x00, 0x00, 0x00, 0x20]);
+
+    // the nmi lands while BRK is pushing, it goes through $fffa but the status keeps B set
+    cpu.mem_bus.nmi = true;
+    cpu.execute(IN::BRK);
+    assert_eq!(cpu.program_counter, 0x3000);
+    assert!(!cpu.mem_bus.nmi);
+    assert_eq!(cpu.mem_bus.read(STACK_START + 0xfe), 0x36);
+    assert_eq!(cpu.mem_bus.read(STACK_START + 0xfd), 0b0011_0000);
+}
@@ -2,43 +2,43 @@
 #![cfg(test)]
 
 use crate::cpu::{
-    cpu::tests::{get_example_byte, set_byte_example, set_multiple_bytes},
     instructions::{self, AddressingMode},
-    CPU,
+    tests::{get_example_byte, set_byte_example, set_multiple_bytes, set_program, test_cpu},
+    Variant,
 };
 
 use instructions as IN;
 #[test]
 fn test_get_next_u8() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![35, 45, 67, 13, 244]);
+    set_multiple_bytes(&mut cpu, 0, &[35, 45, 67, 13, 244]);
 
-    assert_eq!(cpu.get_next_u8(), 35);
-    assert_eq!(cpu.get_next_u8(), 45);
-    assert_eq!(cpu.get_next_u8(), 67);
-    assert_eq!(cpu.get_next_u8(), 13);
-    assert_eq!(cpu.get_next_u8(), 244);
+    assert_eq!(cpu.mem_read_pc_u8(), 35);
+    assert_eq!(cpu.mem_read_pc_u8(), 45);
+    assert_eq!(cpu.mem_read_pc_u8(), 67);
+    assert_eq!(cpu.mem_read_pc_u8(), 13);
+    assert_eq!(cpu.mem_read_pc_u8(), 244);
 }
 
 #[test]
 fn test_get_next_u16() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    set_multiple_bytes(&mut cpu, 0, &vec![0x35, 0x45, 0x67, 0x13, 0x42]);
+    set_multiple_bytes(&mut cpu, 0, &[0x35, 0x45, 0x67, 0x13, 0x42]);
 
-    assert_eq!(cpu.get_next_u16(), 0x4535);
-    assert_eq!(cpu.get_next_u8(), 0x67);
-    assert_eq!(cpu.get_next_u16(), 0x4213);
+    assert_eq!(cpu.mem_read_pc_u16(), 0x4535);
+    assert_eq!(cpu.mem_read_pc_u8(), 0x67);
+    assert_eq!(cpu.mem_read_pc_u16(), 0x4213);
 }
 
 #[test]
 fn test_get_addr_8bit() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
     cpu.reg_x = 2;
     cpu.reg_y = 3;
 
-    set_multiple_bytes(&mut cpu, 200, &vec![0x35, 0x45, 0x67, 0x13, 0x42]);
+    set_multiple_bytes(&mut cpu, 200, &[0x35, 0x45, 0x67, 0x13, 0x42]);
 
     assert_eq!(cpu.get_addr_8bit(200, AddressingMode::ZeroPage), 200);
     assert_eq!(cpu.get_addr_8bit(200, AddressingMode::ZeroPageX), 202);
@@ -55,11 +55,11 @@ fn test_get_addr_8bit() {
 
 #[test]
 fn test_get_addr_16bit() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
     cpu.reg_x = 3;
     cpu.reg_y = 2;
 
-    set_multiple_bytes(&mut cpu, 0x1234, &vec![0x35, 0x45, 0x67, 0x13, 0x42]);
+    set_multiple_bytes(&mut cpu, 0x1234, &[0x35, 0x45, 0x67, 0x13, 0x42]);
 
     assert_eq!(cpu.get_addr_16bit(0x1234, AddressingMode::Absolute), 0x1234);
     assert_eq!(
@@ -73,14 +73,47 @@ fn test_get_addr_16bit() {
     assert_eq!(cpu.get_addr_16bit(0x1234, AddressingMode::Indirect), 0x4535);
 }
 
+#[test]
+fn test_jam() {
+    let mut cpu = test_cpu();
+    set_program(&mut cpu, 0, "NOP\nJAM");
+    set_multiple_bytes(&mut cpu, 0xfffc, &[0x00, 0x00]);
+
+    cpu.run_count(2);
+    assert!(cpu.jammed());
+    assert_eq!(cpu.program_counter, 0x0001);
+
+    // stuck on the opcode, deaf to interrupts, but time goes on
+    cpu.mem_bus.nmi = true;
+    let cycles = cpu.cycle_count;
+    cpu.run_count(3);
+    assert_eq!(cpu.program_counter, 0x0001);
+    assert_eq!(cpu.cycle_count, cycles + 3);
+
+    cpu.reset();
+    assert!(!cpu.jammed());
+    assert_eq!(cpu.program_counter, 0x0000);
+}
+
+#[test]
+fn test_stp() {
+    let mut cpu = test_cpu();
+    cpu.variant = Variant::Wdc65C02;
+    set_program(&mut cpu, 0, "STP");
+
+    cpu.run_once();
+    assert!(cpu.jammed());
+    assert_eq!(cpu.program_counter, 0x0000);
+}
+
 #[test]
 fn test_fetch_value() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     set_byte_example(&mut cpu);
-    set_multiple_bytes(&mut cpu, 0, &vec![0x35, 0x45, 0x67, 0x13, 0x42]);
+    set_multiple_bytes(&mut cpu, 0, &[0x35, 0x45, 0x67, 0x13, 0x42]);
 
-    assert_eq!(cpu.fetch_value(IN::AND_IM), 0x35);
-    assert_eq!(cpu.fetch_value(IN::AND_Z), get_example_byte(0x45));
-    assert_eq!(cpu.fetch_value(IN::AND_A), get_example_byte(0x1367));
+    assert_eq!(cpu.fetch_ins_operand(IN::AND_IM), 0x35);
+    assert_eq!(cpu.fetch_ins_operand(IN::AND_Z), get_example_byte(0x45));
+    assert_eq!(cpu.fetch_ins_operand(IN::AND_A), get_example_byte(0x1367));
 }
@@ -1,10 +1,10 @@
 // PHA, PLS, PHP, PLP, TXS, TSX
-use crate::cpu::{instructions, StatusFlags, CPU};
+use crate::cpu::{instructions, tests::test_cpu, StatusFlags};
 use instructions as IN;
 
 #[test]
 fn test_pha() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.accumulator = 0x15;
 
@@ -16,19 +16,19 @@ fn test_pha() {
 
     assert_eq!(cpu.stack_pointer, 253);
 
-    assert_eq!(cpu.pull_stack(), 0x88);
+    assert_eq!(cpu.stack_pull_u8(), 0x88);
     assert_eq!(cpu.stack_pointer, 254);
 
-    assert_eq!(cpu.pull_stack(), 0x15);
+    assert_eq!(cpu.stack_pull_u8(), 0x15);
     assert_eq!(cpu.stack_pointer, 255);
 
-    assert_eq!(cpu.pull_stack(), 0);
+    assert_eq!(cpu.stack_pull_u8(), 0);
     assert_eq!(cpu.stack_pointer, 0);
 }
 
 #[test]
 fn test_php() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.flags = StatusFlags::from_bits_truncate(0b10101100);
 
@@ -40,22 +40,22 @@ fn test_php() {
 
     assert_eq!(cpu.stack_pointer, 253);
 
-    assert_eq!(cpu.pull_stack(), 0b10110111);
+    assert_eq!(cpu.stack_pull_u8(), 0b10110111);
     assert_eq!(cpu.stack_pointer, 254);
 
-    assert_eq!(cpu.pull_stack(), 0b10111100);
+    assert_eq!(cpu.stack_pull_u8(), 0b10111100);
     assert_eq!(cpu.stack_pointer, 255);
 
-    assert_eq!(cpu.pull_stack(), 0);
+    assert_eq!(cpu.stack_pull_u8(), 0);
     assert_eq!(cpu.stack_pointer, 0);
 }
 
 #[test]
 fn test_pla() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.push_stack(0xf3);
-    cpu.push_stack(0x61);
+    cpu.stack_push_u8(0xf3);
+    cpu.stack_push_u8(0x61);
 
     cpu.execute(IN::PLA);
 
@@ -75,30 +75,30 @@ fn test_pla() {
 
 #[test]
 fn test_plp() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
-    cpu.push_stack(0b10111100);
-    cpu.push_stack(0b00110000);
+    cpu.stack_push_u8(0b10111100);
+    cpu.stack_push_u8(0b00110000);
 
     cpu.execute(IN::PLP);
 
-    assert_eq!(cpu.flags.bits(), 0b00110000);
+    assert_eq!(cpu.flags.bits(), 0b00100000);
     assert_eq!(cpu.stack_pointer, 254);
 
     cpu.execute(IN::PLP);
 
-    assert_eq!(cpu.flags.bits(), 0b10111100);
+    assert_eq!(cpu.flags.bits(), 0b10101100);
     assert_eq!(cpu.stack_pointer, 255);
 
     cpu.execute(IN::PLP);
 
-    assert_eq!(cpu.flags.bits(), 0);
+    assert_eq!(cpu.flags.bits(), 0b00100000);
     assert_eq!(cpu.stack_pointer, 0);
 }
 
 #[test]
 fn test_txs() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.reg_x = 0x81;
 
@@ -109,29 +109,29 @@ fn test_txs() {
 
 #[test]
 fn test_tsx() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.stack_pointer = 0;
 
     cpu.execute(IN::TSX);
 
     assert_eq!(cpu.reg_x, 0);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 1);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 
     cpu.stack_pointer = 0x3f;
 
     cpu.execute(IN::TSX);
 
     assert_eq!(cpu.reg_x, 0x3f);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 
     cpu.stack_pointer = 0xde;
 
     cpu.execute(IN::TSX);
 
     assert_eq!(cpu.reg_x, 0xde);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
 }
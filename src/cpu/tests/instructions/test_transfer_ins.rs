@@ -1,10 +1,10 @@
 // TAX, TXA, TAY, TYA
-use crate::cpu::{instructions, StatusFlags, CPU};
+use crate::cpu::{instructions, tests::test_cpu, StatusFlags};
 use instructions as IN;
 
 #[test]
 fn test_tax() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.accumulator = 0x12;
     cpu.reg_x = 0x34;
@@ -14,13 +14,13 @@ fn test_tax() {
 
     assert_eq!(cpu.accumulator, 0x12);
     assert_eq!(cpu.reg_x, 0x12);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 }
 
 #[test]
 fn test_txa() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.accumulator = 0x12;
     cpu.reg_x = 0x84;
@@ -30,13 +30,13 @@ fn test_txa() {
 
     assert_eq!(cpu.accumulator, 0x84);
     assert_eq!(cpu.reg_x, 0x84);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
 }
 
 #[test]
 fn test_tay() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.accumulator = 0x12;
     cpu.reg_y = 0x34;
@@ -46,13 +46,13 @@ fn test_tay() {
 
     assert_eq!(cpu.accumulator, 0x12);
     assert_eq!(cpu.reg_y, 0x12);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
 }
 
 #[test]
 fn test_tya() {
-    let mut cpu = CPU::new();
+    let mut cpu = test_cpu();
 
     cpu.accumulator = 0x12;
     cpu.reg_y = 0x84;
@@ -62,6 +62,6 @@ fn test_tya() {
 
     assert_eq!(cpu.accumulator, 0x84);
     assert_eq!(cpu.reg_y, 0x84);
-    assert_eq!(cpu.get_flag(StatusFlags::ZERO), 0);
-    assert_eq!(cpu.get_flag(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
 }
@@ -0,0 +1,137 @@
+// LAX, SAX, DCP, ISB, ARR, AXS, NOP (unofficial)
+
+use crate::cpu::{
+    instructions,
+    tests::{set_multiple_bytes, test_cpu},
+    StatusFlags,
+};
+use crate::memory::memory_bus::Bus;
+use instructions as IN;
+
+#[test]
+fn test_lax() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x10]);
+    cpu.mem_bus.write(0x10, 0x80);
+
+    cpu.execute(IN::LAX_Z);
+    assert_eq!(cpu.accumulator, 0x80);
+    assert_eq!(cpu.reg_x, 0x80);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.program_counter, 2);
+    assert_eq!(cpu.cycle_count, 3);
+}
+
+#[test]
+fn test_sax() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x10]);
+    cpu.accumulator = 0b1100_1100;
+    cpu.reg_x = 0b1010_1010;
+
+    // stores a & x and leaves the flags alone
+    cpu.execute(IN::SAX_Z);
+    assert_eq!(cpu.mem_bus.read(0x10), 0b1000_1000);
+    assert_eq!(cpu.flags.bits(), 0);
+}
+
+#[test]
+fn test_dcp() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x10, 0, 0x10]);
+    cpu.mem_bus.write(0x10, 0x43);
+    cpu.accumulator = 0x42;
+
+    cpu.execute(IN::DCP_Z);
+    assert_eq!(cpu.mem_bus.read(0x10), 0x42);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 1);
+    assert_eq!(cpu.cycle_count, 5);
+
+    cpu.execute(IN::DCP_Z);
+    assert_eq!(cpu.mem_bus.read(0x10), 0x41);
+    assert_eq!(cpu.get_status_bit(StatusFlags::ZERO), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 1);
+}
+
+#[test]
+fn test_isb() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x10]);
+    cpu.mem_bus.write(0x10, 0x0f);
+    cpu.accumulator = 0x50;
+    cpu.flags.insert(StatusFlags::CARRY);
+
+    cpu.execute(IN::ISB_Z);
+    assert_eq!(cpu.mem_bus.read(0x10), 0x10);
+    assert_eq!(cpu.accumulator, 0x40);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::OVERFLOW), 0);
+}
+
+#[test]
+fn test_arr() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0xff, 0, 0x7f]);
+
+    // carry comes from bit 6 of the result, overflow from bit 6 ^ bit 5
+    cpu.accumulator = 0xc0;
+    cpu.flags.insert(StatusFlags::CARRY);
+    cpu.execute(IN::ARR_IM);
+    assert_eq!(cpu.accumulator, 0xe0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::OVERFLOW), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+
+    cpu.accumulator = 0xff;
+    cpu.flags.remove(StatusFlags::CARRY);
+    cpu.execute(IN::ARR_IM);
+    assert_eq!(cpu.accumulator, 0x3f);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::OVERFLOW), 1);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 0);
+}
+
+#[test]
+fn test_axs() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0, 0x02, 0, 0x10]);
+    cpu.accumulator = 0x0f;
+    cpu.reg_x = 0xfc;
+
+    // no borrow in: the carry flag doesn't feed into the subtraction
+    cpu.execute(IN::AXS_IM);
+    assert_eq!(cpu.reg_x, 0x0a);
+    assert_eq!(cpu.accumulator, 0x0f);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 1);
+
+    cpu.execute(IN::AXS_IM);
+    assert_eq!(cpu.reg_x, 0xfa);
+    assert_eq!(cpu.get_status_bit(StatusFlags::CARRY), 0);
+    assert_eq!(cpu.get_status_bit(StatusFlags::NEGATIVE), 1);
+}
+
+#[test]
+fn test_multi_byte_nop() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0x0c, 0x34, 0x12, 0x1c, 0xff, 0x12]);
+    cpu.reg_x = 1;
+
+    cpu.tick();
+    assert_eq!(cpu.program_counter, 3);
+    assert_eq!(cpu.cycle_count, 4);
+
+    // the dummy read still pays for the page cross
+    cpu.tick();
+    assert_eq!(cpu.program_counter, 6);
+    assert_eq!(cpu.cycle_count, 9);
+    assert_eq!(cpu.flags.bits(), 0);
+}
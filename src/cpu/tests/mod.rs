@@ -1,21 +1,59 @@
 #![cfg(test)]
 
-use super::CPU;
+use super::{StatusFlags, CPU};
+use crate::{
+    asm::{Assembler, Program},
+    memory::{flat_ram_bus::FlatRamBus, memory_bus::Bus},
+};
+
 pub mod instructions;
+pub mod nestest;
+pub mod single_step;
+pub mod test_call_stack;
 pub mod test_flags;
+pub mod test_interrupts;
+pub mod test_logging;
 pub mod test_memory;
+pub mod test_profiler;
+
+// blank cpu on a flat ram bus: all registers and flags cleared, stack pointer at the top
+pub fn test_cpu() -> CPU<FlatRamBus> {
+    let mut cpu = CPU::new_program(false, FlatRamBus::new(), None);
+    cpu.stack_pointer = 0xff;
+    cpu.flags = StatusFlags::empty();
+    cpu
+}
 
-pub fn set_single_byte(cpu: &mut CPU, address: u16, byte: u8) {
-    cpu.memory.write(address, byte);
+// test_cpu with `src` assembled at $0200 and pc on its first instruction
+pub fn program_cpu(src: &str) -> (CPU<FlatRamBus>, Program) {
+    let mut cpu = test_cpu();
+    let program = Assembler::new().origin(0x0200).assemble(src).unwrap();
+    program.load(&mut cpu.mem_bus);
+    cpu.program_counter = 0x0200;
+    (cpu, program)
 }
 
-pub fn set_multiple_bytes(cpu: &mut CPU, start_address: u16, bytes: &Vec<u8>) {
-    for i in 0..bytes.len() {
-        set_single_byte(cpu, start_address + i as u16, bytes[i]);
+pub fn set_single_byte(cpu: &mut CPU<FlatRamBus>, address: u16, byte: u8) {
+    cpu.mem_bus.write(address, byte);
+}
+
+pub fn set_multiple_bytes(cpu: &mut CPU<FlatRamBus>, start_address: u16, bytes: &[u8]) {
+    for (i, byte) in bytes.iter().enumerate() {
+        set_single_byte(cpu, start_address + i as u16, *byte);
     }
 }
 
-pub fn set_byte_example(cpu: &mut CPU) {
+// assembles `src` in place at `start_address`
+pub fn set_program(cpu: &mut CPU<FlatRamBus>, start_address: u16, src: &str) {
+    let program = Assembler::new()
+        .origin(start_address)
+        .variant(cpu.variant)
+        .assemble(src)
+        .unwrap();
+    program.load(&mut cpu.mem_bus);
+}
+
+pub fn set_byte_example(cpu: &mut CPU<FlatRamBus>) {
     for i in 0..0x1fff_u16 {
         let val = i.wrapping_add(1857).wrapping_mul(937) as u8;
         set_single_byte(cpu, i, val);
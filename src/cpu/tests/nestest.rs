@@ -0,0 +1,104 @@
+// golden trace check against nestest.log. nestest.nes is run in automation mode (straight from
+// $C000, no controller needed) and every logged_execute line is compared with the reference log.
+// point NESTEST_DIR at a directory holding nestest.nes and nestest.log to run it, otherwise the
+// test is skipped
+use std::{env, fs, path::Path};
+
+use crate::{
+    cpu::{
+        instructions::{get_instruction, LDA_AX, LDA_IY},
+        tests::{set_multiple_bytes, test_cpu},
+        CPU,
+    },
+    memory::{
+        cartridge::Cartridge,
+        memory_bus::{Bus, MemoryBus},
+    },
+    ppu::PPU,
+};
+
+const CONTEXT_LINES: usize = 5;
+
+// walks the reference log, pulling one trace line per entry, and stops at the first line that
+// differs. returns the number of matching lines, or a diff with the lines leading up to it
+fn compare_trace(reference: &str, mut next_line: impl FnMut() -> String) -> Result<usize, String> {
+    let expected: Vec<&str> = reference.lines().map(str::trim_end).collect();
+
+    for (i, exp) in expected.iter().enumerate() {
+        let got = next_line();
+        if got.trim_end() == *exp {
+            continue;
+        }
+
+        let mut diff = format!("trace differs at line {}:\n", i + 1);
+        for line in &expected[i.saturating_sub(CONTEXT_LINES)..i] {
+            diff.push_str(&format!("  {line}\n"));
+        }
+        diff.push_str(&format!("- {exp}\n+ {}\n", got.trim_end()));
+        for line in expected.iter().skip(i + 1).take(CONTEXT_LINES) {
+            diff.push_str(&format!("  {line}\n"));
+        }
+        return Err(diff);
+    }
+
+    Ok(expected.len())
+}
+
+#[test]
+fn test_nestest_trace() {
+    let Ok(dir) = env::var("NESTEST_DIR") else {
+        eprintln!("NESTEST_DIR not set, skipping nestest trace");
+        return;
+    };
+    let rom = fs::read(Path::new(&dir).join("nestest.nes")).unwrap();
+    let reference = fs::read_to_string(Path::new(&dir).join("nestest.log")).unwrap();
+
+    let mem_bus = MemoryBus::new(PPU::new(Cartridge::new(rom).unwrap()));
+    let mut cpu = CPU::new_program(false, mem_bus, None);
+    cpu.reset();
+    cpu.program_counter = 0xc000;
+
+    let result = compare_trace(&reference, || {
+        let ins = get_instruction(cpu.mem_bus.dbg_read(cpu.program_counter));
+        let line = cpu.logged_execute(ins);
+        cpu.run_once();
+        line
+    });
+
+    if let Err(diff) = result {
+        panic!("{diff}");
+    }
+}
+
+#[test]
+fn test_compare_trace() {
+    let reference = "A\nB\nC\nD\n";
+
+    let mut lines = ["A", "B", "C", "D"].into_iter();
+    assert_eq!(
+        compare_trace(reference, || lines.next().unwrap().into()),
+        Ok(4)
+    );
+
+    let mut lines = ["A", "B", "X", "D"].into_iter();
+    let diff = compare_trace(reference, || lines.next().unwrap().into()).unwrap_err();
+    assert_eq!(diff, "trace differs at line 3:\n  A\n  B\n- C\n+ X\n  D\n");
+}
+
+#[test]
+fn test_trace_effective_address() {
+    let mut cpu = test_cpu();
+
+    set_multiple_bytes(&mut cpu, 0, &[0xbd, 0x80, 0x02, 0xb1, 0x10]);
+    set_multiple_bytes(&mut cpu, 0x10, &[0xf0, 0x02]);
+    set_multiple_bytes(&mut cpu, 0x0301, &[0x5a]);
+    cpu.reg_x = 0x81;
+    cpu.reg_y = 0x11;
+
+    let line = cpu.logged_execute(LDA_AX);
+    assert!(line.starts_with("0000  BD 80 02  LDA $0280,X @ 0301 = 5A "));
+
+    cpu.program_counter = 3;
+    let line = cpu.logged_execute(LDA_IY);
+    assert!(line.starts_with("0003  B1 10     LDA ($10),Y = 02F0 @ 0301 = 5A "));
+}
@@ -0,0 +1,324 @@
+// runner for the per-opcode single-step vectors in the ProcessorTests format
+// (https://github.com/SingleStepTests/65x02, the nes6502 set: one XX.json per opcode, no BCD).
+// the vectors are too big to vendor so the test is ignored by default, run it against a local
+// checkout with
+//   PROCESSOR_TESTS_DIR=path/to/65x02/nes6502/v1 cargo test test_single_step_vectors -- --ignored
+use std::{collections::BTreeMap, env, fmt, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    cpu::{
+        instructions::{get_instruction, InstructionName, Variant},
+        StatusFlags, CPU,
+    },
+    memory::{flat_ram_bus::FlatRamBus, memory_bus::Bus},
+};
+
+// failures printed per opcode, the rest are only counted
+const REPORTED_PER_OPCODE: usize = 3;
+
+// B and U have no storage in the real register, they only exist on the stack
+const STATUS_MASK: u8 = !(StatusFlags::BREAK.bits() | StatusFlags::BREAK2_U.bits());
+
+#[derive(Deserialize)]
+struct TestCase {
+    name: String,
+    initial: CpuState,
+    #[serde(rename = "final")]
+    final_state: CpuState,
+    cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Deserialize)]
+struct CpuState {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(PartialEq)]
+enum BusAccess {
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+impl fmt::Debug for BusAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BusAccess::Read(addr, val) => write!(f, "R {addr:04X}={val:02X}"),
+            BusAccess::Write(addr, val) => write!(f, "W {addr:04X}={val:02X}"),
+        }
+    }
+}
+
+// flat ram that remembers every read and write the cpu makes, in order
+#[derive(Default)]
+struct RecordingBus {
+    ram: FlatRamBus,
+    accesses: Vec<BusAccess>,
+}
+
+impl Bus for RecordingBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        let val = self.ram.read(addr);
+        self.accesses.push(BusAccess::Read(addr, val));
+        val
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.accesses.push(BusAccess::Write(addr, val));
+        self.ram.write(addr, val);
+    }
+
+    fn dbg_read(&self, addr: u16) -> u8 {
+        self.ram.dbg_read(addr)
+    }
+
+    fn tick(&mut self, cycles: usize) {
+        self.ram.tick(cycles);
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        false
+    }
+
+    fn irq_asserted(&self) -> bool {
+        false
+    }
+}
+
+// runs one vector, returns a description of every way the end state differs
+fn run_case(case: &TestCase) -> Vec<String> {
+    let init = &case.initial;
+    let mut cpu = CPU::new_program(false, RecordingBus::default(), None);
+    cpu.program_counter = init.pc;
+    cpu.stack_pointer = init.s;
+    cpu.accumulator = init.a;
+    cpu.reg_x = init.x;
+    cpu.reg_y = init.y;
+    cpu.flags = StatusFlags::from_bits_retain(init.p);
+    for &(addr, val) in &init.ram {
+        cpu.mem_bus.ram.write(addr, val);
+    }
+
+    let cycles = cpu.tick();
+
+    let fin = &case.final_state;
+    let mut diffs = vec![];
+    let mut check = |what: &str, got: u16, expected: u16| {
+        if got != expected {
+            diffs.push(format!("{what}: got {got:02X}, expected {expected:02X}"));
+        }
+    };
+    check("PC", cpu.program_counter, fin.pc);
+    check("S", cpu.stack_pointer as u16, fin.s as u16);
+    check("A", cpu.accumulator as u16, fin.a as u16);
+    check("X", cpu.reg_x as u16, fin.x as u16);
+    check("Y", cpu.reg_y as u16, fin.y as u16);
+    check(
+        "P",
+        (cpu.flags.bits() & STATUS_MASK) as u16,
+        (fin.p & STATUS_MASK) as u16,
+    );
+    check("cycles", cycles as u16, case.cycles.len() as u16);
+    for &(addr, val) in &fin.ram {
+        let got = cpu.mem_bus.dbg_read(addr);
+        if got != val {
+            diffs.push(format!("[{addr:04X}]: got {got:02X}, expected {val:02X}"));
+        }
+    }
+
+    let expected: Vec<BusAccess> = case
+        .cycles
+        .iter()
+        .map(|(addr, val, kind)| match kind.as_str() {
+            "write" => BusAccess::Write(*addr, *val),
+            _ => BusAccess::Read(*addr, *val),
+        })
+        .collect();
+    if cpu.mem_bus.accesses != expected {
+        diffs.push(format!(
+            "bus: got {:?}, expected {:?}",
+            cpu.mem_bus.accesses, expected
+        ));
+    }
+
+    diffs
+}
+
+// runs every case in one opcode file, returns (cases run, failure reports)
+fn run_file(path: &Path) -> (usize, Vec<String>) {
+    let json = fs::read_to_string(path).unwrap();
+    let cases: Vec<TestCase> = serde_json::from_str(&json).unwrap();
+
+    let failures = cases
+        .iter()
+        .filter_map(|case| {
+            let diffs = run_case(case);
+            (!diffs.is_empty()).then(|| format!("  {}\n    {}", case.name, diffs.join("\n    ")))
+        })
+        .collect();
+
+    (cases.len(), failures)
+}
+
+#[test]
+#[ignore = "needs PROCESSOR_TESTS_DIR, see the top of this file"]
+fn test_single_step_vectors() {
+    let dir = env::var("PROCESSOR_TESTS_DIR").expect("PROCESSOR_TESTS_DIR not set");
+
+    let mut files_run = 0;
+    let mut failed: BTreeMap<u8, (usize, Vec<String>)> = BTreeMap::new();
+    for opcode in 0..=255_u8 {
+        // the jams lock the cpu up, their vectors only describe the bus noise while halted
+        if get_instruction(opcode).name == InstructionName::JAM {
+            continue;
+        }
+        let path = Path::new(&dir).join(format!("{opcode:02x}.json"));
+        if !path.exists() {
+            continue;
+        }
+
+        let (total, failures) = run_file(&path);
+        files_run += 1;
+        if !failures.is_empty() {
+            failed.insert(opcode, (total, failures));
+        }
+    }
+
+    for (opcode, (total, failures)) in &failed {
+        let ins = get_instruction(*opcode);
+        eprintln!(
+            "{opcode:02X} {:?} {:?}: {}/{total} failed",
+            ins.name,
+            ins.mode,
+            failures.len()
+        );
+        for failure in failures.iter().take(REPORTED_PER_OPCODE) {
+            eprintln!("{failure}");
+        }
+    }
+    // a mistyped path would otherwise pass without running anything
+    assert!(files_run > 0, "no XX.json vectors in {dir}");
+    assert!(failed.is_empty(), "{} opcodes failed", failed.len());
+}
+
+#[test]
+fn test_single_step_case() {
+    // LDA #$F4 in the vector file format, then again with the opcode on $FFFF so pc wraps
+    let cases = [
+        r#"{
+            "name": "a9 f4 7c",
+            "initial": { "pc": 1234, "s": 253, "a": 0, "x": 0, "y": 0, "p": 38,
+                         "ram": [[1234, 169], [1235, 244]] },
+            "final": { "pc": 1236, "s": 253, "a": 244, "x": 0, "y": 0, "p": 164,
+                       "ram": [[1234, 169], [1235, 244]] },
+            "cycles": [[1234, 169, "read"], [1235, 244, "read"]]
+        }"#,
+        r#"{
+            "name": "a9 f4 wrap",
+            "initial": { "pc": 65535, "s": 253, "a": 0, "x": 0, "y": 0, "p": 38,
+                         "ram": [[65535, 169], [0, 244]] },
+            "final": { "pc": 1, "s": 253, "a": 244, "x": 0, "y": 0, "p": 164,
+                       "ram": [[65535, 169], [0, 244]] },
+            "cycles": [[65535, 169, "read"], [0, 244, "read"]]
+        }"#,
+    ];
+
+    for case in cases {
+        let case: TestCase = serde_json::from_str(case).unwrap();
+        assert_eq!(run_case(&case), Vec::<String>::new(), "{}", case.name);
+    }
+}
+
+#[test]
+fn test_one_bus_access_per_cycle() {
+    // every 6502 cycle is a bus access, so with the dummy reads and writes in place the number
+    // of accesses has to match the cycle count, and stepped mode clocks the bus once for each
+    let mut seed: u32 = 0x1234_5678;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    let mut ram = FlatRamBus::new().ram;
+    for byte in ram.iter_mut() {
+        *byte = next() as u8;
+    }
+
+    // the nmos part with decimal mode on and the 65C02 add their own bcd and fixup cycles
+    let runs = [
+        (Variant::Ricoh2A03, StatusFlags::empty()),
+        (Variant::Nmos6502, StatusFlags::DECIMAL),
+        (Variant::Wdc65C02, StatusFlags::DECIMAL),
+    ];
+
+    for (variant, forced_flags) in runs {
+        for opcode in 0..=255_u8 {
+            if variant.decode(opcode).name == InstructionName::JAM {
+                continue;
+            }
+
+            for _ in 0..64 {
+                let mut cpu = CPU::new_program(false, RecordingBus::default(), None);
+                cpu.variant = variant;
+                cpu.cycle_stepped = true;
+                cpu.program_counter = next() as u16;
+                cpu.stack_pointer = next() as u8;
+                cpu.accumulator = next() as u8;
+                cpu.reg_x = next() as u8;
+                cpu.reg_y = next() as u8;
+                cpu.flags = StatusFlags::from_bits_retain(next() as u8) | forced_flags;
+                cpu.mem_bus.ram.ram.copy_from_slice(&ram[..]);
+                cpu.mem_bus.ram.write(cpu.program_counter, opcode);
+
+                let cycles = cpu.tick();
+                let context = format!("{variant:?} opcode {opcode:02X}");
+                assert_eq!(cpu.mem_bus.accesses.len(), cycles, "{context}");
+                assert_eq!(cpu.mem_bus.ram.cycle_count, cycles, "{context}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_dummy_accesses() {
+    let mut cpu = CPU::new_program(false, RecordingBus::default(), None);
+    cpu.mem_bus.ram.load(0, &[0xe6, 0x10, 0x9d, 0xff, 0x12]);
+    cpu.mem_bus.ram.write(0x10, 0x41);
+    cpu.reg_x = 0x01;
+
+    // INC $10: the old value is written back before the new one
+    cpu.tick();
+    assert_eq!(
+        cpu.mem_bus.accesses,
+        [
+            BusAccess::Read(0x0000, 0xe6),
+            BusAccess::Read(0x0001, 0x10),
+            BusAccess::Read(0x0010, 0x41),
+            BusAccess::Write(0x0010, 0x41),
+            BusAccess::Write(0x0010, 0x42),
+        ]
+    );
+
+    // STA $12FF,X: reads from the wrong page before writing
+    cpu.mem_bus.accesses.clear();
+    cpu.tick();
+    assert_eq!(
+        cpu.mem_bus.accesses,
+        [
+            BusAccess::Read(0x0002, 0x9d),
+            BusAccess::Read(0x0003, 0xff),
+            BusAccess::Read(0x0004, 0x12),
+            BusAccess::Read(0x1200, 0x00),
+            BusAccess::Write(0x1300, 0x00),
+        ]
+    );
+}
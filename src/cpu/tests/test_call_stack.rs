@@ -0,0 +1,157 @@
+use crate::{
+    cpu::{
+        call_stack::{CallStack, Frame, FrameKind},
+        tests::program_cpu,
+        CPU,
+    },
+    memory::flat_ram_bus::FlatRamBus,
+};
+
+fn run_program(src: &str) -> CPU<FlatRamBus> {
+    let (mut cpu, _) = program_cpu(src);
+    cpu.call_stack = Some(CallStack::default());
+    cpu
+}
+
+fn call_stack(cpu: &CPU<FlatRamBus>) -> &CallStack {
+    cpu.call_stack.as_ref().unwrap()
+}
+
+#[test]
+fn test_call_stack_jsr_rts() {
+    let mut cpu = run_program(
+        "
+        JSR outer
+        NOP
+outer:  JSR inner
+        RTS
+inner:  NOP
+        RTS
+        ",
+    );
+
+    cpu.run_count(2);
+    assert_eq!(cpu.program_counter, 0x0208);
+    assert_eq!(
+        call_stack(&cpu).backtrace(),
+        Ok(&[
+            Frame {
+                kind: FrameKind::Jsr,
+                site: 0x0200,
+                target: 0x0204,
+                ret: 0x0203,
+                sp: 0xfd,
+            },
+            Frame {
+                kind: FrameKind::Jsr,
+                site: 0x0204,
+                target: 0x0208,
+                ret: 0x0207,
+                sp: 0xfb,
+            },
+        ][..])
+    );
+
+    cpu.run_count(2);
+    assert_eq!(call_stack(&cpu).frames().len(), 1);
+    cpu.run_count(1);
+    assert_eq!(call_stack(&cpu).backtrace(), Ok(&[][..]));
+}
+
+#[test]
+fn test_call_stack_interrupts() {
+    let mut cpu = run_program(
+        "
+        NOP
+        .org $0300
+nmi:    NOP
+        RTI
+        .org $FFFA
+        .word nmi
+        ",
+    );
+
+    cpu.mem_bus.nmi = true;
+    cpu.run_once();
+    let frames = call_stack(&cpu).frames();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].kind, FrameKind::Nmi);
+    assert_eq!(frames[0].site, 0x0200);
+
+    cpu.run_once();
+    assert_eq!(call_stack(&cpu).backtrace(), Ok(&[][..]));
+}
+
+#[test]
+fn test_call_stack_rts_jump_table() {
+    let mut cpu = run_program(
+        "
+        JSR dispatch
+        NOP
+dispatch:
+        LDA #>target-1
+        PHA
+        LDA #<target-1
+        PHA
+        RTS
+target: RTS
+        ",
+    );
+
+    cpu.run_count(6);
+    assert_eq!(
+        call_stack(&cpu).desync(),
+        Some("RTS at 020A went to 020B, which no JSR pushed")
+    );
+    assert!(call_stack(&cpu).backtrace().is_err());
+
+    // the jumped-to routine returns through the real frame, which puts it back in sync
+    cpu.run_count(1);
+    assert_eq!(cpu.program_counter, 0x0203);
+    assert_eq!(call_stack(&cpu).backtrace(), Ok(&[][..]));
+}
+
+#[test]
+fn test_call_stack_pla_trick() {
+    let mut cpu = run_program(
+        "
+        JSR outer
+        NOP
+outer:  JSR inner
+        NOP
+inner:  PLA
+        PLA
+        RTS
+        ",
+    );
+
+    cpu.run_count(3);
+    assert_eq!(
+        call_stack(&cpu).desync(),
+        Some("PLA at 0208 pulled the return address pushed at 0204")
+    );
+    assert_eq!(call_stack(&cpu).frames().len(), 1);
+
+    // RTS skips outer and lands back after the first JSR
+    cpu.run_count(2);
+    assert_eq!(cpu.program_counter, 0x0203);
+    assert_eq!(call_stack(&cpu).backtrace(), Ok(&[][..]));
+}
+
+#[test]
+fn test_call_stack_txs() {
+    let mut cpu = run_program(
+        "
+        JSR sub
+sub:    LDX #$FF
+        TXS
+        ",
+    );
+
+    cpu.run_count(3);
+    assert_eq!(
+        call_stack(&cpu).desync(),
+        Some("TXS at 0205 moved the stack pointer from FD to FF")
+    );
+    assert!(call_stack(&cpu).frames().is_empty());
+}
@@ -0,0 +1,140 @@
+#![cfg(test)]
+use crate::{
+    asm::Program,
+    cpu::{
+        tests::{set_multiple_bytes, set_program, test_cpu},
+        StatusFlags, CPU, STACK_START,
+    },
+    memory::{
+        cartridge::{nrom_image, Cartridge},
+        flat_ram_bus::FlatRamBus,
+        memory_bus::{Bus, IrqSource, MemoryBus},
+    },
+    ppu::PPU,
+};
+
+// program at $0200, the irq handler at $0400 is a run of nops
+fn irq_cpu(src: &str) -> CPU<FlatRamBus> {
+    let mut cpu = test_cpu();
+    set_program(&mut cpu, 0x0200, src);
+    set_program(&mut cpu, 0x0400, "NOP\nNOP");
+    set_multiple_bytes(&mut cpu, 0xfffe, &[0x00, 0x04]);
+    cpu.program_counter = 0x0200;
+    cpu
+}
+
+fn pushed_pc(cpu: &mut CPU<FlatRamBus>) -> u16 {
+    let lo = cpu.mem_bus.read(STACK_START + 0xfe);
+    u16::from_le_bytes([lo, cpu.mem_bus.read(STACK_START + 0xff)])
+}
+
+fn pushed_flags(cpu: &mut CPU<FlatRamBus>) -> StatusFlags {
+    StatusFlags::from_bits_truncate(cpu.mem_bus.read(STACK_START + 0xfd))
+}
+
+#[test]
+fn test_irq() {
+    let mut cpu = irq_cpu("NOP");
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 0x0201);
+
+    // taken before the next opcode, then the handler's first instruction runs
+    cpu.mem_bus.irq = true;
+    let cycles = cpu.cycle_count;
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 0x0401);
+    assert_eq!(cpu.cycle_count, cycles + 7 + 2);
+    assert_eq!(cpu.stack_pointer, 0xfc);
+    assert_eq!(pushed_pc(&mut cpu), 0x0201);
+
+    let flags = pushed_flags(&mut cpu);
+    assert!(!flags.contains(StatusFlags::BREAK));
+    assert!(flags.contains(StatusFlags::BREAK2_U));
+    assert!(cpu.flags.contains(StatusFlags::INTERRUPT_DISABLE));
+}
+
+#[test]
+fn test_masked_irq_stays_pending() {
+    let mut cpu = irq_cpu("NOP\nNOP\nCLI\nNOP\nNOP");
+    cpu.flags.insert(StatusFlags::INTERRUPT_DISABLE);
+
+    cpu.mem_bus.irq = true;
+    cpu.run_count(2);
+    assert_eq!(cpu.program_counter, 0x0202);
+    assert_eq!(cpu.stack_pointer, 0xff);
+
+    // still asserted when CLI lets it through
+    cpu.run_count(3);
+    assert_eq!(cpu.program_counter, 0x0401);
+    assert_eq!(pushed_pc(&mut cpu), 0x0204);
+}
+
+#[test]
+fn test_cli_is_one_instruction_late() {
+    let mut cpu = irq_cpu("CLI\nNOP\nNOP");
+    cpu.flags.insert(StatusFlags::INTERRUPT_DISABLE);
+
+    cpu.mem_bus.irq = true;
+    cpu.run_once();
+    assert!(!cpu.flags.contains(StatusFlags::INTERRUPT_DISABLE));
+
+    // the nop after CLI still runs with irqs masked
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 0x0202);
+
+    cpu.run_once();
+    assert_eq!(pushed_pc(&mut cpu), 0x0202);
+}
+
+#[test]
+fn test_sei_is_one_instruction_late() {
+    let mut cpu = irq_cpu("NOP\nSEI\nNOP");
+    cpu.run_once();
+
+    cpu.run_once();
+    assert!(cpu.flags.contains(StatusFlags::INTERRUPT_DISABLE));
+
+    // the irq gets in right after SEI, with I already set in the pushed status
+    cpu.mem_bus.irq = true;
+    cpu.run_once();
+    assert_eq!(pushed_pc(&mut cpu), 0x0202);
+    assert!(pushed_flags(&mut cpu).contains(StatusFlags::INTERRUPT_DISABLE));
+}
+
+#[test]
+fn test_plp_is_one_instruction_late() {
+    // pulls a status with I clear
+    let mut cpu = irq_cpu("LDA #$00\nPHA\nPLP\nNOP\nNOP");
+    cpu.flags.insert(StatusFlags::INTERRUPT_DISABLE);
+    cpu.run_count(2);
+
+    cpu.mem_bus.irq = true;
+    cpu.run_once();
+    assert!(!cpu.flags.contains(StatusFlags::INTERRUPT_DISABLE));
+
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 0x0205);
+
+    cpu.run_once();
+    assert_eq!(cpu.program_counter, 0x0401);
+    assert_eq!(pushed_pc(&mut cpu), 0x0205);
+}
+
+#[test]
+fn test_irq_line_wired_or() {
+    let rom = nrom_image(&Program::default(), &[]);
+    let mut bus = MemoryBus::new(PPU::new(Cartridge::new(rom).unwrap()));
+    assert!(!bus.irq_asserted());
+
+    bus.assert_irq(IrqSource::APU_FRAME_COUNTER);
+    bus.assert_irq(IrqSource::MAPPER);
+    bus.clear_irq(IrqSource::APU_FRAME_COUNTER);
+    assert!(bus.irq_asserted());
+
+    // clearing a source that isn't holding the line changes nothing
+    bus.clear_irq(IrqSource::APU_DMC);
+    assert!(bus.irq_asserted());
+
+    bus.clear_irq(IrqSource::MAPPER);
+    assert!(!bus.irq_asserted());
+}
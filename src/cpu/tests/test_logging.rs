@@ -0,0 +1,101 @@
+use std::{
+    env, fs,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    cpu::tests::{set_multiple_bytes, set_program, test_cpu},
+    loggers::{FileLogger, RingBufferLogger, StatsLogger},
+    LogEvent, LogEventKinds, Logger,
+};
+
+const PROGRAM: &str = "
+    LDA $0200
+    PHA
+    STA $10
+    JAM
+";
+
+fn run_program(logger: Box<dyn Logger>) {
+    let mut cpu = test_cpu();
+    cpu.logger = Some(logger);
+    set_program(&mut cpu, 0, PROGRAM);
+    set_multiple_bytes(&mut cpu, 0x0200, &[0x5a]);
+    for _ in 0..4 {
+        cpu.tick();
+    }
+}
+
+#[test]
+fn test_logged_events() {
+    let ring = Arc::new(Mutex::new(RingBufferLogger::new(64)));
+    run_program(Box::new(ring.clone()));
+
+    let events: Vec<String> = ring
+        .lock()
+        .unwrap()
+        .events()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        events,
+        [
+            "FETCH AD LDA Absolute",
+            "OPERAND 0200",
+            "READ 0200 = 5A",
+            "PC:0003 A:5A X:00 Y:00 P:00 SP:FF CYC:4",
+            "FETCH 48 PHA Implicit",
+            "READ* 0004 = 85",
+            "PUSH 5A",
+            "PC:0004 A:5A X:00 Y:00 P:00 SP:FE CYC:7",
+            "FETCH 85 STA ZeroPage",
+            "OPERAND 0010",
+            "WRITE 0010 = 5A",
+            "PC:0006 A:5A X:00 Y:00 P:00 SP:FE CYC:10",
+            "FETCH 02 JAM Implicit",
+            "READ* 0007 = 00",
+            "BAD OPCODE 02",
+            "PC:0006 A:5A X:00 Y:00 P:00 SP:FE CYC:12",
+        ]
+    );
+}
+
+#[test]
+fn test_ring_buffer_logger() {
+    let mut ring = RingBufferLogger::new(3);
+    for i in 0..5 {
+        ring.log_event(LogEvent::StackPush(i));
+    }
+
+    let kept: Vec<String> = ring.events().map(|e| e.to_string()).collect();
+    assert_eq!(kept, ["PUSH 02", "PUSH 03", "PUSH 04"]);
+}
+
+#[test]
+fn test_stats_logger() {
+    let stats = Arc::new(Mutex::new(StatsLogger::new()));
+    run_program(Box::new(stats.clone()));
+
+    let stats = stats.lock().unwrap();
+    assert_eq!(stats.instructions, 4);
+    assert_eq!(stats.opcodes[0xad], 1);
+    assert_eq!(stats.reads, 1);
+    assert_eq!(stats.writes, 1);
+    assert_eq!(stats.pushes, 1);
+    assert_eq!(stats.bad_opcodes, 1);
+    assert_eq!(stats.top_opcodes(2), [(0x02, 1), (0x48, 1)]);
+}
+
+#[test]
+fn test_file_logger_filter() {
+    let path = env::temp_dir().join(format!("nes_file_logger_{}.log", std::process::id()));
+    let logger = FileLogger::new(
+        &path,
+        LogEventKinds::MEMORY_READ | LogEventKinds::MEMORY_WRITE,
+    );
+    run_program(Box::new(logger.unwrap()));
+
+    let written = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(written, "READ 0200 = 5A\nWRITE 0010 = 5A\n");
+}
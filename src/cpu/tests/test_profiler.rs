@@ -0,0 +1,156 @@
+use crate::{
+    asm::Program,
+    cpu::{instructions::Variant, profiler::RoutineStats, tests::program_cpu, CPU},
+    memory::flat_ram_bus::FlatRamBus,
+};
+
+fn profiled(src: &str) -> (CPU<FlatRamBus>, Program) {
+    let (mut cpu, program) = program_cpu(src);
+    cpu.enable_profiler();
+    (cpu, program)
+}
+
+#[test]
+fn test_profiler_routines() {
+    let (mut cpu, program) = profiled(
+        "
+start:  JSR outer
+        NOP
+        JMP start
+outer:  LDA #$01
+        JSR leaf
+        RTS
+leaf:   NOP
+        RTS
+        ",
+    );
+    let outer = program.label("outer").unwrap();
+    let leaf = program.label("leaf").unwrap();
+
+    // once round, up to the NOP after the first JSR
+    cpu.run_count(7);
+    let profiler = cpu.profiler.as_ref().unwrap();
+    assert_eq!(profiler.total_cycles(), 30);
+    assert_eq!(profiler.instructions(), 7);
+    // the JSR is charged to the caller, the RTS to the routine it returns from
+    assert_eq!(profiler.top_level_cycles(), 8);
+    assert_eq!(
+        profiler.routine(outer),
+        Some(RoutineStats {
+            calls: 1,
+            inclusive: 22,
+            exclusive: 14,
+        })
+    );
+    assert_eq!(
+        profiler.routine(leaf),
+        Some(RoutineStats {
+            calls: 1,
+            inclusive: 8,
+            exclusive: 8,
+        })
+    );
+    assert_eq!(profiler.cycles_at(0x0200), 6);
+    // a flat ram bus never leaves scanline 0
+    assert_eq!(profiler.bands()[0], 30);
+
+    let mut folded = Vec::new();
+    profiler
+        .write_folded(&mut folded, |addr| format!("{addr:04X}"))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "top 8\ntop;0207 14\ntop;0207;020D 8\n"
+    );
+
+    // twice round
+    cpu.run_count(8);
+    let profiler = cpu.profiler.as_ref().unwrap();
+    assert_eq!(profiler.routine(outer).unwrap().calls, 2);
+    assert_eq!(profiler.routine(leaf).unwrap().calls, 2);
+}
+
+#[test]
+fn test_profiler_recursion() {
+    let (mut cpu, program) = profiled(
+        "
+        LDX #$02
+        JSR rec
+        NOP
+rec:    DEX
+        BEQ done
+        JSR rec
+done:   RTS
+        ",
+    );
+
+    cpu.run_count(9);
+    assert_eq!(cpu.program_counter, 0x0205);
+
+    // the inner call's cycles only count once towards the outer call's inclusive time
+    let profiler = cpu.profiler.as_ref().unwrap();
+    assert_eq!(
+        profiler.routine(program.label("rec").unwrap()),
+        Some(RoutineStats {
+            calls: 2,
+            inclusive: 27,
+            exclusive: 27,
+        })
+    );
+}
+
+#[test]
+fn test_profiler_interrupts() {
+    let (mut cpu, program) = profiled(
+        "
+        NOP
+        .org $0300
+nmi:    NOP
+        RTI
+        .org $FFFA
+        .word nmi
+        ",
+    );
+
+    cpu.mem_bus.nmi = true;
+    cpu.run_count(2);
+    // the 7 cycles of interrupt entry belong to the handler
+    let profiler = cpu.profiler.as_ref().unwrap();
+    assert_eq!(profiler.total_cycles(), cpu.cycle_count as u64);
+    assert_eq!(
+        profiler.routine(program.label("nmi").unwrap()),
+        Some(RoutineStats {
+            calls: 1,
+            inclusive: 15,
+            exclusive: 15,
+        })
+    );
+    assert_eq!(profiler.top_level_cycles(), 0);
+    assert_eq!(profiler.cycles_at(0x0300), 9);
+
+    let report = profiler.report(5, |addr| format!("{addr:04X}"));
+    assert!(report.starts_with("15 cycles in 2 instructions\n"));
+    assert!(
+        report.contains("\n0300                          1         15 100.0%         15 100.0%\n")
+    );
+    assert!(report.contains("\n0-7                          15 100.0%\n"));
+}
+
+#[test]
+fn test_profiler_wai() {
+    let (mut cpu, _) = profiled(
+        "
+        .setcpu \"65C02\"
+        WAI
+        ",
+    );
+    cpu.variant = Variant::Wdc65C02;
+
+    // the cycles spent waiting are charged to the WAI
+    cpu.run_count(5);
+    let profiler = cpu.profiler.as_ref().unwrap();
+    assert_eq!(profiler.total_cycles(), 7);
+    assert_eq!(profiler.instructions(), 1);
+    assert_eq!(profiler.cycles_at(0x0200), 7);
+    assert_eq!(profiler.top_level_cycles(), 7);
+}
@@ -0,0 +1,481 @@
+// command engine behind the nes-debug binary. it lives in the library so it can be driven
+// without a terminal: every command is a line of text in, a block of text out
+use std::{collections::BTreeSet, fs};
+
+use crate::{
+    cpu::{
+        call_stack::{CallStack, FrameKind},
+        instructions::InstructionName as IN,
+        profiler::Profiler,
+        StatusFlags, CPU,
+    },
+    disasm::{Disassembler, Item},
+    error::EmulatorError,
+    memory::{
+        cdl::CodeDataLog,
+        memory_bus::{Bus, MemoryBus},
+        watchpoints::{WatchHit, WatchKind},
+    },
+    NESSystem,
+};
+
+pub mod symbols;
+#[cfg(test)]
+mod tests;
+
+use symbols::Symbols;
+
+// continue/until/out give up after this many instructions if nothing stops them
+const RUN_LIMIT: usize = 10_000_000;
+const DIS_BEFORE: usize = 4;
+const DIS_AFTER: usize = 8;
+const MEM_DUMP_LEN: u16 = 64;
+// routines and addresses in a profile report
+const PROFILE_TOP: usize = 20;
+
+const HELP: &str = "\
+s, step [n]            execute n instructions (default 1)
+n, next                step over a JSR
+out, finish            run until the current subroutine returns
+until <addr>           run until pc reaches addr
+c, continue            run until a breakpoint or watchpoint
+b, break <addr>        set a pc breakpoint
+d, delete <addr>       remove a breakpoint or watchpoint
+w, watch [r|w|rw] <addr>  stop when addr is read and/or written (default w)
+bl, breaks             list breakpoints and watchpoints
+r, regs                show registers
+reset                  press the reset button, also un-jams the cpu
+set <a|x|y|sp|p|pc> <val>  edit a register
+m, mem <addr> [len]    dump memory
+l, dis [addr] [count]  disassemble, around pc by default
+bt, backtrace          show the call stack
+sym <file>             load symbols from an ld65 -Ln or FCEUX .nl file
+cdl [file]             show code/data log coverage, or save it as an FCEUX .cdl file
+prof [on|off]          show the profile, or start a fresh one / stop profiling
+prof fold <file>       save the profile as folded stacks for a flamegraph
+an empty line repeats the last command. values are hex, addresses are hex or symbol names";
+
+#[derive(Debug, PartialEq)]
+enum Stop {
+    Done,
+    Breakpoint,
+    Watch(u16, Vec<WatchHit>),
+    Jam(u16),
+    Limit,
+}
+
+pub struct Debugger {
+    pub system: NESSystem,
+    pub symbols: Symbols,
+    breakpoints: BTreeSet<u16>,
+    last_command: String,
+}
+
+impl Debugger {
+    pub fn new(mut system: NESSystem) -> Self {
+        system.cpu_mut().call_stack = Some(CallStack::default());
+        system.cpu_mut().mem_bus.enable_cdl();
+        Debugger {
+            system,
+            symbols: Symbols::default(),
+            breakpoints: BTreeSet::new(),
+            last_command: String::new(),
+        }
+    }
+
+    fn cpu(&self) -> &CPU<MemoryBus> {
+        self.system.cpu()
+    }
+
+    fn profiler(&self) -> Result<&Profiler, String> {
+        self.cpu()
+            .profiler
+            .as_ref()
+            .ok_or_else(|| "the profiler is off, `prof on` starts it".to_string())
+    }
+
+    // symbol or plain address
+    fn name(&self, addr: u16) -> String {
+        self.symbols
+            .describe(addr)
+            .unwrap_or_else(|| format!("{addr:04X}"))
+    }
+
+    fn code_data_log(&self) -> &CodeDataLog {
+        // turned on in new
+        self.system.code_data_log().unwrap()
+    }
+
+    pub fn command(&mut self, line: &str) -> Result<String, String> {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => line.to_string(),
+        };
+        self.last_command = line.clone();
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some((&cmd, args)) = args.split_first() else {
+            return Ok(String::new());
+        };
+
+        match (cmd, args) {
+            ("h" | "help", _) => Ok(HELP.to_string()),
+            ("s" | "step", []) => Ok(self.step(1)),
+            ("s" | "step", [n]) => {
+                let n = n.parse().map_err(|_| format!("bad count {n}"))?;
+                Ok(self.step(n))
+            }
+            ("n" | "next", []) => Ok(self.step_over()),
+            ("out" | "finish", []) => Ok(self.step_out()),
+            ("until", [addr]) => {
+                let target = self.parse_addr(addr)?;
+                let stop = self.run(RUN_LIMIT, |cpu, _| cpu.program_counter == target);
+                Ok(self.report(stop))
+            }
+            ("c" | "continue", []) => {
+                let stop = self.run(RUN_LIMIT, |_, _| false);
+                Ok(self.report(stop))
+            }
+            ("b" | "break", [addr]) => {
+                let addr = self.parse_addr(addr)?;
+                self.breakpoints.insert(addr);
+                Ok(format!("breakpoint at {addr:04X}"))
+            }
+            ("d" | "delete", [addr]) => {
+                let addr = self.parse_addr(addr)?;
+                self.breakpoints.remove(&addr);
+                self.system.cpu_mut().mem_bus.watchpoints.remove(addr);
+                Ok(format!("cleared {addr:04X}"))
+            }
+            ("w" | "watch", [addr]) => self.watch("w", addr),
+            ("w" | "watch", [kinds, addr]) => self.watch(kinds, addr),
+            ("bl" | "breaks", []) => Ok(self.list_breaks()),
+            ("r" | "regs", []) => Ok(self.registers()),
+            ("reset", []) => {
+                self.system.reset();
+                Ok(self.registers())
+            }
+            ("set", [reg, val]) => {
+                self.set_register(reg, parse_hex(val)?)?;
+                Ok(self.registers())
+            }
+            ("m" | "mem", [addr]) => Ok(self.dump(self.parse_addr(addr)?, MEM_DUMP_LEN)),
+            ("m" | "mem", [addr, len]) => Ok(self.dump(self.parse_addr(addr)?, parse_hex(len)?)),
+            ("l" | "dis", []) => Ok(self.disassemble_around(self.cpu().program_counter)),
+            ("l" | "dis", [addr]) => Ok(self.disassemble(self.parse_addr(addr)?, DIS_AFTER)),
+            ("l" | "dis", [addr, count]) => {
+                let count = count.parse().map_err(|_| format!("bad count {count}"))?;
+                Ok(self.disassemble(self.parse_addr(addr)?, count))
+            }
+            ("bt" | "backtrace", []) => Ok(self.backtrace()),
+            ("sym", [path]) => {
+                let text = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+                let loaded = Symbols::parse(&text);
+                let count = loaded.len();
+                self.symbols = loaded;
+                Ok(format!("loaded {count} symbols"))
+            }
+            ("cdl", []) => Ok(self.code_data_log().to_string()),
+            ("cdl", [path]) => {
+                let cdl = self.code_data_log();
+                cdl.save(path).map_err(|e| format!("{path}: {e}"))?;
+                Ok(format!("saved {path}\n{cdl}"))
+            }
+            ("prof", []) => Ok(self.profiler()?.report(PROFILE_TOP, |a| self.name(a))),
+            ("prof", ["on"]) => {
+                self.system.cpu_mut().enable_profiler();
+                Ok("profiling".to_string())
+            }
+            ("prof", ["off"]) => {
+                self.system.cpu_mut().profiler = None;
+                Ok("profiler off".to_string())
+            }
+            ("prof", ["fold", path]) => {
+                let mut file = fs::File::create(path).map_err(|e| format!("{path}: {e}"))?;
+                self.profiler()?
+                    .write_folded(&mut file, |a| self.name(a))
+                    .map_err(|e| format!("{path}: {e}"))?;
+                Ok(format!("saved {path}"))
+            }
+            _ => Err(format!("unknown command `{line}`, try help")),
+        }
+    }
+
+    // runs instructions until `done` says so (it sees the cpu after each one, and the opcode
+    // that was at pc before it), a breakpoint other than the starting pc, or a watchpoint
+    fn run(&mut self, limit: usize, mut done: impl FnMut(&CPU<MemoryBus>, IN) -> bool) -> Stop {
+        self.system.cpu_mut().mem_bus.watchpoints.take_hits();
+
+        for n in 0..limit {
+            let pc = self.cpu().program_counter;
+            if n > 0 && self.breakpoints.contains(&pc) {
+                return Stop::Breakpoint;
+            }
+            let name = self
+                .cpu()
+                .variant
+                .decode(self.cpu().mem_bus.dbg_read(pc))
+                .name;
+
+            if let Err(EmulatorError::Jammed { pc, .. }) = self.system.tick_once() {
+                return Stop::Jam(pc);
+            }
+            let hits = self.system.cpu_mut().mem_bus.watchpoints.take_hits();
+            if !hits.is_empty() {
+                return Stop::Watch(pc, hits);
+            }
+            if done(self.cpu(), name) {
+                return Stop::Done;
+            }
+        }
+        Stop::Limit
+    }
+
+    fn step(&mut self, count: usize) -> String {
+        let count = count.max(1);
+        let mut left = count;
+        let stop = self.run(count, |_, _| {
+            left -= 1;
+            left == 0
+        });
+        self.report(stop)
+    }
+
+    fn step_over(&mut self) -> String {
+        let cpu = self.cpu();
+        let pc = cpu.program_counter;
+        if cpu.variant.decode(cpu.mem_bus.dbg_read(pc)).name != IN::JSR {
+            return self.step(1);
+        }
+
+        let (ret, sp) = (pc.wrapping_add(3), cpu.stack_pointer);
+        let stop = self.run(RUN_LIMIT, |cpu, _| {
+            cpu.program_counter == ret && cpu.stack_pointer == sp
+        });
+        self.report(stop)
+    }
+
+    fn step_out(&mut self) -> String {
+        let sp = self.cpu().stack_pointer;
+        let stop = self.run(RUN_LIMIT, |cpu, name| {
+            matches!(name, IN::RTS | IN::RTI) && cpu.stack_pointer > sp
+        });
+        self.report(stop)
+    }
+
+    fn report(&self, stop: Stop) -> String {
+        let mut out = match stop {
+            Stop::Done => String::new(),
+            Stop::Breakpoint => format!("breakpoint at {:04X}\n", self.cpu().program_counter),
+            Stop::Watch(pc, hits) => hits
+                .iter()
+                .map(|hit| {
+                    let kind = match hit.kind {
+                        WatchKind::Read => "read",
+                        WatchKind::Write => "write",
+                    };
+                    format!(
+                        "watch: {kind} {:04X} = {:02X} by {pc:04X}\n",
+                        hit.addr, hit.val
+                    )
+                })
+                .collect(),
+            Stop::Jam(pc) => format!("cpu jammed at {pc:04X}\n"),
+            Stop::Limit => format!("stopped after {RUN_LIMIT} instructions\n"),
+        };
+        if !out.is_empty() {
+            out.push_str(&self.backtrace());
+            out.push('\n');
+        }
+        out.push_str(&self.disassemble(self.cpu().program_counter, 1));
+        out
+    }
+
+    // innermost first, starting with pc itself
+    fn backtrace(&self) -> String {
+        let Some(call_stack) = self.cpu().call_stack.as_ref() else {
+            return "no call stack".to_string();
+        };
+        let frames = match call_stack.backtrace() {
+            Ok(frames) => frames,
+            Err(reason) => return format!("backtrace unavailable: {reason}"),
+        };
+
+        let describe = |addr: u16| match self.symbols.describe(addr) {
+            Some(name) => format!("{addr:04X}  {name}"),
+            None => format!("{addr:04X}"),
+        };
+        let mut lines = vec![format!("#0  {}", describe(self.cpu().program_counter))];
+        for (i, frame) in frames.iter().rev().enumerate() {
+            let kind = match frame.kind {
+                FrameKind::Jsr => "",
+                FrameKind::Nmi => "  <nmi>",
+                FrameKind::Irq => "  <irq>",
+                FrameKind::Brk => "  <brk>",
+            };
+            lines.push(format!("#{}  {}{kind}", i + 1, describe(frame.site)));
+        }
+        lines.join("\n")
+    }
+
+    fn parse_addr(&self, text: &str) -> Result<u16, String> {
+        match self.symbols.lookup(text) {
+            Some(addr) => Ok(addr),
+            None => parse_hex(text),
+        }
+    }
+
+    fn watch(&mut self, kinds: &str, addr: &str) -> Result<String, String> {
+        let addr = self.parse_addr(addr)?;
+        let kinds = match kinds {
+            "r" => vec![WatchKind::Read],
+            "w" => vec![WatchKind::Write],
+            "rw" => vec![WatchKind::Read, WatchKind::Write],
+            _ => return Err(format!("watch kind is r, w or rw, not {kinds}")),
+        };
+        for kind in kinds {
+            self.system.cpu_mut().mem_bus.watchpoints.add(kind, addr);
+        }
+        Ok(format!("watching {addr:04X}"))
+    }
+
+    fn list_breaks(&self) -> String {
+        let breaks = self
+            .breakpoints
+            .iter()
+            .map(|addr| format!("break {addr:04X}"));
+        let watches = self.cpu().mem_bus.watchpoints.list().map(|(addr, kind)| {
+            let kind = match kind {
+                WatchKind::Read => "r",
+                WatchKind::Write => "w",
+            };
+            format!("watch {kind} {addr:04X}")
+        });
+        breaks.chain(watches).collect::<Vec<_>>().join("\n")
+    }
+
+    fn registers(&self) -> String {
+        let cpu = self.cpu();
+        let flags: String = "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if cpu.flags.bits() & (0x80 >> i) != 0 {
+                    c
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect();
+        format!(
+            "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} SP:{:02X} P:{:02X} {flags} CYC:{}",
+            cpu.program_counter,
+            cpu.accumulator,
+            cpu.reg_x,
+            cpu.reg_y,
+            cpu.stack_pointer,
+            cpu.flags.bits(),
+            cpu.cycle_count
+        )
+    }
+
+    fn set_register(&mut self, reg: &str, val: u16) -> Result<(), String> {
+        let cpu = self.system.cpu_mut();
+        let byte = u8::try_from(val).map_err(|_| format!("{val:X} doesn't fit in {reg}"));
+        match reg.to_ascii_lowercase().as_str() {
+            "a" => cpu.accumulator = byte?,
+            "x" => cpu.reg_x = byte?,
+            "y" => cpu.reg_y = byte?,
+            "sp" => cpu.stack_pointer = byte?,
+            "p" => cpu.flags = StatusFlags::from_bits_retain(byte?),
+            "pc" => cpu.program_counter = val,
+            _ => return Err(format!("unknown register {reg}")),
+        }
+        Ok(())
+    }
+
+    fn dump(&self, start: u16, len: u16) -> String {
+        let bus = &self.cpu().mem_bus;
+        let mut lines = vec![];
+        for row in (0..len).step_by(16) {
+            let addr = start.wrapping_add(row);
+            let bytes: Vec<u8> = (0..16.min(len - row))
+                .map(|i| bus.dbg_read(addr.wrapping_add(i)))
+                .collect();
+            let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02X}")).collect();
+            let ascii: String = bytes
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+                .collect();
+            lines.push(format!("{addr:04X}  {:47}  {ascii}", hex.join(" ")));
+        }
+        lines.join("\n")
+    }
+
+    // the instruction boundary `before` instructions back is a guess: the furthest start that
+    // decodes straight into pc wins
+    fn disassemble_around(&self, pc: u16) -> String {
+        let cpu = self.cpu();
+        let size_at = |addr: u16| cpu.variant.decode(cpu.mem_bus.dbg_read(addr)).bytes as u16;
+
+        let mut start = pc;
+        for back in (1..=DIS_BEFORE as u16 * 3).rev() {
+            let mut starts = vec![];
+            let mut dist = 0;
+            while dist < back {
+                starts.push(pc.wrapping_sub(back - dist));
+                dist += size_at(pc.wrapping_sub(back - dist));
+            }
+            if dist == back {
+                start = starts[starts.len().saturating_sub(DIS_BEFORE)];
+                break;
+            }
+        }
+
+        let before = pc.wrapping_sub(start) as usize;
+        self.disassemble(start, before.min(DIS_BEFORE) + DIS_AFTER)
+    }
+
+    fn disassemble(&self, start: u16, count: usize) -> String {
+        let cpu = self.cpu();
+        let end = start.saturating_add((count as u16).saturating_mul(3));
+        let listing = Disassembler::from_bus(&cpu.mem_bus, start, end)
+            .variant(cpu.variant)
+            .sweep();
+
+        let mut lines = vec![];
+        for item in listing.items().iter().take(count) {
+            let addr = item.addr();
+            let marker = if addr == cpu.program_counter {
+                "=>"
+            } else {
+                ""
+            };
+            let bp = if self.breakpoints.contains(&addr) {
+                "*"
+            } else {
+                " "
+            };
+            let bytes: Vec<String> = (0..item.size() as u16)
+                .map(|i| format!("{:02X}", cpu.mem_bus.dbg_read(addr.wrapping_add(i))))
+                .collect();
+            let text = match item {
+                Item::Code(_, ins) => listing.instruction_text(addr, *ins),
+                Item::Bytes(..) | Item::Words(..) => ".byte".to_string(),
+            };
+            lines.push(format!(
+                "{marker:2}{bp}{addr:04X}  {:9} {text}",
+                bytes.join(" ")
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn parse_hex(text: &str) -> Result<u16, String> {
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).map_err(|_| format!("bad hex value {text}"))
+}
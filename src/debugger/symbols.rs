@@ -0,0 +1,74 @@
+// address -> name for backtraces and commands. reads ld65 label files (ld65 -Ln, "al 00C000
+// .reset") and FCEUX .nl files ("$C000#reset#comment"), or takes the labels of an assembled
+// Program
+use std::collections::BTreeMap;
+
+use crate::asm::Program;
+
+#[derive(Debug, Default)]
+pub struct Symbols {
+    names: BTreeMap<u16, String>,
+}
+
+impl Symbols {
+    // lines in neither format are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut symbols = Symbols::default();
+        for line in text.lines() {
+            let parsed = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["al", addr, name] => u32::from_str_radix(addr, 16)
+                    .ok()
+                    .map(|addr| (addr as u16, name.trim_start_matches('.'))),
+                _ => line.strip_prefix('$').and_then(|nl| {
+                    let mut fields = nl.split('#');
+                    let addr = u16::from_str_radix(fields.next()?, 16).ok()?;
+                    Some((addr, fields.next().filter(|name| !name.is_empty())?))
+                }),
+            };
+            if let Some((addr, name)) = parsed {
+                symbols.insert(addr, name);
+            }
+        }
+        symbols
+    }
+
+    pub fn from_program(program: &Program) -> Self {
+        let mut symbols = Symbols::default();
+        for (name, &addr) in &program.labels {
+            symbols.insert(addr, name);
+        }
+        symbols
+    }
+
+    // the first name given to an address sticks
+    pub fn insert(&mut self, addr: u16, name: &str) {
+        self.names.entry(addr).or_insert_with(|| name.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn get(&self, addr: u16) -> Option<&str> {
+        self.names.get(&addr).map(String::as_str)
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<u16> {
+        self.names
+            .iter()
+            .find_map(|(&addr, n)| (n == name).then_some(addr))
+    }
+
+    // nearest symbol at or below addr: "name" or "name+offset"
+    pub fn describe(&self, addr: u16) -> Option<String> {
+        let (&base, name) = self.names.range(..=addr).next_back()?;
+        Some(match addr - base {
+            0 => name.clone(),
+            offset => format!("{name}+{offset}"),
+        })
+    }
+}
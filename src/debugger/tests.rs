@@ -0,0 +1,227 @@
+use super::{symbols::Symbols, Debugger};
+use crate::{
+    asm::{Assembler, Program},
+    memory::cartridge::{nrom_image, CHR_ROM_PAGE_SIZE},
+    NESSystem,
+};
+
+// one 16 KiB PRG bank at $C000, nmi and irq disabled
+const PROGRAM: &str = "
+        .org $C000
+reset:  LDX #$00
+loop:   JSR sub
+        INX
+        STX $0200
+        JMP loop
+sub:    LDA #$01
+        JSR sub2
+        RTS
+sub2:   NOP
+        RTS
+nmi:    RTI
+
+        .org $FFFA
+        .word nmi, reset, nmi
+";
+
+fn test_program() -> Program {
+    Assembler::new().assemble(PROGRAM).unwrap()
+}
+
+fn test_debugger() -> Debugger {
+    debugger_for(test_program())
+}
+
+fn debugger_for(program: Program) -> Debugger {
+    let rom = nrom_image(&program, &[0; CHR_ROM_PAGE_SIZE]);
+    Debugger::new(NESSystem::new(rom).unwrap())
+}
+
+fn pc(debugger: &Debugger) -> u16 {
+    debugger.system.cpu().program_counter
+}
+
+#[test]
+fn test_step_commands() {
+    let mut debugger = test_debugger();
+    assert_eq!(pc(&debugger), 0xc000);
+
+    let out = debugger.command("step").unwrap();
+    assert_eq!(out, "=> C002  20 0C C0  JSR $C00C\n");
+
+    // over the JSR, SP is back where it was
+    let sp = debugger.system.cpu().stack_pointer;
+    debugger.command("next").unwrap();
+    assert_eq!(pc(&debugger), 0xc005);
+    assert_eq!(debugger.system.cpu().stack_pointer, sp);
+
+    // an empty line repeats the last command, next on a non-JSR is a plain step
+    debugger.command("").unwrap();
+    assert_eq!(pc(&debugger), 0xc006);
+
+    debugger.command("until C002").unwrap();
+    debugger.command("s 3").unwrap();
+    assert_eq!(pc(&debugger), 0xc012);
+    debugger.command("out").unwrap();
+    assert_eq!(pc(&debugger), 0xc011);
+    debugger.command("finish").unwrap();
+    assert_eq!(pc(&debugger), 0xc005);
+}
+
+#[test]
+fn test_breakpoints_and_watchpoints() {
+    let mut debugger = test_debugger();
+
+    debugger.command("b C005").unwrap();
+    let out = debugger.command("c").unwrap();
+    assert_eq!(
+        out,
+        "breakpoint at C005\n#0  C005\n=>*C005  E8        INX\n"
+    );
+
+    debugger.command("d C005").unwrap();
+    debugger.command("watch w 0200").unwrap();
+    let out = debugger.command("c").unwrap();
+    assert_eq!(
+        out,
+        "watch: write 0200 = 01 by C006\n#0  C009\n=> C009  4C 02 C0  JMP $C002\n"
+    );
+
+    // mirrors of ram are caught too
+    debugger.command("d 0200").unwrap();
+    debugger.command("watch 0A00").unwrap();
+    debugger.command("watch r 0300").unwrap();
+    assert_eq!(
+        debugger.command("bl").unwrap(),
+        "watch r 0300\nwatch w 0200"
+    );
+    let out = debugger.command("c").unwrap();
+    assert!(out.starts_with("watch: write 0200 = 02 by C006\n"));
+}
+
+#[test]
+fn test_registers_and_memory() {
+    let mut debugger = test_debugger();
+
+    let out = debugger.command("set a 42").unwrap();
+    assert!(out.starts_with("PC:C000 A:42 X:00 Y:00 SP:FD P:24 nv-bdIzc"));
+    assert!(debugger.command("set q 1").is_err());
+    assert!(debugger.command("set x 100").is_err());
+
+    let out = debugger.command("mem C000 8").unwrap();
+    assert_eq!(
+        out,
+        "C000  A2 00 20 0C C0 E8 8E 00                          ........"
+    );
+
+    debugger.command("until C00E").unwrap();
+    let out = debugger.command("dis").unwrap();
+    assert_eq!(
+        out.lines().take(6).collect::<Vec<_>>(),
+        [
+            "   C005  E8        INX",
+            "   C006  8E 00 02  STX $0200",
+            "   C009  4C 02 C0  JMP $C002",
+            "   C00C  A9 01     LDA #$01",
+            "=> C00E  20 12 C0  JSR $C012",
+            "   C011  60        RTS",
+        ]
+    );
+}
+
+#[test]
+fn test_backtrace() {
+    let mut debugger = test_debugger();
+
+    debugger.command("b C012").unwrap();
+    debugger.command("c").unwrap();
+    assert_eq!(
+        debugger.command("bt").unwrap(),
+        "#0  C012\n#1  C00E\n#2  C002"
+    );
+
+    debugger.symbols = Symbols::from_program(&test_program());
+    debugger.command("s").unwrap();
+    assert_eq!(
+        debugger.command("bt").unwrap(),
+        "#0  C013  sub2+1\n#1  C00E  sub+2\n#2  C002  loop"
+    );
+
+    // symbols work as addresses
+    debugger.command("until loop").unwrap();
+    assert_eq!(pc(&debugger), 0xc002);
+    assert_eq!(debugger.command("bt").unwrap(), "#0  C002  loop");
+    assert!(debugger.command("b nowhere").is_err());
+}
+
+#[test]
+fn test_symbols_parse() {
+    let symbols = Symbols::parse(
+        "al 00C000 .reset\nal 00C00C .sub\n$C012#sub2#leaf routine\n$0200##\ngarbage\n",
+    );
+
+    assert_eq!(symbols.len(), 3);
+    assert_eq!(symbols.get(0xc000), Some("reset"));
+    assert_eq!(symbols.lookup("sub2"), Some(0xc012));
+    assert_eq!(symbols.describe(0xc00e), Some("sub+2".to_string()));
+    assert_eq!(symbols.describe(0x8000), None);
+}
+
+#[test]
+fn test_code_data_log() {
+    let mut debugger = test_debugger();
+    debugger.command("s 2").unwrap();
+
+    // LDX #$00 and JSR sub, the reset vector was read before the debugger turned logging on
+    assert_eq!(
+        debugger.command("cdl").unwrap(),
+        "PRG: 5 code, 0 data, 16379 of 16384 unlogged\nCHR: 0 rendered, 0 read, 8192 of 8192 unlogged"
+    );
+}
+
+#[test]
+fn test_profiler_commands() {
+    let mut debugger = test_debugger();
+    assert!(debugger.command("prof").is_err());
+
+    debugger.command("prof on").unwrap();
+    debugger.symbols = Symbols::from_program(&test_program());
+    debugger.command("until C009").unwrap();
+
+    let report = debugger.command("prof").unwrap();
+    assert!(report.starts_with("36 cycles in 9 instructions\n"));
+    assert!(
+        report.contains("\nsub                           1         22  61.1%         14  38.9%\n")
+    );
+    assert!(
+        report.contains("\nsub2                          1          8  22.2%          8  22.2%\n")
+    );
+
+    debugger.command("prof off").unwrap();
+    assert!(debugger.command("prof").is_err());
+}
+
+#[test]
+fn test_jam_and_reset() {
+    let program = Assembler::new()
+        .assemble(
+            "
+        .org $C000
+reset:  NOP
+        JAM
+        .org $FFFA
+        .word reset, reset, reset
+",
+        )
+        .unwrap();
+    let mut debugger = debugger_for(program);
+
+    let out = debugger.command("c").unwrap();
+    assert!(out.starts_with("cpu jammed at C001\n"), "{out}");
+
+    debugger.command("reset").unwrap();
+    assert!(!debugger.system.cpu().jammed());
+    assert_eq!(pc(&debugger), 0xc000);
+    debugger.command("s").unwrap();
+    assert_eq!(pc(&debugger), 0xc001);
+}
@@ -0,0 +1,472 @@
+// 6502 disassembler for PRG banks and memory ranges. code is told apart from data by following
+// the entry points (normally the reset/nmi/irq vectors) through JMP, JSR and branch targets,
+// anything that is never reached stays data. the listing prints as ca65 source that assembles
+// back to the same bytes
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    cpu::{
+        instructions::{AddressingMode::*, Instruction, InstructionName as IN},
+        Variant, IRQ_VECTOR, NMI_VECTOR, RESET_VECTOR,
+    },
+    make_u16,
+    memory::{cartridge::Cartridge, memory_bus::Bus},
+};
+
+#[cfg(test)]
+mod tests;
+
+const BYTES_PER_LINE: usize = 8;
+const COMMENT_COLUMN: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ByteKind {
+    Data,
+    Opcode,
+    Operand,
+    Vector,
+}
+
+// how an address is referenced, the strongest reference picks the label name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Ref {
+    Data,
+    Jump,
+    Call,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Code(u16, Instruction),
+    Bytes(u16, Vec<u8>),
+    // the nmi/reset/irq vector table
+    Words(u16, Vec<u16>),
+}
+
+impl Item {
+    pub fn addr(&self) -> u16 {
+        match self {
+            Item::Code(addr, _) | Item::Bytes(addr, _) | Item::Words(addr, _) => *addr,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Item::Code(_, ins) => ins.bytes as usize,
+            Item::Bytes(_, bytes) => bytes.len(),
+            Item::Words(_, words) => words.len() * 2,
+        }
+    }
+}
+
+pub struct Disassembler {
+    data: Vec<u8>,
+    origin: u16,
+    variant: Variant,
+    entries: Vec<(u16, Option<&'static str>)>,
+    vector_table: bool,
+}
+
+impl Disassembler {
+    // `data` is mapped starting at `origin`, anything past $FFFF is dropped
+    pub fn new(mut data: Vec<u8>, origin: u16) -> Self {
+        data.truncate(0x10000 - origin as usize);
+        Disassembler {
+            data,
+            origin,
+            variant: Variant::default(),
+            entries: vec![],
+            vector_table: false,
+        }
+    }
+
+    // start..=end as the cpu sees it, read through dbg_read so nothing is disturbed
+    pub fn from_bus(bus: &impl Bus, start: u16, end: u16) -> Self {
+        let data = (start..=end).map(|addr| bus.dbg_read(addr)).collect();
+        Disassembler::new(data, start)
+    }
+
+    // the bank holding the vectors: a 16 KiB bank at $C000, otherwise the last 32 KiB at $8000
+    pub fn from_cartridge(cart: &Cartridge) -> Self {
+        let prg = &cart.prg_rom;
+        let disasm = if prg.len() <= 0x4000 {
+            Disassembler::new(prg.clone(), 0xc000)
+        } else {
+            Disassembler::new(prg[prg.len() - 0x8000..].to_vec(), 0x8000)
+        };
+        disasm.vectors()
+    }
+
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn entry(mut self, addr: u16) -> Self {
+        self.entries.push((addr, None));
+        self
+    }
+
+    // trace from the nmi, reset and irq vectors and keep the table itself as .word data. does
+    // nothing unless the whole table is inside the range
+    pub fn vectors(mut self) -> Self {
+        if self.index(NMI_VECTOR).is_none() || self.index(0xffff).is_none() {
+            return self;
+        }
+        self.vector_table = true;
+        for (vector, name) in [
+            (NMI_VECTOR, "nmi"),
+            (RESET_VECTOR, "reset"),
+            (IRQ_VECTOR, "irq"),
+        ] {
+            let target = self.word_at(vector);
+            self.entries.push((target, Some(name)));
+        }
+        self
+    }
+
+    fn index(&self, addr: u16) -> Option<usize> {
+        let i = addr.wrapping_sub(self.origin) as usize;
+        (i < self.data.len()).then_some(i)
+    }
+
+    fn word_at(&self, addr: u16) -> u16 {
+        let i = self.index(addr).unwrap();
+        make_u16!(self.data[i + 1], self.data[i])
+    }
+
+    // decode everything in order without following any flow, no labels. for windows of memory
+    // where code is already known to start at the origin
+    pub fn sweep(&self) -> Listing {
+        let mut items = vec![];
+        let mut i = 0;
+        while i < self.data.len() {
+            let addr = self.origin.wrapping_add(i as u16);
+            let ins = self.variant.decode(self.data[i]);
+            let item = if i + ins.bytes as usize <= self.data.len() {
+                Item::Code(addr, ins)
+            } else {
+                Item::Bytes(addr, self.data[i..].to_vec())
+            };
+            i += item.size();
+            items.push(item);
+        }
+
+        Listing {
+            origin: self.origin,
+            variant: self.variant,
+            data: self.data.clone(),
+            items,
+            labels: BTreeMap::new(),
+            equates: BTreeMap::new(),
+        }
+    }
+
+    pub fn disassemble(&self) -> Listing {
+        let len = self.data.len();
+        let mut kinds = vec![ByteKind::Data; len];
+        let mut code: BTreeMap<u16, Instruction> = BTreeMap::new();
+        let mut refs: BTreeMap<u16, Ref> = BTreeMap::new();
+        let mut labels: BTreeMap<u16, String> = BTreeMap::new();
+
+        if self.vector_table {
+            let i = self.index(NMI_VECTOR).unwrap();
+            kinds[i..i + 6].fill(ByteKind::Vector);
+        }
+
+        let mut pending = vec![];
+        for &(addr, name) in &self.entries {
+            match name {
+                Some(name) => {
+                    labels.entry(addr).or_insert_with(|| name.to_string());
+                }
+                None => add_ref(&mut refs, addr, Ref::Jump),
+            }
+            pending.push(addr);
+        }
+
+        while let Some(start) = pending.pop() {
+            let mut pc = start;
+            while let Some(i) = self.index(pc) {
+                let ins = self.variant.decode(self.data[i]);
+                let size = ins.bytes as usize;
+                // already traced, runs off the end, overlaps other code, or locks the cpu up
+                if i + size > len
+                    || kinds[i..i + size].iter().any(|&k| k != ByteKind::Data)
+                    || ins.name == IN::JAM
+                {
+                    break;
+                }
+                kinds[i] = ByteKind::Opcode;
+                kinds[i + 1..i + size].fill(ByteKind::Operand);
+                code.insert(pc, ins);
+
+                match ins.mode {
+                    Relative => {
+                        let target = branch_target(pc, 2, self.data[i + 1]);
+                        add_ref(&mut refs, target, Ref::Jump);
+                        pending.push(target);
+                    }
+                    ZeroPageRelative => {
+                        let target = branch_target(pc, 3, self.data[i + 2]);
+                        add_ref(&mut refs, target, Ref::Jump);
+                        pending.push(target);
+                    }
+                    Absolute | AbsoluteX | AbsoluteY | Indirect | AbsoluteIndexedIndirect => {
+                        let target = make_u16!(self.data[i + 2], self.data[i + 1]);
+                        match (ins.name, ins.mode) {
+                            (IN::JSR, _) => {
+                                add_ref(&mut refs, target, Ref::Call);
+                                pending.push(target);
+                            }
+                            (IN::JMP, Absolute) => {
+                                add_ref(&mut refs, target, Ref::Jump);
+                                pending.push(target);
+                            }
+                            _ => add_ref(&mut refs, target, Ref::Data),
+                        }
+                    }
+                    _ => {}
+                }
+
+                if matches!(
+                    ins.name,
+                    IN::JMP | IN::RTS | IN::RTI | IN::BRK | IN::BRA | IN::STP
+                ) {
+                    break;
+                }
+                pc = pc.wrapping_add(size as u16);
+            }
+        }
+
+        for (&addr, &r) in &refs {
+            if self.index(addr).is_none() || labels.contains_key(&addr) {
+                continue;
+            }
+            let prefix = match r {
+                Ref::Call => "sub",
+                Ref::Jump => "loc",
+                Ref::Data if code.contains_key(&addr) => "loc",
+                Ref::Data => "data",
+            };
+            labels.insert(addr, format!("{prefix}_{addr:04X}"));
+        }
+
+        // split the data up so every label lands on the start of an item
+        let mut items = vec![];
+        let mut i = 0;
+        while i < len {
+            let addr = self.origin.wrapping_add(i as u16);
+            let item = match kinds[i] {
+                ByteKind::Opcode => Item::Code(addr, code[&addr]),
+                ByteKind::Vector => {
+                    Item::Words(addr, (0..3).map(|n| self.word_at(addr + n * 2)).collect())
+                }
+                _ => {
+                    let run = (i..len)
+                        .take(BYTES_PER_LINE)
+                        .take_while(|&j| {
+                            let at = self.origin.wrapping_add(j as u16);
+                            kinds[j] == ByteKind::Data && (j == i || !labels.contains_key(&at))
+                        })
+                        .count();
+                    Item::Bytes(addr, self.data[i..i + run].to_vec())
+                }
+            };
+            i += item.size();
+            items.push(item);
+        }
+
+        // labels that can't be placed become equates: outside the range they are plain
+        // addresses, inside an instruction they are offsets from the instruction's own label
+        let item_start =
+            |addr: u16| items[items.partition_point(|item| item.addr() <= addr) - 1].addr();
+        let inner: Vec<u16> = labels
+            .keys()
+            .copied()
+            .filter(|&addr| self.index(addr).is_some() && item_start(addr) != addr)
+            .collect();
+        for &addr in &inner {
+            let base = item_start(addr);
+            labels
+                .entry(base)
+                .or_insert_with(|| format!("loc_{base:04X}"));
+        }
+        let mut equates = BTreeMap::new();
+        for (&addr, name) in &labels {
+            if self.index(addr).is_none() {
+                equates.insert(addr, (name.clone(), format!("${addr:04X}")));
+            } else if inner.contains(&addr) {
+                let base = item_start(addr);
+                let value = format!("{}+{}", labels[&base], addr - base);
+                equates.insert(addr, (name.clone(), value));
+            }
+        }
+
+        Listing {
+            origin: self.origin,
+            variant: self.variant,
+            data: self.data.clone(),
+            items,
+            labels,
+            equates,
+        }
+    }
+}
+
+fn add_ref(refs: &mut BTreeMap<u16, Ref>, addr: u16, r: Ref) {
+    let entry = refs.entry(addr).or_insert(r);
+    *entry = (*entry).max(r);
+}
+
+fn branch_target(pc: u16, size: u16, offset: u8) -> u16 {
+    pc.wrapping_add(size).wrapping_add(offset as i8 as u16)
+}
+
+pub struct Listing {
+    pub origin: u16,
+    pub variant: Variant,
+    data: Vec<u8>,
+    items: Vec<Item>,
+    labels: BTreeMap<u16, String>,
+    // addr -> (name, value) for labels that aren't at the start of an item
+    equates: BTreeMap<u16, (String, String)>,
+}
+
+impl Listing {
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn label(&self, addr: u16) -> Option<&str> {
+        self.labels.get(&addr).map(String::as_str)
+    }
+
+    pub fn is_code(&self, addr: u16) -> bool {
+        let pos = self.items.partition_point(|item| item.addr() <= addr);
+        pos > 0
+            && matches!(self.items[pos - 1], Item::Code(start, ins) if addr - start < ins.bytes as u16)
+    }
+
+    fn bytes(&self, addr: u16, count: usize) -> &[u8] {
+        let i = addr.wrapping_sub(self.origin) as usize;
+        &self.data[i..i + count]
+    }
+
+    fn expr(&self, addr: u16) -> String {
+        match self.labels.get(&addr) {
+            Some(name) => name.clone(),
+            None => format!("${addr:04X}"),
+        }
+    }
+
+    // an absolute operand below $100 would be shrunk to zero page without the a: prefix
+    fn abs_expr(&self, addr: u16) -> String {
+        let prefix = if addr < 0x100 { "a:" } else { "" };
+        format!("{prefix}{}", self.expr(addr))
+    }
+
+    // mnemonic and operand in ca65 syntax
+    pub fn instruction_text(&self, addr: u16, ins: Instruction) -> String {
+        let bytes = self.bytes(addr, ins.bytes as usize);
+        let lo = bytes.get(1).copied().unwrap_or(0);
+        let word = make_u16!(bytes.get(2).copied().unwrap_or(0), lo);
+
+        let operand = match ins.mode {
+            Implicit => String::new(),
+            Accumulator => "A".to_string(),
+            Immediate => format!("#${lo:02X}"),
+            ZeroPage => format!("${lo:02X}"),
+            ZeroPageX => format!("${lo:02X},X"),
+            ZeroPageY => format!("${lo:02X},Y"),
+            Relative => self.expr(branch_target(addr, 2, lo)),
+            Absolute => self.abs_expr(word),
+            AbsoluteX => format!("{},X", self.abs_expr(word)),
+            AbsoluteY => format!("{},Y", self.abs_expr(word)),
+            Indirect => format!("({})", self.expr(word)),
+            IndexedIndirect => format!("(${lo:02X},X)"),
+            IndirectIndexed => format!("(${lo:02X}),Y"),
+            ZeroPageIndirect => format!("(${lo:02X})"),
+            AbsoluteIndexedIndirect => format!("({},X)", self.expr(word)),
+            ZeroPageRelative => {
+                format!("${lo:02X}, {}", self.expr(branch_target(addr, 3, bytes[2])))
+            }
+        };
+
+        // RMB/SMB/BBR/BBS carry the bit number in the mnemonic
+        let mnemonic = match ins.name {
+            IN::RMB | IN::SMB | IN::BBR | IN::BBS => {
+                format!("{}{}", ins.name, (ins.opcode >> 4) & 7)
+            }
+            _ => ins.name.to_string(),
+        };
+
+        if operand.is_empty() {
+            mnemonic
+        } else {
+            format!("{mnemonic} {operand}")
+        }
+    }
+}
+
+// opcodes ca65 has no spelling for (or spells with different bytes) go out as .byte
+fn needs_raw_bytes(ins: Instruction) -> bool {
+    ins.unofficial
+        || matches!(ins.name, IN::BRK | IN::WAI | IN::STP)
+        || (ins.name == IN::NOP && ins.opcode != 0xea)
+}
+
+fn byte_list(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("${b:02X}")).collect();
+    format!(".byte {}", hex.join(", "))
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02X}")).collect();
+    hex.join(" ")
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cpu = match self.variant {
+            Variant::Wdc65C02 => "65C02",
+            Variant::Ricoh2A03 | Variant::Nmos6502 => "6502",
+        };
+        writeln!(f, ".setcpu \"{cpu}\"")?;
+        writeln!(f, ".org ${:04X}", self.origin)?;
+
+        if !self.equates.is_empty() {
+            writeln!(f)?;
+            for (name, value) in self.equates.values() {
+                writeln!(f, "{name} = {value}")?;
+            }
+        }
+        writeln!(f)?;
+
+        for item in &self.items {
+            let addr = item.addr();
+            if let Some(name) = self.labels.get(&addr) {
+                writeln!(f, "{name}:")?;
+            }
+
+            let bytes = self.bytes(addr, item.size());
+            let (text, comment) = match item {
+                Item::Code(_, ins) if needs_raw_bytes(*ins) => (
+                    byte_list(bytes),
+                    format!("{addr:04X}  {}", self.instruction_text(addr, *ins)),
+                ),
+                Item::Code(_, ins) => (
+                    self.instruction_text(addr, *ins),
+                    format!("{addr:04X}  {}", hex_dump(bytes)),
+                ),
+                Item::Bytes(..) => (byte_list(bytes), format!("{addr:04X}")),
+                Item::Words(_, words) => {
+                    let names: Vec<String> = words.iter().map(|&w| self.expr(w)).collect();
+                    (format!(".word {}", names.join(", ")), format!("{addr:04X}"))
+                }
+            };
+            writeln!(f, "        {text:COMMENT_COLUMN$}; {comment}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use super::{Disassembler, Item};
+use crate::{
+    cpu::{instructions::LDX_IM, Variant},
+    memory::flat_ram_bus::FlatRamBus,
+};
+
+#[test]
+fn test_disassemble_listing() {
+    let program = vec![
+        0xa2, 0x00, // LDX #$00
+        0xbd, 0x10, 0x80, // LDA table,X
+        0x20, 0x0c, 0x80, // JSR sub
+        0xd0, 0xf6, // BNE start
+        0xf0, 0xfe, // BEQ *
+        0x8d, 0x10, 0x00, // STA a:$0010
+        0x60, // RTS
+        0x01, 0x02, 0x03, // table
+    ];
+    let listing = Disassembler::new(program, 0x8000)
+        .entry(0x8000)
+        .disassemble();
+
+    assert_eq!(
+        listing.to_string(),
+        r#".setcpu "6502"
+.org $8000
+
+loc_8000:
+        LDX #$00                ; 8000  A2 00
+        LDA data_8010,X         ; 8002  BD 10 80
+        JSR sub_800C            ; 8005  20 0C 80
+        BNE loc_8000            ; 8008  D0 F6
+loc_800A:
+        BEQ loc_800A            ; 800A  F0 FE
+sub_800C:
+        STA a:$0010             ; 800C  8D 10 00
+        RTS                     ; 800F  60
+data_8010:
+        .byte $01, $02, $03     ; 8010
+"#
+    );
+    assert_eq!(listing.items()[0], Item::Code(0x8000, LDX_IM));
+    assert!(listing.is_code(0x800e));
+    assert!(!listing.is_code(0x8010));
+    assert_eq!(listing.label(0x800c), Some("sub_800C"));
+}
+
+#[test]
+fn test_disassemble_vectors() {
+    let mut bus = FlatRamBus::new();
+    bus.load(
+        0xfff0,
+        &[
+            0xa7, 0x10, // LAX $10
+            0x2c, 0xa9, 0x01, // BIT $01A9, hiding LDA #$01
+            0x6c, 0x00, 0x02, // JMP ($0200)
+            0x40, // RTI
+            0xff, // unused
+            0xf8, 0xff, 0xf0, 0xff, 0xf8, 0xff, // nmi, reset, irq
+        ],
+    );
+
+    let listing = Disassembler::from_bus(&bus, 0xfff0, 0xffff)
+        .entry(0xfff3)
+        .vectors()
+        .disassemble();
+
+    assert_eq!(
+        listing.to_string(),
+        r#".setcpu "6502"
+.org $FFF0
+
+loc_FFF3 = loc_FFF2+1
+
+reset:
+        .byte $A7, $10          ; FFF0  LAX $10
+loc_FFF2:
+        BIT $01A9               ; FFF2  2C A9 01
+        JMP ($0200)             ; FFF5  6C 00 02
+nmi:
+        RTI                     ; FFF8  40
+        .byte $FF               ; FFF9
+        .word nmi, reset, nmi   ; FFFA
+"#
+    );
+}
+
+#[test]
+fn test_disassemble_65c02() {
+    let program = vec![
+        0x0f, 0x12, 0x02, // BBR0 $12, +2
+        0x77, 0x34, // RMB7 $34
+        0x80, 0xfc, // BRA -4
+    ];
+    let listing = Disassembler::new(program, 0x0400)
+        .variant(Variant::Wdc65C02)
+        .entry(0x0400)
+        .disassemble();
+
+    assert_eq!(
+        listing.to_string(),
+        r#".setcpu "65C02"
+.org $0400
+
+loc_0400:
+        BBR0 $12, loc_0405      ; 0400  0F 12 02
+loc_0403:
+        RMB7 $34                ; 0403  77 34
+loc_0405:
+        BRA loc_0403            ; 0405  80 FC
+"#
+    );
+}
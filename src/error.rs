@@ -0,0 +1,24 @@
+// what can go wrong loading or running a game. none of it takes the process down: a jammed cpu
+// just sits there until NESSystem::reset
+use std::{error, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmulatorError {
+    // the rom file couldn't be loaded
+    BadRom(&'static str),
+    // a JAM (KIL) opcode, or the 65C02's STP, halted the cpu
+    Jammed { pc: u16, opcode: u8 },
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmulatorError::BadRom(reason) => write!(f, "can't load rom: {reason}"),
+            EmulatorError::Jammed { pc, opcode } => {
+                write!(f, "cpu jammed by opcode {opcode:02X} at {pc:04X}")
+            }
+        }
+    }
+}
+
+impl error::Error for EmulatorError {}
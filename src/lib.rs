@@ -1,13 +1,26 @@
+pub mod asm;
 pub mod cpu;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod loggers;
 pub mod memory;
 pub mod ppu;
 pub mod ui;
 
-use chrono::prelude::Utc;
+#[cfg(test)]
+mod tests;
+
+use bitflags::bitflags;
 use cpu::{instructions::Instruction, CPU};
-use memory::{cartridge::Cartridge, memory_bus::MemoryBus};
+use error::EmulatorError;
+use memory::{
+    cartridge::Cartridge,
+    cdl::CodeDataLog,
+    memory_bus::{Bus, MemoryBus},
+};
 use ppu::PPU;
-use std::{fs::File, io::Write};
+use std::fmt;
 
 #[macro_export]
 macro_rules! make_u16 {
@@ -31,65 +44,258 @@ pub enum InputType {
 }
 
 pub struct NESSystem {
-    cpu: CPU,
+    cpu: CPU<MemoryBus>,
 }
 
 impl NESSystem {
-    pub fn new(raw_bytes: Vec<u8>) -> Result<Self, &'static str> {
-        let mem_bus = MemoryBus::new(PPU::new(Cartridge::new(raw_bytes)?));
-        let mut cpu = CPU::new_program(false, mem_bus, None);
-        cpu.reset();
-        Ok(NESSystem { cpu })
+    pub fn new(raw_bytes: Vec<u8>) -> Result<Self, EmulatorError> {
+        NESSystem::builder(raw_bytes).build()
+    }
+
+    pub fn builder(raw_bytes: Vec<u8>) -> NESSystemBuilder {
+        NESSystemBuilder {
+            raw_bytes,
+            logger: None,
+            trace: false,
+            cycle_stepped: false,
+            code_data_log: false,
+            profile: false,
+        }
+    }
+
+    pub fn cpu(&self) -> &CPU<MemoryBus> {
+        &self.cpu
+    }
+
+    // registers, memory and watchpoints, for debuggers and tools
+    pub fn cpu_mut(&mut self) -> &mut CPU<MemoryBus> {
+        &mut self.cpu
     }
 
     // pub fn get_frame_pixel_buffer(&self) -> [u8; WIDTH * HEIGHT] {
     //     self.cpu.mem_bus.get_frame_pixel_buffer()
     // }
 
-    pub fn render(&self, target: &mut [u8; WIDTH * HEIGHT]) {
+    // a 512 colour index per pixel: the 6 bit palette colour, then the emphasis bits from
+    // PPUMASK above it
+    pub fn render(&self, target: &mut [u16; WIDTH * HEIGHT]) {
         self.cpu.mem_bus.render(target);
     }
 
-    pub fn tick_once(&mut self) {
+    // None unless the builder turned it on, see memory::cdl
+    pub fn code_data_log(&self) -> Option<&CodeDataLog> {
+        self.cpu.mem_bus.cdl()
+    }
+
+    // see CPU::cycle_stepped
+    pub fn set_cycle_stepped(&mut self, cycle_stepped: bool) {
+        self.cpu.cycle_stepped = cycle_stepped;
+    }
+
+    // the reset button, also the way out of a jammed cpu
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    // the tick_ functions stop early and say so once the cpu has jammed
+    pub fn tick_once(&mut self) -> Result<(), EmulatorError> {
         self.cpu.run_once();
+        self.check_jammed()
     }
 
-    pub fn tick_n(&mut self, count: usize) {
-        self.cpu.run_count(count);
+    pub fn tick_n(&mut self, count: usize) -> Result<(), EmulatorError> {
+        for _ in 0..count {
+            self.tick_once()?;
+        }
+        Ok(())
     }
 
-    pub fn tick_one_frame(&mut self) {
-        let starting_cycles = self.cpu.cycle_count;
-        while self.cpu.cycle_count - starting_cycles < 29781 {
-            self.cpu.run_once();
+    // runs until the ppu finishes the frame it's on, stopping after the instruction it
+    // finished during
+    pub fn tick_one_frame(&mut self) -> Result<(), EmulatorError> {
+        let frame = self.frame_count();
+        self.run_until(|system| system.frame_count() != frame)
+    }
+
+    // runs at least one instruction, and then more until `done` says so
+    pub fn run_until(
+        &mut self,
+        mut done: impl FnMut(&NESSystem) -> bool,
+    ) -> Result<(), EmulatorError> {
+        loop {
+            self.tick_once()?;
+            if done(self) {
+                return Ok(());
+            }
         }
     }
 
-    pub fn save_log(&self) {
-        let mut log_file = File::create(format!(
-            "logs/ran_{}.log",
-            Utc::now().format("%Y-%m-%d_%H-%M-%S")
-        ))
-        .unwrap();
+    // frames the ppu has finished since power on
+    pub fn frame_count(&self) -> u64 {
+        self.cpu.mem_bus.frame_count()
+    }
+
+    // 0-239 are drawn, 241-260 are vblank and 261 is the pre-render line
+    pub fn scanline(&self) -> u16 {
+        self.cpu.mem_bus.get_ppu_scanline()
+    }
+
+    // 0-340 within the scanline
+    pub fn dot(&self) -> usize {
+        self.cpu.mem_bus.get_ppu_cycles()
+    }
 
-        let _ = log_file.write_all(self.cpu.log.as_bytes());
+    fn check_jammed(&self) -> Result<(), EmulatorError> {
+        if !self.cpu.jammed() {
+            return Ok(());
+        }
+        let pc = self.cpu.program_counter;
+        Err(EmulatorError::Jammed {
+            pc,
+            opcode: self.cpu.mem_bus.dbg_read(pc),
+        })
     }
 }
 
+pub struct NESSystemBuilder {
+    raw_bytes: Vec<u8>,
+    logger: Option<Box<dyn Logger>>,
+    trace: bool,
+    cycle_stepped: bool,
+    code_data_log: bool,
+    profile: bool,
+}
+
+impl NESSystemBuilder {
+    // receives every LogEvent the cpu emits, see the loggers module for ready-made ones
+    pub fn logger(mut self, logger: Box<dyn Logger>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    // print a nestest style trace line to stdout for every instruction
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn cycle_stepped(mut self, cycle_stepped: bool) -> Self {
+        self.cycle_stepped = cycle_stepped;
+        self
+    }
+
+    // mark which rom bytes run as code, get read as data or get drawn, see memory::cdl
+    pub fn code_data_log(mut self, code_data_log: bool) -> Self {
+        self.code_data_log = code_data_log;
+        self
+    }
+
+    // count cycles per address, routine and scanline band, see CPU::profiler
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn build(self) -> Result<NESSystem, EmulatorError> {
+        let cart = Cartridge::new(self.raw_bytes).map_err(EmulatorError::BadRom)?;
+        let mem_bus = MemoryBus::new(PPU::new(cart));
+        let mut cpu = CPU::new_program(self.trace, mem_bus, self.logger);
+        cpu.cycle_stepped = self.cycle_stepped;
+        if self.code_data_log {
+            cpu.mem_bus.enable_cdl();
+        }
+        if self.profile {
+            cpu.enable_profiler();
+        }
+        cpu.reset();
+        Ok(NESSystem { cpu })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum LogEvent {
     InstructionFetch(Instruction),
+    // a JAM opcode locked the cpu up
     BadOpcode(u8),
     OperandFetch(u16),
+    // pointer and dummy reads made while resolving an address
     MemoryReadIntermediate(u16, u8),
     MemoryRead(u16, u8),
     MemoryWrite(u8, u16),
     StackPush(u8),
     StackPull(u8),
     NMIInterupt,
+    // registers after every instruction
     StateUpdate(cpu::CPUStateLog),
 }
 
+bitflags! {
+    // one bit per LogEvent variant, for filtering
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct LogEventKinds: u16 {
+        const INSTRUCTION_FETCH = 1 << 0;
+        const BAD_OPCODE = 1 << 1;
+        const OPERAND_FETCH = 1 << 2;
+        const MEMORY_READ_INTERMEDIATE = 1 << 3;
+        const MEMORY_READ = 1 << 4;
+        const MEMORY_WRITE = 1 << 5;
+        const STACK_PUSH = 1 << 6;
+        const STACK_PULL = 1 << 7;
+        const NMI_INTERUPT = 1 << 8;
+        const STATE_UPDATE = 1 << 9;
+    }
+}
+
+impl LogEvent {
+    pub fn kind(&self) -> LogEventKinds {
+        match self {
+            LogEvent::InstructionFetch(_) => LogEventKinds::INSTRUCTION_FETCH,
+            LogEvent::BadOpcode(_) => LogEventKinds::BAD_OPCODE,
+            LogEvent::OperandFetch(_) => LogEventKinds::OPERAND_FETCH,
+            LogEvent::MemoryReadIntermediate(..) => LogEventKinds::MEMORY_READ_INTERMEDIATE,
+            LogEvent::MemoryRead(..) => LogEventKinds::MEMORY_READ,
+            LogEvent::MemoryWrite(..) => LogEventKinds::MEMORY_WRITE,
+            LogEvent::StackPush(_) => LogEventKinds::STACK_PUSH,
+            LogEvent::StackPull(_) => LogEventKinds::STACK_PULL,
+            LogEvent::NMIInterupt => LogEventKinds::NMI_INTERUPT,
+            LogEvent::StateUpdate(_) => LogEventKinds::STATE_UPDATE,
+        }
+    }
+}
+
+impl fmt::Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogEvent::InstructionFetch(ins) => {
+                write!(f, "FETCH {:02X} {} {:?}", ins.opcode, ins.name, ins.mode)
+            }
+            LogEvent::BadOpcode(opcode) => write!(f, "BAD OPCODE {opcode:02X}"),
+            LogEvent::OperandFetch(operand) => write!(f, "OPERAND {operand:04X}"),
+            LogEvent::MemoryReadIntermediate(addr, val) => {
+                write!(f, "READ* {addr:04X} = {val:02X}")
+            }
+            LogEvent::MemoryRead(addr, val) => write!(f, "READ {addr:04X} = {val:02X}"),
+            LogEvent::MemoryWrite(val, addr) => write!(f, "WRITE {addr:04X} = {val:02X}"),
+            LogEvent::StackPush(val) => write!(f, "PUSH {val:02X}"),
+            LogEvent::StackPull(val) => write!(f, "PULL {val:02X}"),
+            LogEvent::NMIInterupt => write!(f, "NMI"),
+            LogEvent::StateUpdate(s) => write!(
+                f,
+                "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+                s.program_counter,
+                s.accumulator,
+                s.reg_x,
+                s.reg_y,
+                s.flags,
+                s.stack_pointer,
+                s.cycle_count
+            ),
+        }
+    }
+}
+
 pub trait Logger: std::fmt::Debug + Send {
     fn log_event(&mut self, le: LogEvent);
+    // called once an instruction and all of its events are done
     fn log_state(&mut self);
 }
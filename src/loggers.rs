@@ -0,0 +1,194 @@
+// ready-made Logger implementations. attach one with NESSystem::builder(..).logger(..), wrap it in
+// an Arc<Mutex<..>> first to keep a handle for reading it back while the system runs
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::prelude::Utc;
+
+use crate::{LogEvent, LogEventKinds, Logger};
+
+impl<L: Logger> Logger for Arc<Mutex<L>> {
+    fn log_event(&mut self, le: LogEvent) {
+        self.lock().unwrap().log_event(le);
+    }
+
+    fn log_state(&mut self) {
+        self.lock().unwrap().log_state();
+    }
+}
+
+// keeps the last `capacity` events, older ones are dropped
+#[derive(Debug)]
+pub struct RingBufferLogger {
+    events: VecDeque<LogEvent>,
+    capacity: usize,
+}
+
+impl RingBufferLogger {
+    pub fn new(capacity: usize) -> Self {
+        RingBufferLogger {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &LogEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+impl Logger for RingBufferLogger {
+    fn log_event(&mut self, le: LogEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(le);
+    }
+
+    fn log_state(&mut self) {}
+}
+
+// writes one line per event whose kind is in `filter`, flushed after every instruction
+#[derive(Debug)]
+pub struct FileLogger {
+    out: BufWriter<File>,
+    filter: LogEventKinds,
+}
+
+impl FileLogger {
+    pub fn new(path: impl AsRef<Path>, filter: LogEventKinds) -> io::Result<Self> {
+        Ok(FileLogger {
+            out: BufWriter::new(File::create(path)?),
+            filter,
+        })
+    }
+
+    // dir/ran_<date>_<time>.log
+    pub fn timestamped(dir: impl AsRef<Path>, filter: LogEventKinds) -> io::Result<Self> {
+        let name = format!("ran_{}.log", Utc::now().format("%Y-%m-%d_%H-%M-%S"));
+        FileLogger::new(dir.as_ref().join(name), filter)
+    }
+}
+
+impl Logger for FileLogger {
+    fn log_event(&mut self, le: LogEvent) {
+        if self.filter.contains(le.kind()) {
+            // a full disk shouldn't take the emulator down with it
+            let _ = writeln!(self.out, "{le}");
+        }
+    }
+
+    fn log_state(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+// counts events instead of keeping them
+#[derive(Debug, Clone)]
+pub struct StatsLogger {
+    pub opcodes: [usize; 256],
+    pub instructions: usize,
+    pub reads: usize,
+    pub intermediate_reads: usize,
+    pub writes: usize,
+    pub pushes: usize,
+    pub pulls: usize,
+    pub nmis: usize,
+    pub bad_opcodes: usize,
+    pub cycles: usize,
+}
+
+impl Default for StatsLogger {
+    fn default() -> Self {
+        StatsLogger {
+            opcodes: [0; 256],
+            instructions: 0,
+            reads: 0,
+            intermediate_reads: 0,
+            writes: 0,
+            pushes: 0,
+            pulls: 0,
+            nmis: 0,
+            bad_opcodes: 0,
+            cycles: 0,
+        }
+    }
+}
+
+impl StatsLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // (opcode, count) for the `n` most executed opcodes, most executed first
+    pub fn top_opcodes(&self, n: usize) -> Vec<(u8, usize)> {
+        let mut counts: Vec<(u8, usize)> = (0..=255_u8)
+            .map(|op| (op, self.opcodes[op as usize]))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
+impl Logger for StatsLogger {
+    fn log_event(&mut self, le: LogEvent) {
+        match le {
+            LogEvent::InstructionFetch(ins) => {
+                self.instructions += 1;
+                self.opcodes[ins.opcode as usize] += 1;
+            }
+            LogEvent::BadOpcode(_) => self.bad_opcodes += 1,
+            LogEvent::OperandFetch(_) => {}
+            LogEvent::MemoryReadIntermediate(..) => self.intermediate_reads += 1,
+            LogEvent::MemoryRead(..) => self.reads += 1,
+            LogEvent::MemoryWrite(..) => self.writes += 1,
+            LogEvent::StackPush(_) => self.pushes += 1,
+            LogEvent::StackPull(_) => self.pulls += 1,
+            LogEvent::NMIInterupt => self.nmis += 1,
+            LogEvent::StateUpdate(state) => self.cycles = state.cycle_count,
+        }
+    }
+
+    fn log_state(&mut self) {}
+}
+
+impl fmt::Display for StatsLogger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "instructions: {}", self.instructions)?;
+        writeln!(f, "cycles: {}", self.cycles)?;
+        writeln!(
+            f,
+            "reads: {} (+{} intermediate), writes: {}",
+            self.reads, self.intermediate_reads, self.writes
+        )?;
+        writeln!(f, "pushes: {}, pulls: {}", self.pushes, self.pulls)?;
+        writeln!(f, "nmis: {}, bad opcodes: {}", self.nmis, self.bad_opcodes)?;
+        for (opcode, count) in self.top_opcodes(10) {
+            writeln!(f, "  {opcode:02X}: {count}")?;
+        }
+        Ok(())
+    }
+}
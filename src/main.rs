@@ -29,7 +29,7 @@ fn main() {
     }
     let rom_name: &String = &env::args().collect::<Vec<String>>()[1];
 
-    let raw_bytes = fs::read(rom_name.to_owned()).unwrap();
+    let raw_bytes = fs::read(rom_name).unwrap();
 
     let event_loop = EventLoop::new().unwrap();
     let mut input = WinitInputHelper::new();
@@ -65,7 +65,7 @@ fn main() {
         {
             renderer.draw_to(pixels.frame_mut());
 
-            if let Err(_) = pixels.render() {
+            if pixels.render().is_err() {
                 println!("error in pixels.render :(");
                 elwt.exit();
                 return;
@@ -89,7 +89,11 @@ fn main() {
             //     }
             // }
 
-            emu.tick_one_frame();
+            if let Err(e) = emu.tick_one_frame() {
+                println!("{e}");
+                elwt.exit();
+                return;
+            }
 
             renderer.modify_buffer(|buf| emu.render(buf));
 
@@ -1,19 +1,34 @@
-use crate::ppu::Mirroring;
+use crate::{
+    memory::{cdl::CodeDataLog, memory_bus::PRG_ROM_START},
+    ppu::Mirroring,
+};
+
+#[cfg(test)]
+mod tests;
 
 pub const NES_TAG: [u8; 4] = [0x4e, 0x45, 0x53, 0x1a]; // string "NES<CTRL-Z>" in ascii
 pub const PRG_ROM_PAGE_SIZE: usize = 0x4000;
 pub const CHR_ROM_PAGE_SIZE: usize = 0x2000;
+const HEADER_SIZE: usize = 16;
 
 #[derive(Debug)]
 pub struct Cartridge {
     pub prg_rom: Vec<u8>,
-    pub chr_rom: Vec<u8>,
+    // the pattern tables: CHR rom from the file, or when chr_ram is set 8 KiB of ram the game
+    // draws its own tiles into
+    pub chr: Vec<u8>,
+    pub chr_ram: bool,
     pub mapper: u8,
     pub screen_mirroring: Mirroring,
+    // code/data log of the rom, off unless asked for
+    pub cdl: Option<CodeDataLog>,
 }
 
 impl Cartridge {
     pub fn new(raw_bytes: Vec<u8>) -> Result<Cartridge, &'static str> {
+        if raw_bytes.len() < HEADER_SIZE {
+            return Err("file is too short for an iNES header");
+        }
         if raw_bytes[0..4] != NES_TAG {
             return Err("file format is not iNES 1.0 (missing NES tag)");
         }
@@ -28,8 +43,11 @@ impl Cartridge {
 
         let trainer_offset = if raw_bytes[6] & 0b100 != 0 { 512 } else { 0 };
 
-        let prg_rom_start = 16 + trainer_offset;
+        let prg_rom_start = HEADER_SIZE + trainer_offset;
         let chr_rom_start = prg_rom_start + prg_rom_size;
+        if raw_bytes.len() < chr_rom_start + chr_rom_size {
+            return Err("file is shorter than the rom sizes in its header");
+        }
 
         let four_screen = raw_bytes[6] & 0b1000 != 0;
         let vertical_mirroring = raw_bytes[6] & 0b1 != 0;
@@ -41,21 +59,84 @@ impl Cartridge {
 
         let mapper = (raw_bytes[7] & 0b1111_0000) | (raw_bytes[6] >> 4);
 
+        let chr_ram = chr_rom_size == 0;
+        let chr = if chr_ram {
+            vec![0; CHR_ROM_PAGE_SIZE]
+        } else {
+            raw_bytes[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec()
+        };
+
         Ok(Cartridge {
             prg_rom: raw_bytes[prg_rom_start..(prg_rom_start + prg_rom_size)].to_vec(),
-            chr_rom: raw_bytes[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec(),
+            chr,
+            chr_ram,
             mapper,
             screen_mirroring,
+            cdl: None,
         })
     }
 
+    // offset into prg_rom that a cpu address maps to. only NROM is supported so far: 16 KiB
+    // carts are mirrored into $C000
+    pub fn prg_offset(&self, addr: u16) -> Option<usize> {
+        if addr < PRG_ROM_START || self.prg_rom.is_empty() {
+            return None;
+        }
+        Some((addr - PRG_ROM_START) as usize % self.prg_rom.len())
+    }
+
+    // offset into chr that a ppu pattern table address maps to
+    pub fn chr_offset(&self, addr: u16) -> Option<usize> {
+        if addr > 0x1fff || self.chr.is_empty() {
+            return None;
+        }
+        Some(addr as usize % self.chr.len())
+    }
+
+    // a pattern table write through $2007, rom ignores it
+    pub fn write_chr(&mut self, addr: u16, val: u8) {
+        if !self.chr_ram {
+            return;
+        }
+        if let Some(offset) = self.chr_offset(addr) {
+            self.chr[offset] = val;
+        }
+    }
+
+    // starts a fresh code/data log sized for this rom. chr ram isn't part of the rom file so
+    // it isn't logged, same as FCEUX
+    pub fn enable_cdl(&mut self) {
+        let chr_len = if self.chr_ram { 0 } else { self.chr.len() };
+        self.cdl = Some(CodeDataLog::new(self.prg_rom.len(), chr_len));
+    }
+
     pub fn dummy() -> Self {
         println!("WARNING: using dummy rom (no program can be loaded)");
         Self {
             prg_rom: vec![],
-            chr_rom: vec![],
+            chr: vec![],
+            chr_ram: false,
             mapper: 0,
             screen_mirroring: Mirroring::FourScreen,
+            cdl: None,
         }
     }
 }
+
+// an iNES image for tests: mapper 0, horizontal mirroring, one PRG bank assembled at $C000
+// (mirrored at $8000) and `chr` as CHR rom, an empty `chr` gives the cart CHR ram
+#[cfg(test)]
+pub fn nrom_image(program: &crate::asm::Program, chr: &[u8]) -> Vec<u8> {
+    let mut prg = vec![0; PRG_ROM_PAGE_SIZE];
+    for segment in &program.segments {
+        let start = segment.origin as usize - 0xc000;
+        prg[start..start + segment.bytes.len()].copy_from_slice(&segment.bytes);
+    }
+
+    let chr_banks = (chr.len() / CHR_ROM_PAGE_SIZE) as u8;
+    let mut rom = NES_TAG.to_vec();
+    rom.extend([1, chr_banks, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    rom.extend(prg);
+    rom.extend(chr);
+    rom
+}
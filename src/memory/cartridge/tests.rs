@@ -0,0 +1,46 @@
+use super::{nrom_image, Cartridge, CHR_ROM_PAGE_SIZE, NES_TAG, PRG_ROM_PAGE_SIZE};
+use crate::{asm::Assembler, error::EmulatorError, NESSystem};
+
+// PRG starts with a NOP and CHR is all $55, optionally with a trainer
+fn rom(trainer: bool) -> Vec<u8> {
+    let program = Assembler::new().assemble(".org $C000\nNOP").unwrap();
+    let mut rom = nrom_image(&program, &[0x55; CHR_ROM_PAGE_SIZE]);
+    if trainer {
+        rom[6] |= 0b100;
+        rom.splice(16..16, [0; 512]);
+    }
+    rom
+}
+
+#[test]
+fn test_load() {
+    let cart = Cartridge::new(rom(false)).unwrap();
+    assert_eq!(cart.prg_rom.len(), PRG_ROM_PAGE_SIZE);
+    assert_eq!(cart.chr.len(), CHR_ROM_PAGE_SIZE);
+    assert!(!cart.chr_ram);
+
+    // the trainer sits between the header and PRG rom
+    let cart = Cartridge::new(rom(true)).unwrap();
+    assert_eq!(cart.prg_rom[0], 0xea);
+    assert_eq!(cart.chr[0], 0x55);
+}
+
+#[test]
+fn test_truncated_rom() {
+    assert!(Cartridge::new(vec![]).is_err());
+    assert!(Cartridge::new(NES_TAG.to_vec()).is_err());
+
+    let full = rom(false);
+    assert!(Cartridge::new(full[..full.len() - 1].to_vec()).is_err());
+    assert!(Cartridge::new(full[..16 + PRG_ROM_PAGE_SIZE / 2].to_vec()).is_err());
+
+    // a trainer flag without the trainer leaves CHR rom short
+    let mut no_trainer = rom(false);
+    no_trainer[6] |= 0b100;
+    assert!(Cartridge::new(no_trainer).is_err());
+
+    assert!(matches!(
+        NESSystem::new(full[..100].to_vec()),
+        Err(EmulatorError::BadRom(_))
+    ));
+}
@@ -0,0 +1,142 @@
+// code/data logger, saved in FCEUX's .cdl format: one flag byte per PRG rom byte followed by one
+// per CHR rom byte, so a log lines up with the rom file (minus the header) and tools that read
+// FCEUX logs take it as is
+use std::{fmt, fs, io, path::Path};
+
+use bitflags::bitflags;
+
+use crate::memory::memory_bus::ReadKind;
+
+#[cfg(test)]
+mod tests;
+
+const JMP_INDIRECT: u8 = 0x6c;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct PrgFlags: u8 {
+        const CODE = 0b0000_0001;
+        const DATA = 0b0000_0010;
+        // the 8 KiB window ($8000, $A000, $C000 or $E000) the byte was last seen through
+        const BANK = 0b0000_1100;
+        // the target of a JMP ($nnnn)
+        const INDIRECT_CODE = 0b0001_0000;
+        // read through a (zp,X) or (zp),Y pointer
+        const INDIRECT_DATA = 0b0010_0000;
+        // played as DMC samples (there's no APU yet, so never set)
+        const PCM = 0b0100_0000;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct ChrFlags: u8 {
+        const RENDERED = 0b0000_0001;
+        // read through $2007
+        const READ = 0b0000_0010;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeDataLog {
+    prg: Vec<u8>,
+    chr: Vec<u8>,
+    // the instruction being fetched was jumped to through JMP ($nnnn)
+    indirect_target: bool,
+    // the last opcode fetched was a JMP ($nnnn), so the next instruction is its target
+    indirect_jump: bool,
+}
+
+impl CodeDataLog {
+    pub fn new(prg_len: usize, chr_len: usize) -> Self {
+        CodeDataLog {
+            prg: vec![0; prg_len],
+            chr: vec![0; chr_len],
+            indirect_target: false,
+            indirect_jump: false,
+        }
+    }
+
+    // picks up a log saved earlier, it has to be for a rom of the same size
+    pub fn from_bytes(bytes: &[u8], prg_len: usize, chr_len: usize) -> Result<Self, &'static str> {
+        if bytes.len() != prg_len + chr_len {
+            return Err("code/data log size doesn't match the rom");
+        }
+        let mut cdl = CodeDataLog::new(prg_len, chr_len);
+        cdl.prg.copy_from_slice(&bytes[..prg_len]);
+        cdl.chr.copy_from_slice(&bytes[prg_len..]);
+        Ok(cdl)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.prg.as_slice(), self.chr.as_slice()].concat()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn prg_flags(&self, offset: usize) -> PrgFlags {
+        PrgFlags::from_bits_truncate(self.prg[offset])
+    }
+
+    pub fn chr_flags(&self, offset: usize) -> ChrFlags {
+        ChrFlags::from_bits_truncate(self.chr[offset])
+    }
+
+    // every cpu read goes through here, `offset` is where it landed in PRG rom if it did
+    pub(crate) fn log_read(&mut self, addr: u16, offset: Option<usize>, kind: ReadKind, val: u8) {
+        if kind == ReadKind::Opcode {
+            self.indirect_target = self.indirect_jump;
+            self.indirect_jump = val == JMP_INDIRECT;
+        }
+
+        let Some(offset) = offset else {
+            return;
+        };
+        let mut flags = match kind {
+            ReadKind::Opcode | ReadKind::Operand if self.indirect_target => {
+                PrgFlags::CODE | PrgFlags::INDIRECT_CODE
+            }
+            ReadKind::Opcode | ReadKind::Operand => PrgFlags::CODE,
+            ReadKind::Data => PrgFlags::DATA,
+            ReadKind::IndirectData => PrgFlags::DATA | PrgFlags::INDIRECT_DATA,
+            ReadKind::Other => return,
+        };
+        flags |= PrgFlags::from_bits_retain(((addr >> 11) & 0b1100) as u8);
+
+        let logged = &mut self.prg[offset];
+        *logged = (*logged & !PrgFlags::BANK.bits()) | flags.bits();
+    }
+
+    // chr ram carts have nothing to log
+    pub(crate) fn log_chr(&mut self, offset: usize, flags: ChrFlags) {
+        if let Some(logged) = self.chr.get_mut(offset) {
+            *logged |= flags.bits();
+        }
+    }
+}
+
+impl fmt::Display for CodeDataLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let count = |log: &[u8], mask: u8| log.iter().filter(|&&b| b & mask != 0).count();
+        let unlogged = |log: &[u8]| log.iter().filter(|&&b| b == 0).count();
+
+        writeln!(
+            f,
+            "PRG: {} code, {} data, {} of {} unlogged",
+            count(&self.prg, PrgFlags::CODE.bits()),
+            count(&self.prg, PrgFlags::DATA.bits()),
+            unlogged(&self.prg),
+            self.prg.len()
+        )?;
+        write!(
+            f,
+            "CHR: {} rendered, {} read, {} of {} unlogged",
+            count(&self.chr, ChrFlags::RENDERED.bits()),
+            count(&self.chr, ChrFlags::READ.bits()),
+            unlogged(&self.chr),
+            self.chr.len()
+        )
+    }
+}
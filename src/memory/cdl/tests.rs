@@ -0,0 +1,138 @@
+use super::{ChrFlags, CodeDataLog, PrgFlags};
+use crate::{
+    asm::Assembler,
+    memory::{
+        cartridge::{nrom_image, CHR_ROM_PAGE_SIZE, PRG_ROM_PAGE_SIZE},
+        memory_bus::Bus,
+    },
+    NESSystem,
+};
+
+// one 16 KiB PRG bank, mirrored at $8000 and $C000
+const PROGRAM: &str = "
+        .org $C000
+reset:  LDA table
+        LDA #<table
+        STA $00
+        LDA #>table
+        STA $01
+        LDY #$01
+        LDA ($00),Y
+        LDA #$00
+        STA $2006
+        LDA #$10
+        STA $2006
+        LDA $2007
+        JMP (vec)
+target: JMP $8000 + target - $C000
+table:  .byte $11, $22, $33
+vec:    .word target
+nmi:    RTI
+
+        .org $FFFA
+        .word nmi, reset, nmi
+";
+
+fn test_system() -> (NESSystem, u16) {
+    test_system_chr(1)
+}
+
+fn test_system_chr(chr_banks: u8) -> (NESSystem, u16) {
+    let program = Assembler::new().assemble(PROGRAM).unwrap();
+    let rom = nrom_image(&program, &vec![0; CHR_ROM_PAGE_SIZE * chr_banks as usize]);
+    let system = NESSystem::builder(rom).code_data_log(true).build().unwrap();
+    (system, program.label("target").unwrap())
+}
+
+fn prg(system: &NESSystem, addr: u16) -> PrgFlags {
+    system
+        .code_data_log()
+        .unwrap()
+        .prg_flags(addr as usize - 0xc000)
+}
+
+#[test]
+fn test_prg_logging() {
+    let (mut system, target) = test_system();
+    let table = target + 3;
+    let vec = table + 3;
+
+    system.tick_n(15).unwrap();
+    let bank_c000 = PrgFlags::from_bits_retain(0b1000);
+    let bank_8000 = PrgFlags::empty();
+
+    // LDA table, opcode and operand
+    for addr in 0xc000..0xc003 {
+        assert_eq!(prg(&system, addr), PrgFlags::CODE | bank_c000);
+    }
+    assert_eq!(prg(&system, table), PrgFlags::DATA | bank_c000);
+    assert_eq!(
+        prg(&system, table + 1),
+        PrgFlags::DATA | PrgFlags::INDIRECT_DATA | bank_c000
+    );
+    assert_eq!(prg(&system, table + 2), PrgFlags::empty());
+
+    // the pointer JMP (vec) went through is data, where it went is indirect code. the JMP
+    // there is only ever seen through the $8000 mirror after that
+    assert_eq!(prg(&system, vec), PrgFlags::DATA | bank_c000);
+    assert_eq!(prg(&system, vec + 1), PrgFlags::DATA | bank_c000);
+    assert_eq!(
+        prg(&system, target),
+        PrgFlags::CODE | PrgFlags::INDIRECT_CODE | bank_8000
+    );
+
+    // reset vector
+    assert_eq!(
+        prg(&system, 0xfffc),
+        PrgFlags::DATA | PrgFlags::from_bits_retain(0b1100)
+    );
+}
+
+#[test]
+fn test_chr_logging() {
+    let (mut system, _) = test_system();
+    system.tick_n(12).unwrap();
+
+    let cdl = system.code_data_log().unwrap();
+    assert_eq!(cdl.chr_flags(0x10), ChrFlags::READ);
+    assert_eq!(cdl.chr_flags(0x00), ChrFlags::empty());
+
+    // the nametables are all tile 0 from the background table at $0000, fetched once the
+    // background is shown
+    system.cpu_mut().mem_bus.write(0x2001, 0b0000_1000);
+    system.tick_one_frame().unwrap();
+    let cdl = system.code_data_log().unwrap();
+    assert_eq!(cdl.chr_flags(0x00), ChrFlags::RENDERED);
+    assert_eq!(cdl.chr_flags(0x0f), ChrFlags::RENDERED);
+    assert_eq!(cdl.chr_flags(0x10), ChrFlags::READ);
+}
+
+#[test]
+fn test_cdl_file() {
+    let (mut system, _) = test_system();
+    system.tick_n(15).unwrap();
+
+    let cdl = system.code_data_log().unwrap();
+    let bytes = cdl.to_bytes();
+    assert_eq!(bytes.len(), PRG_ROM_PAGE_SIZE + CHR_ROM_PAGE_SIZE);
+    assert_eq!(bytes[0], 0x09);
+    assert_eq!(bytes[PRG_ROM_PAGE_SIZE + 0x10], 0x02);
+
+    let loaded = CodeDataLog::from_bytes(&bytes, PRG_ROM_PAGE_SIZE, CHR_ROM_PAGE_SIZE).unwrap();
+    assert_eq!(loaded.to_bytes(), bytes);
+    assert!(CodeDataLog::from_bytes(&bytes, PRG_ROM_PAGE_SIZE, 0).is_err());
+}
+
+#[test]
+fn test_chr_ram() {
+    // the log covers the rom file, chr ram isn't in it
+    let (mut system, _) = test_system_chr(0);
+    system.cpu_mut().mem_bus.write(0x2001, 0b0000_1000);
+    system.tick_one_frame().unwrap();
+
+    let cdl = system.code_data_log().unwrap();
+    assert_eq!(cdl.to_bytes().len(), PRG_ROM_PAGE_SIZE);
+    assert!(cdl
+        .to_string()
+        .ends_with("CHR: 0 rendered, 0 read, 0 of 0 unlogged"));
+}
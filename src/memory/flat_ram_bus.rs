@@ -0,0 +1,60 @@
+use crate::memory::memory_bus::Bus;
+
+// 64 KiB of plain ram with no other devices attached, for running the 6502 core on its own
+// (tests, non-NES projects). interrupt lines are driven by hand through nmi/irq
+#[derive(Debug)]
+pub struct FlatRamBus {
+    pub ram: Box<[u8; 0x10000]>,
+    pub nmi: bool,
+    pub irq: bool,
+    pub cycle_count: usize,
+}
+
+impl FlatRamBus {
+    pub fn new() -> Self {
+        Self {
+            ram: Box::new([0; 0x10000]),
+            nmi: false,
+            irq: false,
+            cycle_count: 0,
+        }
+    }
+
+    pub fn load(&mut self, start_address: u16, bytes: &[u8]) {
+        for (i, byte) in bytes.iter().enumerate() {
+            self.ram[start_address.wrapping_add(i as u16) as usize] = *byte;
+        }
+    }
+}
+
+impl Default for FlatRamBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus for FlatRamBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.ram[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.ram[addr as usize] = val;
+    }
+
+    fn dbg_read(&self, addr: u16) -> u8 {
+        self.ram[addr as usize]
+    }
+
+    fn tick(&mut self, cycles: usize) {
+        self.cycle_count += cycles;
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi)
+    }
+
+    fn irq_asserted(&self) -> bool {
+        self.irq
+    }
+}
@@ -1,4 +1,17 @@
-use crate::{make_u16, ppu::PPU, HEIGHT, WIDTH};
+use crate::{
+    make_u16,
+    memory::{
+        cdl::CodeDataLog,
+        watchpoints::{WatchKind, Watchpoints},
+    },
+    ppu::PPU,
+    HEIGHT, WIDTH,
+};
+
+use bitflags::bitflags;
+
+#[cfg(test)]
+mod tests;
 
 pub const RAM_START: u16 = 0x0000;
 pub const RAM_END_MIRRORED: u16 = 0x1fff;
@@ -21,18 +34,81 @@ pub const PRG_ROM_END_MIRRORED: u16 = 0xffff;
 pub struct MemoryBus {
     cpu_ram: [u8; 0x800],
     ppu: PPU,
+    irq_line: IrqSource,
+    // a $4014 write the cpu hasn't stalled for yet
+    oam_dma_pending: bool,
+    // frames the ppu has finished
+    frame_count: u64,
+    pub watchpoints: Watchpoints,
 }
 
-pub trait Bus<MemoryMapper, PPU> {
-    fn new(ppu: PPU);
+// everything the cpu can see. MemoryBus is the NES memory map, FlatRamBus is plain 64 KiB of
+// ram for running the 6502 core on its own
+pub trait Bus {
     fn read(&mut self, addr: u16) -> u8;
+    // the cpu reads through this, saying what each read is for
+    fn read_as(&mut self, addr: u16, _kind: ReadKind) -> u8 {
+        self.read(addr)
+    }
     fn write(&mut self, addr: u16, val: u8);
+    // read without side effects (ppu latches, etc.) for logging and debugging
+    fn dbg_read(&self, addr: u16) -> u8;
+
+    // clock every other device on the bus forward by a number of cpu cycles
+    fn tick(&mut self, cycles: usize);
+    fn poll_nmi(&mut self) -> bool;
+    fn irq_asserted(&self) -> bool;
+
+    // whether the last instruction started an OAM DMA, which halts the cpu while it copies.
+    // taking it clears it
+    fn take_oam_dma(&mut self) -> bool {
+        false
+    }
+
+    // low byte first, the order the 6502 puts them on the bus
+    fn read_16bit(&mut self, addr: u16) -> u16 {
+        let lo = self.read(addr);
+        make_u16!(self.read(addr.wrapping_add(1)), lo)
+    }
+
+    fn dbg_read_16bit(&self, addr: u16) -> u16 {
+        make_u16!(self.dbg_read(addr.wrapping_add(1)), self.dbg_read(addr))
+    }
+
+    // NMI is edge triggered so polling consumes it, IRQ is level triggered and stays pending
+    // until its source clears it
+    fn poll_interrupt(&mut self) -> Option<InterruptType> {
+        if self.poll_nmi() {
+            Some(InterruptType::NonMaskable)
+        } else if self.irq_asserted() {
+            Some(InterruptType::Request)
+        } else {
+            None
+        }
+    }
+
+    // where the ppu is, for nestest-style trace lines and frame stepping. cycles is the dot
+    // within the scanline
+    fn get_ppu_cycles(&self) -> usize {
+        0
+    }
+
+    fn get_ppu_scanline(&self) -> u16 {
+        0
+    }
+}
 
-    fn get_frame_pixel_buffer(&self) -> [u8; WIDTH * HEIGHT];
-    fn tick_ppu(&mut self, cycles: usize);
-    fn poll_interrupt(&self) -> Option<InterruptType>;
-    fn get_ppu_cycles(&self) -> usize;
-    fn get_ppu_scanline(&self) -> u16;
+// what a cpu read is for, so a code/data log can tell code from data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadKind {
+    Opcode,
+    Operand,
+    // the value an instruction works on, pointers and interrupt vectors
+    Data,
+    // data reached through a (zp,X), (zp),Y or (zp) pointer
+    IndirectData,
+    // the stack and dummy reads
+    Other,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -41,47 +117,60 @@ pub enum InterruptType {
     Request,     // IRQ
 }
 
+bitflags! {
+    // the IRQ line is shared (wired-or), it stays asserted while any source holds it
+    #[derive(Debug, Clone, Copy)]
+    pub struct IrqSource: u8 {
+        const APU_FRAME_COUNTER = 0b00000001;
+        const APU_DMC = 0b00000010;
+        const MAPPER = 0b00000100;
+    }
+}
+
 impl MemoryBus {
     pub fn new(ppu: PPU) -> Self {
         MemoryBus {
             cpu_ram: [0; 2048],
             ppu,
+            irq_line: IrqSource::empty(),
+            oam_dma_pending: false,
+            frame_count: 0,
+            watchpoints: Watchpoints::default(),
         }
     }
 
-    pub fn read(&mut self, addr: u16) -> u8 {
-        match addr {
-            RAM_START..=RAM_END_MIRRORED => {
-                let truncated_addr = addr & RAM_ADDR_MASK;
-                self.cpu_ram[truncated_addr as usize]
-            }
-            PPU_REG_START..=PPU_REG_END_MIRRORED => match addr {
-                0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => {
-                    // panic!("cannot read from write only PPU address 0x{:x}", addr);
-                    return 0;
-                }
-                0x2002 => self.ppu.read_status(),
-                0x2004 => self.ppu.read_oam_data(),
-                0x2007 => self.ppu.read_data(),
+    // pub fn get_frame_pixel_buffer(&self) -> [u8; WIDTH * HEIGHT] {
+    //     self.ppu.get_frame_pixel_buffer()
+    // }
 
-                _ => self.read(addr & PPU_REG_ADDR_MASK),
-            },
-            PRG_ROM_START..=PRG_ROM_END_MIRRORED => {
-                let mut prg_addr = addr - PRG_ROM_START;
-                if self.ppu.cart.prg_rom.len() == 0x4000 && prg_addr >= 0x4000 {
-                    prg_addr %= 0x4000;
-                }
-                self.ppu.cart.prg_rom[prg_addr as usize]
-            }
-            _ => {
-                // println!("WARNING: BAD READ at 0x{:x}", addr);
-                0
-                // panic!("bad memory read at 0x{:x}", addr);
-            }
+    pub fn render(&self, target: &mut [u16; WIDTH * HEIGHT]) {
+        self.ppu.draw_to_buffer(target);
+    }
+
+    pub fn cdl(&self) -> Option<&CodeDataLog> {
+        self.ppu.cart.cdl.as_ref()
+    }
+
+    // keeps a log already running
+    pub fn enable_cdl(&mut self) {
+        if self.ppu.cart.cdl.is_none() {
+            self.ppu.cart.enable_cdl();
         }
     }
 
-    pub fn dbg_read(&self, addr: u16) -> u8 {
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn assert_irq(&mut self, source: IrqSource) {
+        self.irq_line.insert(source);
+    }
+
+    pub fn clear_irq(&mut self, source: IrqSource) {
+        self.irq_line.remove(source);
+    }
+
+    fn read_mapped(&mut self, addr: u16) -> u8 {
         match addr {
             RAM_START..=RAM_END_MIRRORED => {
                 let truncated_addr = addr & RAM_ADDR_MASK;
@@ -90,21 +179,18 @@ impl MemoryBus {
             PPU_REG_START..=PPU_REG_END_MIRRORED => match addr {
                 0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => {
                     // panic!("cannot read from write only PPU address 0x{:x}", addr);
-                    return 0;
+                    0
                 }
-                0x2002 => self.ppu.regs.stat.bits(),
-                0x2004 => self.ppu.regs.oam_data,
-                0x2007 => self.ppu.regs.ppu_data,
+                0x2002 => self.ppu.read_status(),
+                0x2004 => self.ppu.read_oam_data(),
+                0x2007 => self.ppu.read_data(),
 
-                _ => self.dbg_read(addr & PPU_REG_ADDR_MASK),
+                _ => self.read_mapped(addr & PPU_REG_ADDR_MASK),
+            },
+            PRG_ROM_START..=PRG_ROM_END_MIRRORED => match self.ppu.cart.prg_offset(addr) {
+                Some(offset) => self.ppu.cart.prg_rom[offset],
+                None => 0,
             },
-            PRG_ROM_START..=PRG_ROM_END_MIRRORED => {
-                let mut prg_addr = addr - PRG_ROM_START;
-                if self.ppu.cart.prg_rom.len() == 0x4000 && prg_addr >= 0x4000 {
-                    prg_addr %= 0x4000;
-                }
-                self.ppu.cart.prg_rom[prg_addr as usize]
-            }
             _ => {
                 // println!("WARNING: BAD READ at 0x{:x}", addr);
                 0
@@ -113,15 +199,7 @@ impl MemoryBus {
         }
     }
 
-    pub fn dbg_read_16bit(&self, addr: u16) -> u16 {
-        make_u16!(self.dbg_read(addr + 1), self.dbg_read(addr))
-    }
-
-    pub fn read_16bit(&mut self, addr: u16) -> u16 {
-        make_u16!(self.read(addr + 1), self.read(addr))
-    }
-
-    pub fn write(&mut self, addr: u16, val: u8) {
+    fn write_mapped(&mut self, addr: u16, val: u8) {
         match addr {
             RAM_START..=RAM_END_MIRRORED => {
                 let truncated_addr = addr & RAM_ADDR_MASK;
@@ -130,7 +208,8 @@ impl MemoryBus {
             PPU_REG_START..=PPU_REG_END_MIRRORED => match addr & PPU_REG_ADDR_MASK {
                 0x2000 => self.ppu.write_to_ctrl(val),
                 0x2001 => self.ppu.write_to_mask(val),
-                0x2002 => panic!("attempting to write to PPU status (read only)"),
+                // read only
+                0x2002 => {}
                 0x2003 => self.ppu.write_to_oam_addr(val),
                 0x2004 => self.ppu.write_to_oam_data(val),
                 0x2005 => self.ppu.write_to_scrl(val),
@@ -146,13 +225,10 @@ impl MemoryBus {
                 }
 
                 self.ppu.write_oam_dma(&buffer);
+                self.oam_dma_pending = true;
             }
-            PRG_ROM_START..=PRG_ROM_END_MIRRORED => {
-                panic!(
-                    "attempting to write to rom space at 0x{:x} (val {val})",
-                    addr
-                );
-            }
+            // NROM has no registers, writes to rom go nowhere
+            PRG_ROM_START..=PRG_ROM_END_MIRRORED => {}
             _ => {
                 // println!("WARNING: BAD WRITE at 0x{:x} (val {val})", addr);
 
@@ -160,27 +236,82 @@ impl MemoryBus {
             }
         }
     }
+}
 
-    // pub fn get_frame_pixel_buffer(&self) -> [u8; WIDTH * HEIGHT] {
-    //     self.ppu.get_frame_pixel_buffer()
-    // }
+impl Bus for MemoryBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.read_as(addr, ReadKind::Data)
+    }
 
-    pub fn render(&self, target: &mut [u8; WIDTH * HEIGHT]) {
-        self.ppu.draw_to_buffer(target);
+    fn read_as(&mut self, addr: u16, kind: ReadKind) -> u8 {
+        let val = self.read_mapped(addr);
+        self.watchpoints.check(WatchKind::Read, addr, val);
+
+        let offset = self.ppu.cart.prg_offset(addr);
+        if let Some(cdl) = self.ppu.cart.cdl.as_mut() {
+            cdl.log_read(addr, offset, kind, val);
+        }
+        val
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.watchpoints.check(WatchKind::Write, addr, val);
+        self.write_mapped(addr, val);
+    }
+
+    fn dbg_read(&self, addr: u16) -> u8 {
+        match addr {
+            RAM_START..=RAM_END_MIRRORED => {
+                let truncated_addr = addr & RAM_ADDR_MASK;
+                self.cpu_ram[truncated_addr as usize]
+            }
+            PPU_REG_START..=PPU_REG_END_MIRRORED => match addr {
+                0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => {
+                    // panic!("cannot read from write only PPU address 0x{:x}", addr);
+                    0
+                }
+                0x2002 => self.ppu.regs.stat.bits(),
+                0x2004 => self.ppu.read_oam_data(),
+                0x2007 => self.ppu.regs.ppu_data,
+
+                _ => self.dbg_read(addr & PPU_REG_ADDR_MASK),
+            },
+            PRG_ROM_START..=PRG_ROM_END_MIRRORED => match self.ppu.cart.prg_offset(addr) {
+                Some(offset) => self.ppu.cart.prg_rom[offset],
+                None => 0,
+            },
+            _ => {
+                // println!("WARNING: BAD READ at 0x{:x}", addr);
+                0
+                // panic!("bad memory read at 0x{:x}", addr);
+            }
+        }
     }
-    pub fn tick_ppu(&mut self, cycles: usize) {
-        self.ppu.tick(cycles * 3); // ppu clock cycles are 3x faster than cpu
+
+    fn tick(&mut self, cycles: usize) {
+        // ppu clock cycles are 3x faster than cpu
+        if self.ppu.tick(cycles * 3) {
+            self.frame_count += 1;
+        }
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        matches!(self.ppu.interrupt.take(), Some(InterruptType::NonMaskable))
+    }
+
+    fn irq_asserted(&self) -> bool {
+        !self.irq_line.is_empty()
     }
 
-    pub fn poll_interrupt(&self) -> Option<InterruptType> {
-        self.ppu.interrupt
+    fn take_oam_dma(&mut self) -> bool {
+        std::mem::take(&mut self.oam_dma_pending)
     }
 
-    pub fn get_ppu_cycles(&self) -> usize {
+    fn get_ppu_cycles(&self) -> usize {
         self.ppu.cycle_count
     }
 
-    pub fn get_ppu_scanline(&self) -> u16 {
+    fn get_ppu_scanline(&self) -> u16 {
         self.ppu.scanline
     }
 }
@@ -0,0 +1,36 @@
+use super::{Bus, MemoryBus};
+use crate::{
+    asm::Assembler,
+    memory::cartridge::{nrom_image, Cartridge, CHR_ROM_PAGE_SIZE},
+    ppu::PPU,
+};
+
+fn test_bus() -> MemoryBus {
+    let program = Assembler::new()
+        .assemble(".org $C000\n.byte $11, $22")
+        .unwrap();
+    let rom = nrom_image(&program, &[0x33; CHR_ROM_PAGE_SIZE]);
+    MemoryBus::new(PPU::new(Cartridge::new(rom).unwrap()))
+}
+
+#[test]
+fn test_ignored_writes() {
+    let mut bus = test_bus();
+
+    // rom, through both mirrors
+    bus.write(0x8000, 0x5a);
+    bus.write(0xc001, 0x5a);
+    assert_eq!(bus.dbg_read(0xc000), 0x11);
+    assert_eq!(bus.dbg_read(0x8001), 0x22);
+
+    // CHR rom through $2007
+    bus.write(0x2006, 0x00);
+    bus.write(0x2006, 0x00);
+    bus.write(0x2007, 0x5a);
+    assert_eq!(bus.ppu.cart.chr[0], 0x33);
+
+    // PPUSTATUS is read only
+    let status = bus.ppu.regs.stat.bits();
+    bus.write(0x2002, 0xff);
+    assert_eq!(bus.ppu.regs.stat.bits(), status);
+}
@@ -1,2 +1,5 @@
 pub mod cartridge;
+pub mod cdl;
+pub mod flat_ram_bus;
 pub mod memory_bus;
+pub mod watchpoints;
@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+
+use crate::memory::memory_bus::{
+    PPU_REG_ADDR_MASK, PPU_REG_END_MIRRORED, PPU_REG_START, RAM_ADDR_MASK, RAM_END_MIRRORED,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchHit {
+    pub kind: WatchKind,
+    // the address as the cpu put it on the bus, mirrors included
+    pub addr: u16,
+    pub val: u8,
+}
+
+// read/write watchpoints for MemoryBus. a watch on ram or a ppu register also catches its
+// mirrors. hits pile up until the debugger takes them
+#[derive(Debug, Default)]
+pub struct Watchpoints {
+    reads: BTreeSet<u16>,
+    writes: BTreeSet<u16>,
+    hits: Vec<WatchHit>,
+}
+
+fn canonical(addr: u16) -> u16 {
+    match addr {
+        0..=RAM_END_MIRRORED => addr & RAM_ADDR_MASK,
+        PPU_REG_START..=PPU_REG_END_MIRRORED => addr & PPU_REG_ADDR_MASK,
+        _ => addr,
+    }
+}
+
+impl Watchpoints {
+    pub fn add(&mut self, kind: WatchKind, addr: u16) {
+        match kind {
+            WatchKind::Read => self.reads.insert(canonical(addr)),
+            WatchKind::Write => self.writes.insert(canonical(addr)),
+        };
+    }
+
+    // removes both kinds
+    pub fn remove(&mut self, addr: u16) {
+        self.reads.remove(&canonical(addr));
+        self.writes.remove(&canonical(addr));
+    }
+
+    // (address, kind) for every watchpoint
+    pub fn list(&self) -> impl Iterator<Item = (u16, WatchKind)> + '_ {
+        let reads = self.reads.iter().map(|&a| (a, WatchKind::Read));
+        let writes = self.writes.iter().map(|&a| (a, WatchKind::Write));
+        reads.chain(writes)
+    }
+
+    pub fn take_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.hits)
+    }
+
+    pub(crate) fn check(&mut self, kind: WatchKind, addr: u16, val: u8) {
+        let set = match kind {
+            WatchKind::Read => &self.reads,
+            WatchKind::Write => &self.writes,
+        };
+        if !set.is_empty() && set.contains(&canonical(addr)) {
+            self.hits.push(WatchHit { kind, addr, val });
+        }
+    }
+}
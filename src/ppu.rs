@@ -1,7 +1,7 @@
 #![allow(non_snake_case)]
 
 use crate::{
-    memory::{cartridge::Cartridge, memory_bus::InterruptType},
+    memory::{cartridge::Cartridge, cdl::ChrFlags, memory_bus::InterruptType},
     ppu::ppu_registers::*,
     HEIGHT, WIDTH,
 };
@@ -9,10 +9,30 @@ use crate::{
 // pub mod ppu;
 pub mod ppu_registers;
 
+#[cfg(test)]
+mod tests;
+
+const DOTS_PER_SCANLINE: usize = 341;
+const PRE_RENDER_SCANLINE: u16 = 261;
+const VBLANK_SCANLINE: u16 = 241;
+
+// one of the (up to) 8 sprites on the scanline being drawn, fetched at the end of the line before
+#[derive(Debug, Clone, Copy, Default)]
+struct ScanlineSprite {
+    x: u8,
+    attributes: u8,
+    // already flipped horizontally if the sprite is
+    pattern_lo: u8,
+    pattern_hi: u8,
+    // oam entry 0, the one whose pixels set the sprite 0 hit flag
+    sprite_zero: bool,
+}
+
 #[derive(Debug)]
 pub struct PPU {
     pub palette_table: [u8; 32],
-    pub vram: [u8; 2048],
+    // the console has 2 KiB of nametable ram, four screen carts bring the other 2
+    pub vram: [u8; 0x1000],
     pub oam_data: [u8; 256],
 
     pub cart: Cartridge,
@@ -21,40 +41,70 @@ pub struct PPU {
 
     internal_data_buf: u8,
 
+    // the dot within the scanline, 0-340
     pub cycle_count: usize,
+    // 0-239 are drawn, vblank starts on 241 and 261 is the pre-render line
     pub scanline: u16,
+    // every other frame is a dot short, see step
+    odd_frame: bool,
 
     pub interrupt: Option<InterruptType>,
+
+    // palette indices with the emphasis bits above them, see draw_pixel. written a dot at a
+    // time as the picture is drawn
+    frame: Vec<u16>,
+
+    // the background tile being fetched, it's loaded into the shifters every 8 dots
+    next_tile: u8,
+    next_attribute: u8,
+    next_pattern_lo: u8,
+    next_pattern_hi: u8,
+    // 16 pixels of background, the current tile in the high byte and the next in the low.
+    // the attribute bits are spread out to match so fine x can pick from all four the same way
+    pattern_shift_lo: u16,
+    pattern_shift_hi: u16,
+    attribute_shift_lo: u16,
+    attribute_shift_hi: u16,
+
+    // oam indices of the sprites found on the next scanline, then their patterns
+    sprites_found: Vec<usize>,
+    sprites: Vec<ScanlineSprite>,
 }
 
 impl PPU {
     pub fn new(cart: Cartridge) -> Self {
         PPU {
-            vram: [0; 2048],
+            vram: [0; 0x1000],
             oam_data: [0; 64 * 4],
             cart,
             palette_table: [0; 32],
             regs: Registers::new(),
             internal_data_buf: 0,
-            cycle_count: 21,
+            cycle_count: 0,
             scanline: 0,
+            odd_frame: false,
             interrupt: None,
+            frame: vec![0; WIDTH * HEIGHT],
+            next_tile: 0,
+            next_attribute: 0,
+            next_pattern_lo: 0,
+            next_pattern_hi: 0,
+            pattern_shift_lo: 0,
+            pattern_shift_hi: 0,
+            attribute_shift_lo: 0,
+            attribute_shift_hi: 0,
+            sprites_found: Vec::with_capacity(8),
+            sprites: Vec::with_capacity(8),
         }
     }
 
-    pub fn draw_to_buffer(&self, target: &mut [u8; WIDTH * HEIGHT]) {
-        self.draw_background(target);
+    // the last frame drawn, or the one being drawn so far
+    pub fn draw_to_buffer(&self, target: &mut [u16; WIDTH * HEIGHT]) {
+        target.copy_from_slice(&self.frame);
     }
 
-    // pub fn get_frame_pixel_buffer(&self) -> [u8; WIDTH * HEIGHT] {
-    //     let mut frame = [0; WIDTH * HEIGHT];
-    //     self.draw_background(&mut frame);
-    //     // println!("{:?}", frame);
-    //     frame
-    // }
-
-    fn draw_background(&self, frame: &mut [u8; WIDTH * HEIGHT]) {
-        let bank_offset = if self
+    fn background_pattern_table(&self) -> u16 {
+        if self
             .regs
             .ctrl
             .contains(ControlFlags::BACKGROUND_PATTERN_TABLE_ADDR)
@@ -62,99 +112,356 @@ impl PPU {
             0x1000
         } else {
             0
-        };
+        }
+    }
+
+    fn sprite_height(&self) -> usize {
+        if self.regs.ctrl.contains(ControlFlags::SPRITE_SIZE) {
+            16
+        } else {
+            8
+        }
+    }
+
+    // where the low pattern byte of the row of `sprite` that lands on the line after `scanline`
+    // lives, vertical flip included. 8x16 sprites ignore the sprite pattern table flag, bit 0
+    // of the tile index picks the table
+    fn sprite_row_addr(&self, sprite: &[u8], scanline: usize) -> u16 {
+        let tile_index = sprite[1] as u16;
+        let mut row = scanline - sprite[0] as usize;
+        if sprite[2] & 0b1000_0000 != 0 {
+            row = self.sprite_height() - 1 - row;
+        }
+        let row = row as u16;
+
+        if self.sprite_height() == 16 {
+            let bank = (tile_index & 1) * 0x1000;
+            let tile = (tile_index & 0xfe) + row / 8;
+            bank + tile * 16 + row % 8
+        } else {
+            let bank = if self
+                .regs
+                .ctrl
+                .contains(ControlFlags::SPRITE_PATTERN_TABLE_ADDR)
+            {
+                0x1000
+            } else {
+                0
+            };
+            bank + tile_index * 16 + row
+        }
+    }
+
+    fn rendering_enabled(&self) -> bool {
+        self.regs
+            .mask
+            .intersects(MaskFlags::BACKGROUND_ENABLE | MaskFlags::SPRITE_ENABLE)
+    }
+
+    // on a line the ppu draws or prepares with rendering on, when it's using v and oam itself
+    fn rendering_in_progress(&self) -> bool {
+        let rendering_line = self.scanline < HEIGHT as u16 || self.scanline == PRE_RENDER_SCANLINE;
+        rendering_line && self.rendering_enabled()
+    }
+
+    // advances the ppu by a number of its own cycles (dots), true when a frame has finished,
+    // which is when the pre-render line ends and the next frame's line 0 starts
+    pub fn tick(&mut self, cycles: usize) -> bool {
+        let mut frame_done = false;
+        for _ in 0..cycles {
+            frame_done |= self.step();
+        }
+        frame_done
+    }
+
+    fn step(&mut self) -> bool {
+        let dot = self.cycle_count;
+        let scanline = self.scanline;
+        let visible = scanline < HEIGHT as u16;
+        let pre_render = scanline == PRE_RENDER_SCANLINE;
+
+        let rendering = self.rendering_in_progress();
+        // the shifters move before the dot's pixel comes out of them
+        if rendering {
+            self.fetch_background(dot, pre_render);
+        }
+        if visible && (1..=WIDTH).contains(&dot) {
+            self.draw_pixel(dot - 1, scanline as usize);
+        }
+        if rendering {
+            self.fetch_sprites(dot, scanline);
+        }
+
+        if scanline == VBLANK_SCANLINE && dot == 1 {
+            self.regs.stat.insert(StatusFlags::VBLANK);
+            if self.regs.ctrl.contains(ControlFlags::VBLANK_NMI_ENABLE) {
+                self.interrupt = Some(InterruptType::NonMaskable);
+            }
+        }
+        if pre_render && dot == 1 {
+            self.interrupt = None;
+            self.regs.stat.remove(
+                StatusFlags::SPRITE_0_HIT | StatusFlags::SPRITE_OVERFLOW | StatusFlags::VBLANK,
+            );
+        }
+
+        self.cycle_count += 1;
+        // with rendering on, odd frames skip the last dot of the pre-render line
+        if pre_render
+            && self.cycle_count == DOTS_PER_SCANLINE - 1
+            && self.odd_frame
+            && self.rendering_enabled()
+        {
+            self.cycle_count += 1;
+        }
+        if self.cycle_count >= DOTS_PER_SCANLINE {
+            self.cycle_count = 0;
+            self.scanline += 1;
+            if self.scanline > PRE_RENDER_SCANLINE {
+                self.scanline = 0;
+                self.odd_frame = !self.odd_frame;
+                return true;
+            }
+        }
+        false
+    }
+
+    // the background pipeline: a nametable, attribute and two pattern bytes every 8 dots for the
+    // 32 tiles on screen (1-256) and the first two of the next line (321-336), moving v along
+    // as it goes
+    fn fetch_background(&mut self, dot: usize, pre_render: bool) {
+        if (2..=257).contains(&dot) || (321..=337).contains(&dot) {
+            self.shift_background();
+
+            match (dot - 1) % 8 {
+                0 => {
+                    self.load_background_shifters();
+                    self.next_tile = self.vram_read(self.regs.tile_addr());
+                }
+                2 => {
+                    let attribute = self.vram_read(self.regs.attribute_addr());
+                    self.next_attribute = (attribute >> self.regs.attribute_shift()) & 0b11;
+                }
+                4 => {
+                    self.next_pattern_lo = self.pattern_read(self.background_row_addr());
+                }
+                6 => {
+                    self.next_pattern_hi = self.pattern_read(self.background_row_addr() + 8);
+                }
+                7 => self.regs.increment_x(),
+                _ => {}
+            }
+        }
+
+        match dot {
+            256 => self.regs.increment_y(),
+            257 => {
+                self.load_background_shifters();
+                self.regs.copy_horizontal();
+            }
+            // unused nametable fetches at the end of the line
+            338 | 340 => self.next_tile = self.vram_read(self.regs.tile_addr()),
+            280..=304 if pre_render => self.regs.copy_vertical(),
+            _ => {}
+        }
+    }
+
+    fn background_row_addr(&self) -> u16 {
+        self.background_pattern_table() + self.next_tile as u16 * 16 + self.regs.fine_y()
+    }
+
+    fn shift_background(&mut self) {
+        self.pattern_shift_lo <<= 1;
+        self.pattern_shift_hi <<= 1;
+        self.attribute_shift_lo <<= 1;
+        self.attribute_shift_hi <<= 1;
+    }
+
+    fn load_background_shifters(&mut self) {
+        let spread = |bit: u8| if bit != 0 { 0xff } else { 0x00 };
+        self.pattern_shift_lo = (self.pattern_shift_lo & 0xff00) | self.next_pattern_lo as u16;
+        self.pattern_shift_hi = (self.pattern_shift_hi & 0xff00) | self.next_pattern_hi as u16;
+        self.attribute_shift_lo =
+            (self.attribute_shift_lo & 0xff00) | spread(self.next_attribute & 0b01);
+        self.attribute_shift_hi =
+            (self.attribute_shift_hi & 0xff00) | spread(self.next_attribute & 0b10);
+    }
 
-        for tile_index in 0..960 {
-            let pattern_index = self.vram[tile_index] as usize;
-            let tile_x = tile_index % 32;
-            let tile_y = tile_index / 32;
-
-            let tile_offset = bank_offset + (pattern_index * 16);
-            let tile_slice = &self.cart.chr_rom[tile_offset..(tile_offset + 16)];
-
-            let local_chunk_palette = self.get_background_chunk_palette(tile_x, tile_y);
-
-            for local_pix_y in 0..8 {
-                let color_bit_hi = tile_slice[local_pix_y];
-                let color_bit_lo = tile_slice[local_pix_y + 8];
-                for local_pix_x in 0..8 {
-                    let bit = 7 - local_pix_x;
-                    let color_select =
-                        (((color_bit_hi >> bit) & 1) << 1) | ((color_bit_lo >> bit) & 1);
-                    let pix_x = tile_x * 8 + local_pix_x;
-                    let pix_y = tile_y * 8 + local_pix_y;
-                    frame[pix_x + WIDTH * pix_y] = local_chunk_palette[color_select as usize];
+    // sprite evaluation for the next line happens while this one is drawn, the patterns are
+    // fetched in the hblank after it (257-320), 8 dots a sprite. the pre-render line finds
+    // nothing, so there are never sprites on line 0
+    fn fetch_sprites(&mut self, dot: usize, scanline: u16) {
+        // oam addr is held at 0 while the patterns are fetched
+        if (257..=320).contains(&dot) {
+            self.regs.oam_addr = 0;
+        }
+
+        match dot {
+            // hardware works through oam over dots 65-256, the result's the same
+            256 => {
+                self.sprites_found.clear();
+                if scanline == PRE_RENDER_SCANLINE {
+                    return;
+                }
+                self.evaluate_sprites(scanline as usize);
+            }
+            257 => self.sprites.clear(),
+            264..=320 if dot & 0b111 == 0 => {
+                let Some(&i) = self.sprites_found.get((dot - 264) / 8) else {
+                    return;
+                };
+                let sprite = [
+                    self.oam_data[i * 4],
+                    self.oam_data[i * 4 + 1],
+                    self.oam_data[i * 4 + 2],
+                    self.oam_data[i * 4 + 3],
+                ];
+                let row_addr = self.sprite_row_addr(&sprite, scanline as usize);
+                let mut pattern_lo = self.pattern_read(row_addr);
+                let mut pattern_hi = self.pattern_read(row_addr + 8);
+                if sprite[2] & 0b0100_0000 != 0 {
+                    pattern_lo = pattern_lo.reverse_bits();
+                    pattern_hi = pattern_hi.reverse_bits();
                 }
+                self.sprites.push(ScanlineSprite {
+                    x: sprite[3],
+                    attributes: sprite[2],
+                    pattern_lo,
+                    pattern_hi,
+                    sprite_zero: i == 0,
+                });
             }
+            _ => {}
         }
     }
 
-    fn get_background_chunk_palette(&self, tile_x: usize, tile_y: usize) -> [u8; 4] {
-        // chunk layout:
-        // A A | B B
-        // A A | B B
-        // ---------
-        // C C | D D
-        // C C | D D
+    // finds the first 8 sprites in oam order that cover the line after `scanline`. past that
+    // the hardware goes on looking for a ninth to set the overflow flag, but it steps through
+    // the bytes of each sprite as well as from sprite to sprite, so it checks tile numbers,
+    // attributes and x positions as if they were y positions
+    fn evaluate_sprites(&mut self, scanline: usize) {
+        let height = self.sprite_height();
+        let on_line = |y: u8| scanline.wrapping_sub(y as usize) < height;
+
+        let mut n = 0;
+        while n < 64 && self.sprites_found.len() < 8 {
+            if on_line(self.oam_data[n * 4]) {
+                self.sprites_found.push(n);
+            }
+            n += 1;
+        }
 
-        // the attrib table has chunks of 4x4 tiles, so find 4x4 block of given coord
-        let attrib_table_index = (tile_x / 4) + (8 * (tile_y / 4));
-        let attrib_byte = self.vram[960 + attrib_table_index];
+        let mut m = 0;
+        while n < 64 {
+            if on_line(self.oam_data[n * 4 + m]) {
+                self.regs.stat.insert(StatusFlags::SPRITE_OVERFLOW);
+                break;
+            }
+            n += 1;
+            m = (m + 1) & 0b11;
+        }
+    }
 
-        // split into 2x2 grid within 4x4 chunk
-        let pallet_index = match ((tile_x % 4) / 2, (tile_y % 4) / 2) {
-            (0, 0) => (attrib_byte >> 0) & 0b11,
-            (1, 0) => (attrib_byte >> 2) & 0b11,
-            (0, 1) => (attrib_byte >> 4) & 0b11,
-            (1, 1) => (attrib_byte >> 6) & 0b11,
-            _ => unreachable!(),
+    fn draw_pixel(&mut self, x: usize, y: usize) {
+        let mask = self.regs.mask;
+        let addr = if self.rendering_enabled() {
+            self.pixel_palette_addr(x, mask)
+        } else if self.regs.v & 0x3f00 == 0x3f00 {
+            // with rendering off the backdrop's shown, unless v points into the palette, then
+            // it's the colour there
+            self.regs.v
+        } else {
+            0x3f00
         };
 
-        // pallette table has weird layout for 13 colours:
-        // [UBG, {0,1,2}, {3,4,5}, {6,7,8}, {9,10,11}]
-        let pallete_start: usize = 1 + (pallet_index as usize) * 4;
-        [
-            self.palette_table[0],
-            self.palette_table[pallete_start],
-            self.palette_table[pallete_start + 1],
-            self.palette_table[pallete_start + 2],
-        ]
+        // greyscale keeps just the brightness column of the palette
+        let color_mask = if mask.contains(MaskFlags::GREYSCALE_ENABLE) {
+            0x30
+        } else {
+            0x3f
+        };
+        let color = (self.palette_table[palette_index(addr)] & color_mask) as u16;
+        // the red, green and blue emphasis bits go above the 6 bit colour, a 512 colour index
+        let emphasis = (mask.bits() as u16 & 0b1110_0000) << 1;
+        self.frame[x + WIDTH * y] = color | emphasis;
     }
 
-    pub fn tick(&mut self, cycles: usize) -> bool {
-        self.cycle_count += cycles;
-        if self.cycle_count >= 341 {
-            self.cycle_count -= 341;
-            self.scanline += 1;
+    // where in the palette the background and sprites say dot `x` comes from
+    fn pixel_palette_addr(&mut self, x: usize, mask: MaskFlags) -> u16 {
+        let left_edge = x < 8;
+
+        let bit = 15 - self.regs.x as u16;
+        let mut bg_pixel =
+            ((self.pattern_shift_hi >> bit) & 1) << 1 | ((self.pattern_shift_lo >> bit) & 1);
+        let bg_palette =
+            ((self.attribute_shift_hi >> bit) & 1) << 1 | ((self.attribute_shift_lo >> bit) & 1);
+        if !mask.contains(MaskFlags::BACKGROUND_ENABLE)
+            || (left_edge && !mask.contains(MaskFlags::LEFT_EDGE_BACKGROUND))
+        {
+            bg_pixel = 0;
+        }
 
-            if self.scanline == 241 {
-                self.regs.stat.insert(StatusFlags::VBLANK);
-                self.regs.stat.remove(StatusFlags::SPRITE_0_HIT);
-                if self.regs.ctrl.contains(ControlFlags::VBLANK_NMI_ENABLE) {
-                    self.interrupt = Some(InterruptType::NonMaskable);
+        // the lowest numbered sprite with a solid pixel wins a dot, even when it's behind the
+        // background and a higher numbered sprite in front would have shown
+        let sprites_shown = mask.contains(MaskFlags::SPRITE_ENABLE)
+            && (!left_edge || mask.contains(MaskFlags::LEFT_EDGE_SPRITE));
+        let sprite_pixel = if sprites_shown {
+            self.sprites.iter().find_map(|sprite| {
+                let column = x.wrapping_sub(sprite.x as usize);
+                if column >= 8 {
+                    return None;
                 }
-                // println!("PPU palette table: {:?}", self.palette_table);
+                let bit = 7 - column;
+                let pixel =
+                    ((sprite.pattern_hi >> bit) & 1) << 1 | ((sprite.pattern_lo >> bit) & 1);
+                // colour 0 is see-through
+                (pixel != 0).then_some((pixel as u16, *sprite))
+            })
+        } else {
+            None
+        };
+
+        // sprite 0 hits where it overlaps the background with both showing, but never on the
+        // last column
+        if let Some((_, sprite)) = sprite_pixel {
+            if sprite.sprite_zero && bg_pixel != 0 && x != WIDTH - 1 {
+                self.regs.stat.insert(StatusFlags::SPRITE_0_HIT);
             }
+        }
 
-            if self.scanline >= 262 {
-                self.scanline = 0;
-                self.interrupt = None;
-                self.regs.stat.remove(StatusFlags::SPRITE_0_HIT);
-                self.regs.stat.remove(StatusFlags::VBLANK);
-                return true;
+        // sprite palettes are the last four in the table
+        match sprite_pixel {
+            Some((pixel, sprite)) if bg_pixel == 0 || sprite.attributes & 0b0010_0000 == 0 => {
+                0x3f10 | ((sprite.attributes & 0b11) as u16) << 2 | pixel
             }
+            _ if bg_pixel != 0 => 0x3f00 | bg_palette << 2 | bg_pixel,
+            _ => 0x3f00,
+        }
+    }
+
+    // nametables and attributes
+    fn vram_read(&self, addr: u16) -> u8 {
+        self.vram[self.mirror_vram_addr(addr) as usize]
+    }
+
+    // what rendering fetches from the pattern tables, logged as rendered
+    fn pattern_read(&mut self, addr: u16) -> u8 {
+        let Some(offset) = self.cart.chr_offset(addr) else {
+            return 0;
+        };
+        if let Some(cdl) = self.cart.cdl.as_mut() {
+            cdl.log_chr(offset, ChrFlags::RENDERED);
         }
-        return false;
+        self.cart.chr[offset]
     }
 
     pub fn write_to_ppu_addr(&mut self, val: u8) {
-        self.regs.ppu_addr.write(val);
-        self.regs.scrl.toggle_latch();
+        self.regs.write_addr(val);
     }
 
     pub fn write_to_ctrl(&mut self, val: u8) {
         let nmi_status_before = self.regs.ctrl.contains(ControlFlags::VBLANK_NMI_ENABLE);
-        self.regs.ctrl = ControlFlags::from_bits_truncate(val);
+        self.regs.write_ctrl(val);
         let nmi_status_after = self.regs.ctrl.contains(ControlFlags::VBLANK_NMI_ENABLE);
         if !nmi_status_before && nmi_status_after && self.regs.stat.contains(StatusFlags::VBLANK) {
             self.interrupt = Some(InterruptType::NonMaskable);
@@ -170,47 +477,34 @@ impl PPU {
     }
 
     pub fn write_to_oam_data(&mut self, val: u8) {
+        // sprite evaluation owns oam while rendering, the write is lost and only bumps the
+        // sprite number part of the address
+        if self.rendering_in_progress() {
+            self.regs.oam_addr = self.regs.oam_addr.wrapping_add(4);
+            return;
+        }
         self.oam_data[self.regs.oam_addr as usize] = val;
         self.regs.oam_addr = self.regs.oam_addr.wrapping_add(1);
-        dbg!("warning: writing to PPU oam_data; this shouldn't really happen");
     }
 
     pub fn write_to_scrl(&mut self, val: u8) {
-        self.regs.scrl.write(val);
-        self.regs.ppu_addr.toggle_latch();
+        self.regs.write_scroll(val);
     }
 
     pub fn write_to_data(&mut self, val: u8) {
-        let addr = self.regs.ppu_addr.get_addr();
+        let addr = self.regs.vram_addr();
         self.increment_vram_addr();
 
         match addr {
             // TODO make these into constants?
-            0..=0x1fff => {
-                panic!("attempting to write to chr rom at 0x{:x}", addr);
-            }
+            0..=0x1fff => self.cart.write_chr(addr, val),
 
-            0x2000..=0x2fff => {
+            // $3000-$3EFF mirrors the nametables
+            0x2000..=0x3eff => {
                 self.vram[self.mirror_vram_addr(addr) as usize] = val;
             }
 
-            0x3000..=0x3eff => {
-                panic!(
-                    "attempting to write to unused ppu memory (0x3000..0x3eff) 0x{:x}",
-                    addr
-                )
-            }
-
-            //Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
-            0x3f10 | 0x3f14 | 0x3f18 | 0x3f1c => {
-                let addr_mirror = addr - 0x10;
-                self.palette_table[(addr_mirror - 0x3f00) as usize] = val;
-            }
-
-            0x3f00..=0x3fff => {
-                self.palette_table[(addr - 0x3f00) as usize] = val;
-            }
-            _ => panic!("bad write to ppu data at 0x{:x}", addr),
+            _ => self.palette_table[palette_index(addr)] = val,
         }
     }
 
@@ -221,8 +515,20 @@ impl PPU {
         }
     }
 
+    // no side effects, reading $2004 doesn't move the address
     pub fn read_oam_data(&self) -> u8 {
-        self.regs.oam_data
+        // evaluation starts each line by filling secondary oam with $FF, which is what the
+        // bus sees meanwhile
+        if self.rendering_in_progress() && (1..=64).contains(&self.cycle_count) {
+            return 0xff;
+        }
+        let addr = self.regs.oam_addr as usize;
+        // bits 2-4 of the attribute byte don't exist
+        if addr & 0b11 == 2 {
+            self.oam_data[addr] & 0b1110_0011
+        } else {
+            self.oam_data[addr]
+        }
     }
 
     pub fn read_status(&mut self) -> u8 {
@@ -231,49 +537,42 @@ impl PPU {
         //     println!("STATUS IS NEGATIVE!!!\n\n\n");
         // }
         self.regs.stat.remove(StatusFlags::VBLANK);
-        self.regs.ppu_addr.reset_latch();
-        self.regs.scrl.reset_latch();
+        self.regs.reset_latch();
         data
     }
 
     pub fn read_data(&mut self) -> u8 {
-        let addr = self.regs.ppu_addr.get_addr();
+        let addr = self.regs.vram_addr();
         self.increment_vram_addr();
 
         match addr {
             // TODO make these into constants?
             0..=0x1fff => {
                 let result = self.internal_data_buf;
-                self.internal_data_buf = self.cart.chr_rom[addr as usize];
+                if let Some(offset) = self.cart.chr_offset(addr) {
+                    self.internal_data_buf = self.cart.chr[offset];
+                    if let Some(cdl) = self.cart.cdl.as_mut() {
+                        cdl.log_chr(offset, ChrFlags::READ);
+                    }
+                }
                 result
             }
-            0x2000..=0x2fff => {
+            0x2000..=0x3eff => {
                 let result = self.internal_data_buf;
                 self.internal_data_buf = self.vram[self.mirror_vram_addr(addr) as usize];
                 result
             }
-            0x3000..=0x3eff => panic!(
-                "addr space 0x3000..0x3eff is not expected to be used, requested = {} ",
-                addr
-            ),
-
-            //Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
-            0x3f10 | 0x3f14 | 0x3f18 | 0x3f1c => {
-                let addr_mirror = addr - 0x10;
-                self.palette_table[(addr_mirror - 0x3f00) as usize]
-            }
 
-            0x3f00..=0x3fff => self.palette_table[(addr - 0x3f00) as usize],
-            _ => panic!("unexpected access to mirrored space {}", addr),
+            _ => self.palette_table[palette_index(addr)],
         }
     }
 
     pub fn mirror_vram_addr(&self, addr: u16) -> u16 {
         // code from: https://github.com/bugzmanov/nes_ebook/blob/master/code/ch6.1/src/ppu/mod.rs
 
-        // Horizontal:      Vertical:
-        //   [ A ] [ a ]      [ A ] [ B ]
-        //   [ B ] [ b ]      [ a ] [ b ]
+        // Horizontal:      Vertical:        FourScreen:
+        //   [ A ] [ a ]      [ A ] [ B ]      [ A ] [ B ]
+        //   [ B ] [ b ]      [ a ] [ b ]      [ C ] [ D ]
         let mirrored_vram = addr & 0b10111111111111; // mirror down 0x3000-0x3eff to 0x2000 - 0x2eff
         let vram_index = mirrored_vram - 0x2000; // to vram vector
         let name_table = vram_index / 0x400;
@@ -285,10 +584,25 @@ impl PPU {
         }
     }
 
+    // while rendering the access glitches v the way the renderer moves it, a step right and down
     fn increment_vram_addr(&mut self) {
-        self.regs
-            .ppu_addr
-            .increment(self.regs.ctrl.get_increment_val());
+        if self.rendering_in_progress() {
+            self.regs.increment_x();
+            self.regs.increment_y();
+        } else {
+            self.regs.increment_vram_addr();
+        }
+    }
+}
+
+// $3F00-$3FFF repeats the 32 palette bytes, and $3F10/$3F14/$3F18/$3F1C are mirrors of
+// $3F00/$3F04/$3F08/$3F0C
+fn palette_index(addr: u16) -> usize {
+    let index = (addr & 0x1f) as usize;
+    if index >= 0x10 && index & 0b11 == 0 {
+        index - 0x10
+    } else {
+        index
     }
 }
 
@@ -1,20 +1,41 @@
 use bitflags::bitflags;
 
-use crate::make_u16;
-
+// the scroll and vram address registers are loopy's (the names everyone uses, from the nesdev
+// "skinny on nes scrolling" doc). v and t are laid out as
+//   yyy NN YYYYY XXXXX
+//   fine y, nametable, coarse y, coarse x
 #[derive(Debug)]
 pub struct Registers {
     pub ctrl: ControlFlags,
     pub mask: MaskFlags,
     pub stat: StatusFlags,
     pub oam_addr: u8,
-    pub oam_data: u8,
-    pub scrl: PPUScrollRegister,
-    pub ppu_addr: PPUAddressRegister,
     pub ppu_data: u8,
     pub oam_dma: u8,
+
+    // current vram address, which is also where rendering is up to
+    pub v: u16,
+    // temporary vram address, the top left of the screen as set through $2000/$2005/$2006
+    pub t: u16,
+    // fine x scroll
+    pub x: u8,
+    // first/second write toggle shared by $2005 and $2006
+    pub w: bool,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+const COARSE_X: u16 = 0x001f;
+const COARSE_Y: u16 = 0x03e0;
+const NAMETABLE_X: u16 = 0x0400;
+const NAMETABLE_Y: u16 = 0x0800;
+const NAMETABLE: u16 = NAMETABLE_X | NAMETABLE_Y;
+const FINE_Y: u16 = 0x7000;
+
 impl Registers {
     pub fn new() -> Self {
         Self {
@@ -22,101 +43,118 @@ impl Registers {
             mask: MaskFlags::empty(),
             stat: StatusFlags::empty(),
             oam_addr: 0,
-            oam_data: 0,
-            scrl: PPUScrollRegister::new(),
-            ppu_addr: PPUAddressRegister::new(),
             ppu_data: 0,
             oam_dma: 0,
+            v: 0,
+            t: 0,
+            x: 0,
+            w: false,
         }
     }
-}
-
-#[derive(Debug)]
-pub struct PPUAddressRegister {
-    value: (u8, u8),
-    write_to_hi_ptr: bool,
-}
 
-impl PPUAddressRegister {
-    pub fn new() -> Self {
-        PPUAddressRegister {
-            value: (0, 0), // high byte first, lo byte second
-            write_to_hi_ptr: true,
-        }
-    }
-    fn set_addr(&mut self, data: u16) {
-        self.value.0 = (data >> 8) as u8;
-        self.value.1 = (data & 0xff) as u8;
+    // $2000, the nametable bits go to t
+    pub fn write_ctrl(&mut self, val: u8) {
+        self.ctrl = ControlFlags::from_bits_truncate(val);
+        self.t = (self.t & !NAMETABLE) | (((val & 0b11) as u16) << 10);
     }
 
-    pub fn write(&mut self, data: u8) {
-        if self.write_to_hi_ptr {
-            self.value.0 = data;
+    // $2005, x scroll then y scroll
+    pub fn write_scroll(&mut self, val: u8) {
+        if !self.w {
+            self.t = (self.t & !COARSE_X) | (val >> 3) as u16;
+            self.x = val & 0b111;
         } else {
-            self.value.1 = data;
+            self.t = (self.t & !(COARSE_Y | FINE_Y))
+                | (((val & 0b111) as u16) << 12)
+                | (((val >> 3) as u16) << 5);
         }
-
-        if self.get_addr() > 0x3fff {
-            self.set_addr(self.get_addr() & 0x3fff);
-        }
-        self.toggle_latch();
+        self.w = !self.w;
     }
 
-    pub fn increment(&mut self, inc: u8) {
-        let lo = self.value.1;
-        self.value.1 = self.value.1.wrapping_add(inc);
-        if lo > self.value.1 {
-            self.value.0 = self.value.0.wrapping_add(1);
-        }
-        if self.get_addr() > 0x3fff {
-            self.set_addr(self.get_addr() & 0x3fff);
+    // $2006, high byte then low byte. v only changes on the second write
+    pub fn write_addr(&mut self, val: u8) {
+        if !self.w {
+            self.t = (self.t & 0x00ff) | (((val & 0x3f) as u16) << 8);
+        } else {
+            self.t = (self.t & 0xff00) | val as u16;
+            self.v = self.t;
         }
+        self.w = !self.w;
     }
 
+    // reading $2002 resets the toggle
     pub fn reset_latch(&mut self) {
-        self.write_to_hi_ptr = true;
+        self.w = false;
     }
 
-    pub fn toggle_latch(&mut self) {
-        self.write_to_hi_ptr = !self.write_to_hi_ptr;
+    // the vram address $2007 reads and writes go to
+    pub fn vram_addr(&self) -> u16 {
+        self.v & 0x3fff
     }
 
-    pub fn get_addr(&self) -> u16 {
-        make_u16!(self.value.0, self.value.1)
+    // after a $2007 access outside rendering
+    pub fn increment_vram_addr(&mut self) {
+        self.v = self.v.wrapping_add(self.ctrl.get_increment_val() as u16) & 0x7fff;
     }
-}
 
-#[derive(Debug)]
-pub struct PPUScrollRegister {
-    x_scroll: u8,
-    y_scroll: u8,
-    write_to_x: bool,
-}
-
-impl PPUScrollRegister {
-    pub fn new() -> Self {
-        Self {
-            x_scroll: 0,
-            y_scroll: 0,
-            write_to_x: true,
+    // one tile right, into the next nametable across at the end of a row
+    pub fn increment_x(&mut self) {
+        if self.v & COARSE_X == 31 {
+            self.v &= !COARSE_X;
+            self.v ^= NAMETABLE_X;
+        } else {
+            self.v += 1;
         }
     }
 
-    pub fn write(&mut self, val: u8) {
-        if self.write_to_x {
-            self.x_scroll = val;
-        } else {
-            self.y_scroll = val;
+    // one pixel down, into the next nametable down after row 29. rows 30 and 31 are the
+    // attribute table, scrolling there wraps without switching nametables
+    pub fn increment_y(&mut self) {
+        if self.v & FINE_Y != FINE_Y {
+            self.v += 0x1000;
+            return;
         }
-        self.toggle_latch();
+        self.v &= !FINE_Y;
+        let coarse_y = match (self.v & COARSE_Y) >> 5 {
+            29 => {
+                self.v ^= NAMETABLE_Y;
+                0
+            }
+            31 => 0,
+            y => y + 1,
+        };
+        self.v = (self.v & !COARSE_Y) | (coarse_y << 5);
     }
 
-    pub fn reset_latch(&mut self) {
-        self.write_to_x = true;
+    // start of a scanline, x goes back to the left edge from t
+    pub fn copy_horizontal(&mut self) {
+        let mask = COARSE_X | NAMETABLE_X;
+        self.v = (self.v & !mask) | (self.t & mask);
     }
 
-    pub fn toggle_latch(&mut self) {
-        self.write_to_x = !self.write_to_x;
+    // start of a frame, y goes back to the top from t
+    pub fn copy_vertical(&mut self) {
+        let mask = FINE_Y | NAMETABLE_Y | COARSE_Y;
+        self.v = (self.v & !mask) | (self.t & mask);
+    }
+
+    // nametable byte for the tile at v
+    pub fn tile_addr(&self) -> u16 {
+        0x2000 | (self.v & 0x0fff)
+    }
+
+    // attribute byte covering the tile at v
+    pub fn attribute_addr(&self) -> u16 {
+        0x23c0 | (self.v & NAMETABLE) | ((self.v >> 4) & 0b111000) | ((self.v >> 2) & 0b111)
+    }
+
+    // which 2 bits of the attribute byte belong to the tile at v
+    pub fn attribute_shift(&self) -> u16 {
+        ((self.v >> 4) & 0b100) | (self.v & 0b10)
+    }
+
+    pub fn fine_y(&self) -> u16 {
+        (self.v & FINE_Y) >> 12
     }
 }
 
@@ -145,7 +183,7 @@ impl ControlFlags {
 }
 
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy)]
     pub struct MaskFlags: u8 {
         const GREYSCALE_ENABLE = 0b00000001;
         const LEFT_EDGE_BACKGROUND = 0b00000010;
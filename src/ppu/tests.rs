@@ -0,0 +1,567 @@
+use super::{Mirroring, PPU};
+use crate::{
+    asm::Program,
+    memory::cartridge::{nrom_image, Cartridge, CHR_ROM_PAGE_SIZE},
+    ppu::ppu_registers::{ControlFlags, MaskFlags, StatusFlags},
+    HEIGHT, WIDTH,
+};
+
+// tile 1 of each pattern table is colour 1 down its left column, tile 2 is solid colour 3
+fn test_ppu() -> PPU {
+    let mut chr = vec![0; CHR_ROM_PAGE_SIZE];
+    for bank in [0, 0x1000] {
+        for row in 0..8 {
+            chr[bank + 16 + row] = 0b1000_0000;
+            chr[bank + 32 + row] = 0xff;
+            chr[bank + 32 + row + 8] = 0xff;
+        }
+    }
+    let cart = Cartridge {
+        prg_rom: vec![],
+        chr,
+        chr_ram: false,
+        mapper: 0,
+        screen_mirroring: Mirroring::Horizontal,
+        cdl: None,
+    };
+
+    let mut ppu = PPU::new(cart);
+    ppu.palette_table[0] = 0x0f;
+    // background palette 0 and sprite palettes 0 and 1
+    ppu.palette_table[1..4].copy_from_slice(&[0x01, 0x02, 0x03]);
+    ppu.palette_table[0x11..0x14].copy_from_slice(&[0x11, 0x12, 0x13]);
+    ppu.palette_table[0x15..0x18].copy_from_slice(&[0x21, 0x22, 0x23]);
+    // no sprites, oam y of $FF is below the screen
+    ppu.oam_data = [0xff; 256];
+    ppu
+}
+
+fn set_sprite(ppu: &mut PPU, index: usize, x: u8, y: u8, tile: u8, attributes: u8) {
+    ppu.oam_data[index * 4..index * 4 + 4].copy_from_slice(&[y, tile, attributes, x]);
+}
+
+// runs the ppu with rendering on until it has drawn a whole frame
+fn draw(ppu: &mut PPU) -> Vec<u16> {
+    ppu.regs.mask = MaskFlags::BACKGROUND_ENABLE
+        | MaskFlags::SPRITE_ENABLE
+        | MaskFlags::LEFT_EDGE_BACKGROUND
+        | MaskFlags::LEFT_EDGE_SPRITE;
+    for _ in 0..2 {
+        while !ppu.tick(1) {}
+    }
+
+    let mut frame = [0; WIDTH * HEIGHT];
+    ppu.draw_to_buffer(&mut frame);
+    frame.to_vec()
+}
+
+fn pixel(frame: &[u16], x: usize, y: usize) -> u16 {
+    frame[x + WIDTH * y]
+}
+
+#[test]
+fn test_sprite_8x8() {
+    let mut ppu = test_ppu();
+    // sprites show a line below their oam y
+    set_sprite(&mut ppu, 0, 10, 19, 1, 0b01);
+    set_sprite(&mut ppu, 1, 30, 19, 1, 0b0100_0000);
+
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 10, 19), 0x0f);
+    assert_eq!(pixel(&frame, 10, 20), 0x21);
+    assert_eq!(pixel(&frame, 10, 27), 0x21);
+    assert_eq!(pixel(&frame, 10, 28), 0x0f);
+    // colour 0 is see-through
+    assert_eq!(pixel(&frame, 11, 20), 0x0f);
+
+    // flipped horizontally, the column moves to the right
+    assert_eq!(pixel(&frame, 30, 20), 0x0f);
+    assert_eq!(pixel(&frame, 37, 20), 0x11);
+
+    // from the other pattern table
+    ppu.regs.ctrl = ControlFlags::SPRITE_PATTERN_TABLE_ADDR;
+    set_sprite(&mut ppu, 0, 10, 19, 2, 0);
+    ppu.cart.chr[0x1000 + 32] = 0;
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 10, 20), 0x12);
+    assert_eq!(pixel(&frame, 10, 21), 0x13);
+}
+
+#[test]
+fn test_sprite_8x16() {
+    let mut ppu = test_ppu();
+    ppu.regs.ctrl = ControlFlags::SPRITE_SIZE;
+    // tiles 2 and 3 from $1000, the bottom half is tile 3 which is empty
+    set_sprite(&mut ppu, 0, 0, 0, 0b11, 0);
+    // tiles 0 and 1 from $0000 flipped vertically, tile 1 ends up on top
+    set_sprite(&mut ppu, 1, 20, 0, 0, 0b1000_0000);
+
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 0, 1), 0x13);
+    assert_eq!(pixel(&frame, 0, 8), 0x13);
+    assert_eq!(pixel(&frame, 0, 9), 0x0f);
+    assert_eq!(pixel(&frame, 20, 1), 0x11);
+    assert_eq!(pixel(&frame, 20, 8), 0x11);
+    assert_eq!(pixel(&frame, 20, 9), 0x0f);
+    assert_eq!(pixel(&frame, 20, 16), 0x0f);
+}
+
+#[test]
+fn test_sprite_priority() {
+    let mut ppu = test_ppu();
+    // the two top left background tiles are solid, everything else is tile 0 and see-through
+    ppu.vram[0] = 2;
+    ppu.vram[1] = 2;
+
+    // in front of the background
+    set_sprite(&mut ppu, 0, 0, 0, 2, 0);
+    // behind it, which still hides sprite 2 where they overlap
+    set_sprite(&mut ppu, 1, 8, 0, 2, 0b0010_0001);
+    set_sprite(&mut ppu, 2, 12, 0, 2, 0);
+    // behind a see-through background
+    set_sprite(&mut ppu, 3, 24, 0, 2, 0b0010_0001);
+
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 7, 1), 0x13);
+    assert_eq!(pixel(&frame, 9, 1), 0x03);
+    assert_eq!(pixel(&frame, 13, 1), 0x03);
+    assert_eq!(pixel(&frame, 16, 1), 0x13);
+    assert_eq!(pixel(&frame, 24, 1), 0x23);
+}
+
+#[test]
+fn test_sprites_per_scanline() {
+    let mut ppu = test_ppu();
+    for i in 0..9 {
+        set_sprite(&mut ppu, i, i as u8 * 10, 0, 2, 0);
+    }
+    // the ninth sprite on a line is dropped, until the ones before it have ended
+    set_sprite(&mut ppu, 0, 0, 0x40, 2, 0);
+
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 70, 1), 0x13);
+    assert_eq!(pixel(&frame, 80, 1), 0x13);
+
+    set_sprite(&mut ppu, 0, 0, 0, 2, 0);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 70, 1), 0x13);
+    assert_eq!(pixel(&frame, 80, 1), 0x0f);
+    assert_eq!(pixel(&frame, 0, 1), 0x13);
+}
+
+#[test]
+fn test_loopy_registers() {
+    let mut ppu = test_ppu();
+    ppu.write_to_ctrl(0b10);
+    assert_eq!(ppu.regs.t, 0x0800);
+
+    ppu.read_status();
+    ppu.write_to_scrl(0x7d);
+    assert_eq!((ppu.regs.t, ppu.regs.x, ppu.regs.w), (0x080f, 5, true));
+    ppu.write_to_scrl(0x5e);
+    assert_eq!((ppu.regs.t, ppu.regs.w), (0x696f, false));
+
+    // v only picks up t on the second $2006 write
+    ppu.write_to_ppu_addr(0x3d);
+    assert_eq!((ppu.regs.t, ppu.regs.v), (0x3d6f, 0));
+    ppu.write_to_ppu_addr(0xf0);
+    assert_eq!((ppu.regs.t, ppu.regs.v), (0x3df0, 0x3df0));
+
+    // $2005 and $2006 share the toggle, and reading $2002 resets it
+    ppu.write_to_scrl(0);
+    ppu.read_status();
+    ppu.write_to_ppu_addr(0x21);
+    ppu.write_to_ppu_addr(0x08);
+    assert_eq!(ppu.regs.v, 0x2108);
+}
+
+#[test]
+fn test_fine_x_scroll() {
+    let mut ppu = test_ppu();
+    // tile 1's left column, in the second tile of the row
+    ppu.vram[1] = 1;
+
+    // 3 pixels of fine x move it from x 8 to x 5
+    ppu.write_to_scrl(3);
+    ppu.write_to_scrl(0);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 4, 0), 0x0f);
+    assert_eq!(pixel(&frame, 5, 0), 0x01);
+    assert_eq!(pixel(&frame, 6, 0), 0x0f);
+}
+
+#[test]
+fn test_mid_frame_palette_change() {
+    let mut ppu = test_ppu();
+    draw(&mut ppu);
+
+    // the backdrop changes half way down
+    ppu.tick(341 * 120);
+    ppu.palette_table[0] = 0x20;
+    while !ppu.tick(1) {}
+
+    let mut frame = [0; WIDTH * HEIGHT];
+    ppu.draw_to_buffer(&mut frame);
+    assert_eq!(pixel(&frame, 0, 119), 0x0f);
+    assert_eq!(pixel(&frame, 255, 119), 0x0f);
+    assert_eq!(pixel(&frame, 0, 120), 0x20);
+    assert_eq!(pixel(&frame, 255, 239), 0x20);
+}
+
+// solid tiles in the nametable at `vram_offset`, the rest of vram is see-through tile 0
+fn fill_nametable(ppu: &mut PPU, vram_offset: usize) {
+    ppu.vram[vram_offset..vram_offset + 960].fill(2);
+}
+
+#[test]
+fn test_horizontal_scroll() {
+    let mut ppu = test_ppu();
+    ppu.cart.screen_mirroring = Mirroring::Vertical;
+    fill_nametable(&mut ppu, 0x400);
+
+    // half a screen and 4 pixels across, into the nametable on the right
+    ppu.write_to_scrl(132);
+    ppu.write_to_scrl(0);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 123, 0), 0x0f);
+    assert_eq!(pixel(&frame, 124, 0), 0x03);
+    assert_eq!(pixel(&frame, 255, 239), 0x03);
+
+    // starting from the right one, it wraps back round to the left
+    ppu.write_to_ctrl(0b01);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 123, 0), 0x03);
+    assert_eq!(pixel(&frame, 124, 0), 0x0f);
+}
+
+#[test]
+fn test_vertical_scroll() {
+    let mut ppu = test_ppu();
+    ppu.cart.screen_mirroring = Mirroring::Horizontal;
+    fill_nametable(&mut ppu, 0x400);
+
+    // nametables are 30 tiles high, the one below starts 120 lines down
+    ppu.write_to_scrl(0);
+    ppu.write_to_scrl(120);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 0, 119), 0x0f);
+    assert_eq!(pixel(&frame, 0, 120), 0x03);
+
+    ppu.write_to_ctrl(0b10);
+    ppu.write_to_scrl(0);
+    ppu.write_to_scrl(0);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 0, 0), 0x03);
+    assert_eq!(pixel(&frame, 255, 239), 0x03);
+}
+
+#[test]
+fn test_split_scroll() {
+    let mut ppu = test_ppu();
+    ppu.cart.screen_mirroring = Mirroring::Vertical;
+    fill_nametable(&mut ppu, 0x400);
+    draw(&mut ppu);
+
+    // a status bar split, x scroll changes from the next line on
+    ppu.tick(341 * 100);
+    ppu.write_to_scrl(128);
+    ppu.write_to_scrl(0);
+    while !ppu.tick(1) {}
+
+    let mut frame = [0; WIDTH * HEIGHT];
+    ppu.draw_to_buffer(&mut frame);
+    assert_eq!(pixel(&frame, 200, 100), 0x0f);
+    assert_eq!(pixel(&frame, 100, 101), 0x0f);
+    assert_eq!(pixel(&frame, 200, 101), 0x03);
+}
+
+#[test]
+fn test_four_screen() {
+    let mut ppu = test_ppu();
+    ppu.cart.screen_mirroring = Mirroring::FourScreen;
+    fill_nametable(&mut ppu, 0xc00);
+
+    ppu.write_to_ctrl(0b11);
+    assert_eq!(pixel(&draw(&mut ppu), 0, 0), 0x03);
+    ppu.write_to_ctrl(0b00);
+    assert_eq!(pixel(&draw(&mut ppu), 0, 0), 0x0f);
+}
+
+fn status(ppu: &PPU, flag: StatusFlags) -> bool {
+    ppu.regs.stat.contains(flag)
+}
+
+#[test]
+fn test_sprite_zero_hit() {
+    let mut ppu = test_ppu();
+    fill_nametable(&mut ppu, 0);
+    set_sprite(&mut ppu, 0, 100, 49, 2, 0);
+    draw(&mut ppu);
+    assert!(!status(&ppu, StatusFlags::SPRITE_0_HIT));
+
+    // the first overlapping pixel is x 100 on line 50, drawn on dot 101
+    ppu.tick(341 * 50 + 101);
+    assert!(!status(&ppu, StatusFlags::SPRITE_0_HIT));
+    ppu.tick(1);
+    assert!(status(&ppu, StatusFlags::SPRITE_0_HIT));
+
+    // it stays set until the pre-render line
+    ppu.tick(341 * (260 - 50));
+    assert!(status(&ppu, StatusFlags::SPRITE_0_HIT));
+    ppu.tick(341);
+    assert!(!status(&ppu, StatusFlags::SPRITE_0_HIT));
+}
+
+#[test]
+fn test_sprite_zero_hit_exceptions() {
+    let hit = |x: u8, tile: u8, attributes: u8, mask: MaskFlags| {
+        let mut ppu = test_ppu();
+        fill_nametable(&mut ppu, 0);
+        set_sprite(&mut ppu, 0, x, 49, tile, attributes);
+        draw(&mut ppu);
+        ppu.regs.mask = mask;
+        ppu.tick(341 * 100);
+        status(&ppu, StatusFlags::SPRITE_0_HIT)
+    };
+    let showing = MaskFlags::BACKGROUND_ENABLE | MaskFlags::SPRITE_ENABLE;
+    let left_edge = MaskFlags::LEFT_EDGE_BACKGROUND | MaskFlags::LEFT_EDGE_SPRITE;
+
+    // behind the background still counts
+    assert!(hit(100, 2, 0b0010_0000, showing));
+    // only in the left column, which is clipped unless both edges are shown
+    assert!(!hit(0, 1, 0, showing));
+    assert!(!hit(0, 1, 0, showing | MaskFlags::LEFT_EDGE_SPRITE));
+    assert!(hit(0, 1, 0, showing | left_edge));
+    // only on x 255, flipped so the column is on the right
+    assert!(!hit(248, 1, 0b0100_0000, showing | left_edge));
+    assert!(hit(247, 1, 0b0100_0000, showing | left_edge));
+    // not with the background hidden
+    assert!(!hit(100, 2, 0, MaskFlags::SPRITE_ENABLE | left_edge));
+}
+
+#[test]
+fn test_sprite_overflow() {
+    let overflow = |ppu: &mut PPU| {
+        draw(ppu);
+        ppu.tick(341 * 241);
+        status(ppu, StatusFlags::SPRITE_OVERFLOW)
+    };
+
+    // 8 sprites on lines 1-8 don't overflow, a ninth does
+    let mut ppu = test_ppu();
+    for i in 0..8 {
+        set_sprite(&mut ppu, i, 0, 0, 0xff, 0xff);
+    }
+    assert!(!overflow(&mut ppu));
+    set_sprite(&mut ppu, 20, 0, 0, 0xff, 0xff);
+    assert!(overflow(&mut ppu));
+    // cleared on the pre-render line
+    ppu.tick(341 * 21);
+    assert!(!status(&ppu, StatusFlags::SPRITE_OVERFLOW));
+
+    // after the eighth, evaluation looks at sprite 9's tile number, sprite 10's attributes..
+    // so a ninth sprite on the line can be missed
+    let mut ppu = test_ppu();
+    for i in 0..8 {
+        set_sprite(&mut ppu, i, 0, 0, 0xff, 0xff);
+    }
+    set_sprite(&mut ppu, 9, 0xff, 0, 0xff, 0xff);
+    assert!(!overflow(&mut ppu));
+
+    // ..or one seen where there's only eight
+    set_sprite(&mut ppu, 9, 0xff, 0xff, 0xff, 0xff);
+    set_sprite(&mut ppu, 10, 0xff, 0xff, 0xff, 0);
+    assert!(overflow(&mut ppu));
+}
+
+// a frame drawn with only the given PPUMASK bits
+fn draw_masked(ppu: &mut PPU, mask: MaskFlags) -> Vec<u16> {
+    draw(ppu);
+    ppu.regs.mask = mask;
+    while !ppu.tick(1) {}
+
+    let mut frame = [0; WIDTH * HEIGHT];
+    ppu.draw_to_buffer(&mut frame);
+    frame.to_vec()
+}
+
+#[test]
+fn test_mask_layers() {
+    let mut ppu = test_ppu();
+    ppu.vram[0] = 2;
+    set_sprite(&mut ppu, 0, 100, 0, 2, 0);
+    let left_edge = MaskFlags::LEFT_EDGE_BACKGROUND | MaskFlags::LEFT_EDGE_SPRITE;
+
+    let frame = draw_masked(&mut ppu, MaskFlags::BACKGROUND_ENABLE | left_edge);
+    assert_eq!(pixel(&frame, 0, 1), 0x03);
+    assert_eq!(pixel(&frame, 100, 1), 0x0f);
+
+    let frame = draw_masked(&mut ppu, MaskFlags::SPRITE_ENABLE | left_edge);
+    assert_eq!(pixel(&frame, 0, 1), 0x0f);
+    assert_eq!(pixel(&frame, 100, 1), 0x13);
+}
+
+#[test]
+fn test_left_edge_clipping() {
+    let mut ppu = test_ppu();
+    ppu.vram[1] = 2;
+    set_sprite(&mut ppu, 0, 4, 0, 2, 0);
+    let showing = MaskFlags::BACKGROUND_ENABLE | MaskFlags::SPRITE_ENABLE;
+
+    let frame = draw_masked(&mut ppu, showing);
+    assert_eq!(pixel(&frame, 7, 1), 0x0f);
+    assert_eq!(pixel(&frame, 8, 1), 0x13);
+
+    // the background goes all the way left, the sprite's still cut off so the tile shows
+    ppu.vram[0] = 2;
+    let frame = draw_masked(&mut ppu, showing | MaskFlags::LEFT_EDGE_BACKGROUND);
+    assert_eq!(pixel(&frame, 7, 1), 0x03);
+    ppu.vram[0] = 0;
+
+    let frame = draw_masked(&mut ppu, showing | MaskFlags::LEFT_EDGE_SPRITE);
+    assert_eq!(pixel(&frame, 4, 1), 0x13);
+    assert_eq!(pixel(&frame, 3, 1), 0x0f);
+}
+
+#[test]
+fn test_greyscale_and_emphasis() {
+    let mut ppu = test_ppu();
+    ppu.palette_table[0] = 0x16;
+    let showing = MaskFlags::BACKGROUND_ENABLE | MaskFlags::SPRITE_ENABLE;
+
+    let frame = draw_masked(&mut ppu, showing | MaskFlags::GREYSCALE_ENABLE);
+    assert_eq!(pixel(&frame, 0, 0), 0x10);
+
+    // emphasis goes above the colour, red is bit 6
+    let frame = draw_masked(&mut ppu, showing | MaskFlags::EMPHASIZE_RED);
+    assert_eq!(pixel(&frame, 0, 0), 0x40 | 0x16);
+    let frame = draw_masked(
+        &mut ppu,
+        showing | MaskFlags::EMPHASIZE_GREEN | MaskFlags::EMPHASIZE_BLUE,
+    );
+    assert_eq!(pixel(&frame, 0, 0), 0x180 | 0x16);
+}
+
+#[test]
+fn test_rendering_off() {
+    let mut ppu = test_ppu();
+    fill_nametable(&mut ppu, 0);
+    set_sprite(&mut ppu, 0, 100, 0, 2, 0);
+
+    // just the backdrop, and v stays where the cpu left it
+    ppu.regs.v = 0x2345;
+    for _ in 0..2 {
+        while !ppu.tick(1) {}
+    }
+    let mut frame = [0; WIDTH * HEIGHT];
+    ppu.draw_to_buffer(&mut frame);
+    assert!(frame.iter().all(|&color| color == 0x0f));
+    assert_eq!(ppu.regs.v, 0x2345);
+
+    // unless v points into the palette
+    ppu.regs.v = 0x3f03;
+    while !ppu.tick(1) {}
+    ppu.draw_to_buffer(&mut frame);
+    assert!(frame.iter().all(|&color| color == 0x03));
+}
+
+#[test]
+fn test_oam_data_register() {
+    let mut ppu = test_ppu();
+    ppu.write_to_oam_addr(0x10);
+    for val in [0x20, 0x30, 0xff, 0x40] {
+        ppu.write_to_oam_data(val);
+    }
+    assert_eq!(ppu.regs.oam_addr, 0x14);
+    assert_eq!(ppu.oam_data[0x10..0x14], [0x20, 0x30, 0xff, 0x40]);
+
+    // reads don't move the address
+    ppu.write_to_oam_addr(0x12);
+    assert_eq!(ppu.read_oam_data(), 0xe3);
+    assert_eq!(ppu.read_oam_data(), 0xe3);
+    ppu.write_to_oam_addr(0x13);
+    assert_eq!(ppu.read_oam_data(), 0x40);
+
+    // while rendering: secondary oam being cleared shows through, writes are dropped and
+    // bump the address a whole sprite
+    draw(&mut ppu);
+    ppu.tick(10);
+    ppu.write_to_oam_addr(0x13);
+    assert_eq!(ppu.read_oam_data(), 0xff);
+    ppu.write_to_oam_data(0x55);
+    assert_eq!(ppu.regs.oam_addr, 0x17);
+    // after the clear, oam itself
+    ppu.write_to_oam_addr(0x13);
+    ppu.tick(100);
+    assert_eq!(ppu.read_oam_data(), 0x40);
+
+    // the address is zeroed while sprite patterns are fetched
+    ppu.tick(150);
+    assert_eq!(ppu.regs.oam_addr, 0);
+}
+
+#[test]
+fn test_chr_ram() {
+    // no CHR banks
+    let cart = Cartridge::new(nrom_image(&Program::default(), &[])).unwrap();
+    assert!(cart.chr_ram);
+    assert_eq!(cart.chr.len(), CHR_ROM_PAGE_SIZE);
+
+    let mut ppu = PPU::new(cart);
+    ppu.palette_table[0] = 0x0f;
+    ppu.palette_table[1..4].copy_from_slice(&[0x01, 0x02, 0x03]);
+    ppu.oam_data = [0xff; 256];
+
+    // tile 1 made solid colour 1 through $2007
+    ppu.write_to_ppu_addr(0x00);
+    ppu.write_to_ppu_addr(0x10);
+    for _ in 0..8 {
+        ppu.write_to_data(0xff);
+    }
+    ppu.write_to_ppu_addr(0x20);
+    ppu.write_to_ppu_addr(0x00);
+    ppu.write_to_data(0x01);
+
+    // and read back, through the read buffer
+    ppu.write_to_ppu_addr(0x00);
+    ppu.write_to_ppu_addr(0x10);
+    ppu.read_data();
+    assert_eq!(ppu.read_data(), 0xff);
+
+    ppu.write_to_scrl(0);
+    ppu.write_to_scrl(0);
+    let frame = draw(&mut ppu);
+    assert_eq!(pixel(&frame, 0, 0), 0x01);
+    assert_eq!(pixel(&frame, 7, 7), 0x01);
+    assert_eq!(pixel(&frame, 8, 0), 0x0f);
+
+    // rom stays as it is
+    let mut ppu = test_ppu();
+    ppu.write_to_ppu_addr(0x00);
+    ppu.write_to_ppu_addr(0x10);
+    ppu.write_to_data(0xff);
+    assert_eq!(ppu.cart.chr[0x10], 0b1000_0000);
+}
+
+#[test]
+fn test_odd_frame_skipped_dot() {
+    let frame_length = |ppu: &mut PPU| {
+        let mut dots = 1;
+        while !ppu.tick(1) {
+            dots += 1;
+        }
+        dots
+    };
+
+    // rendering off, every frame is 262 full lines
+    let mut ppu = test_ppu();
+    assert_eq!(frame_length(&mut ppu), 341 * 262);
+    assert_eq!(frame_length(&mut ppu), 341 * 262);
+
+    // rendering on, every other frame is a dot short
+    ppu.regs.mask = MaskFlags::BACKGROUND_ENABLE;
+    assert_eq!(frame_length(&mut ppu), 341 * 262);
+    assert_eq!(frame_length(&mut ppu), 341 * 262 - 1);
+    assert_eq!(frame_length(&mut ppu), 341 * 262);
+    assert_eq!(frame_length(&mut ppu), 341 * 262 - 1);
+}
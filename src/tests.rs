@@ -0,0 +1,123 @@
+use crate::{
+    asm::Assembler,
+    error::EmulatorError,
+    memory::{
+        cartridge::{nrom_image, CHR_ROM_PAGE_SIZE},
+        memory_bus::Bus,
+    },
+    NESSystem,
+};
+
+// `src` assembled into an NROM image with blank CHR rom, just after reset
+fn test_system(src: &str) -> NESSystem {
+    let program = Assembler::new().assemble(src).unwrap();
+    NESSystem::new(nrom_image(&program, &[0; CHR_ROM_PAGE_SIZE])).unwrap()
+}
+
+#[test]
+fn test_jam() {
+    let mut system = test_system(
+        "
+        .org $C000
+reset:  NOP
+        JAM
+        .org $FFFA
+        .word reset, reset, reset
+",
+    );
+
+    let jammed = Err(EmulatorError::Jammed {
+        pc: 0xc001,
+        opcode: 0x02,
+    });
+    assert_eq!(system.tick_n(2), jammed);
+    assert!(system.cpu().jammed());
+    // and stays that way until reset
+    assert_eq!(system.tick_once(), jammed);
+
+    system.reset();
+    assert!(!system.cpu().jammed());
+    assert_eq!(system.tick_once(), Ok(()));
+    assert_eq!(system.cpu().program_counter, 0xc001);
+}
+
+#[test]
+fn test_oam_dma() {
+    // `pad` moves the $4014 write between odd and even cycles
+    let dma = |pad: &str| {
+        let mut system = test_system(&format!(
+            "
+        .org $C000
+reset:  {pad}
+        LDA #$02
+        STA $4014
+        NOP
+        .org $FFFA
+        .word reset, reset, reset
+"
+        ));
+        for i in 0..=255 {
+            system.cpu_mut().mem_bus.write(0x0200 + i, i as u8);
+        }
+
+        system.cpu_mut().enable_profiler();
+        system.tick_once().unwrap();
+        let before = system.cpu().cycle_count;
+        system.tick_n(2).unwrap();
+        let cpu = system.cpu();
+        let stall = cpu.cycle_count - before - 6;
+
+        // the profiler sees the stall too, on the STA
+        let profiler = cpu.profiler.as_ref().unwrap();
+        assert_eq!(profiler.total_cycles(), cpu.cycle_count as u64 - 7);
+        assert_eq!(
+            profiler.cycles_at(cpu.program_counter - 3),
+            4 + stall as u64
+        );
+        (system, stall)
+    };
+
+    // reset leaves the cpu on cycle 7, the STA write lands on cycle 14 or 15 and the
+    // instruction finishes on 15 or 16
+    let (_, stall) = dma("NOP");
+    assert_eq!(stall, 514);
+    let (mut system, stall) = dma("LDA $00");
+    assert_eq!(stall, 513);
+
+    // oam has the page, read back through $2003/$2004
+    let bus = &mut system.cpu_mut().mem_bus;
+    bus.write(0x2003, 0x05);
+    assert_eq!(bus.read(0x2004), 0x05);
+    // the attribute byte has no bits 2-4
+    bus.write(0x2003, 0x06);
+    assert_eq!(bus.read(0x2004), 0x06 & 0b1110_0011);
+    bus.write(0x2003, 0xfe);
+    assert_eq!(bus.read(0x2004), 0xe2);
+}
+
+#[test]
+fn test_frame_stepping() {
+    let mut system = test_system(
+        "
+        .org $C000
+reset:  JMP reset
+        .org $FFFA
+        .word reset, reset, reset
+",
+    );
+    assert_eq!(system.frame_count(), 0);
+
+    // stops on the instruction the frame ended in, a JMP is 9 dots
+    system.tick_one_frame().unwrap();
+    assert_eq!(system.frame_count(), 1);
+    assert_eq!(system.scanline(), 0);
+    assert!(system.dot() < 9);
+
+    system.run_until(|s| s.scanline() == 241).unwrap();
+    assert_eq!((system.frame_count(), system.scanline()), (1, 241));
+    assert!(system.dot() < 9);
+
+    system.tick_one_frame().unwrap();
+    system.tick_one_frame().unwrap();
+    assert_eq!((system.frame_count(), system.scanline()), (3, 0));
+}
@@ -7,7 +7,7 @@ pub mod pixels_renderer;
 
 pub trait Renderer {
     fn draw_to(&self, buf: &mut [u8]);
-    fn modify_buffer<T: FnOnce(&mut [u8; WIDTH * HEIGHT])>(&mut self, f: T);
+    fn modify_buffer<T: FnOnce(&mut [u16; WIDTH * HEIGHT])>(&mut self, f: T);
 }
 
 pub trait Controller {
@@ -16,6 +16,37 @@ pub trait Controller {
 
 type NESSystemPalette = [(u8, u8, u8); 64];
 
+// how much of a colour channel is left when emphasis is on for the other channels
+const EMPHASIS_ATTENUATION: f32 = 0.75;
+
+// the 64 colours for each of the 8 emphasis settings, indexed by what NESSystem::render gives.
+// emphasising a channel darkens the other two
+pub fn emphasized_palette(palette: &NESSystemPalette) -> Vec<(u8, u8, u8)> {
+    let mut out = Vec::with_capacity(512);
+    for emphasis in 0..8 {
+        let (red, green, blue) = (
+            emphasis & 0b001 != 0,
+            emphasis & 0b010 != 0,
+            emphasis & 0b100 != 0,
+        );
+        let scale = |others: bool, value: u8| {
+            if others {
+                (value as f32 * EMPHASIS_ATTENUATION) as u8
+            } else {
+                value
+            }
+        };
+        for &(r, g, b) in palette {
+            out.push((
+                scale(green || blue, r),
+                scale(red || blue, g),
+                scale(red || green, b),
+            ));
+        }
+    }
+    out
+}
+
 #[rustfmt::skip]
 pub static PALETTE_NTSC: [(u8,u8,u8); 64] = [
     (0x80, 0x80, 0x80), (0x00, 0x3D, 0xA6), (0x00, 0x12, 0xB0), (0x44, 0x00, 0x96),
@@ -1,17 +1,18 @@
 use crate::{HEIGHT, WIDTH};
 
-use super::{NESSystemPalette, Renderer};
+use super::{emphasized_palette, NESSystemPalette, Renderer};
 
 pub struct PixelsRenderer {
-    pub palette_buffer: [u8; WIDTH * HEIGHT],
-    palette: NESSystemPalette,
+    pub palette_buffer: [u16; WIDTH * HEIGHT],
+    // all 512 colours, see emphasized_palette
+    palette: Vec<(u8, u8, u8)>,
 }
 
 impl PixelsRenderer {
     pub fn new(palette: NESSystemPalette) -> Self {
         Self {
             palette_buffer: [0; WIDTH * HEIGHT],
-            palette,
+            palette: emphasized_palette(&palette),
         }
     }
 }
@@ -27,7 +28,7 @@ impl Renderer for PixelsRenderer {
         }
     }
 
-    fn modify_buffer<T: FnOnce(&mut [u8; WIDTH * HEIGHT])>(&mut self, f: T) {
+    fn modify_buffer<T: FnOnce(&mut [u16; WIDTH * HEIGHT])>(&mut self, f: T) {
         f(&mut self.palette_buffer);
     }
 }